@@ -0,0 +1,48 @@
+//! Compares the `get` throughput of a fragmented tree before and after `Octree::compact`.
+//!
+//! Run with `cargo bench --bench compact`.
+
+use std::{hint::black_box, time::Instant};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use voxel_octree::{pos, Octree, PosU8};
+
+fn random_pos(rng: &mut StdRng) -> PosU8 {
+    pos!(rng.gen(), rng.gen(), rng.gen())
+}
+
+fn time_gets(octree: &Octree<u16, 128>, queries: &[PosU8]) -> f64 {
+    let start = Instant::now();
+    for _ in 0..10 {
+        for pos in queries {
+            black_box(octree.get(*pos));
+        }
+    }
+    start.elapsed().as_secs_f64()
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    // fragment the slabs with cycles of random inserts and removes:
+    let mut octree = Octree::<u16, 128>::new();
+    for _ in 0..10 {
+        for _ in 0..100_000 {
+            let pos = random_pos(&mut rng);
+            octree.insert(pos, rng.gen_range(0..8));
+        }
+        for _ in 0..50_000 {
+            let pos = random_pos(&mut rng);
+            octree.remove(pos);
+        }
+    }
+    let queries: Vec<PosU8> = (0..1_000_000).map(|_| random_pos(&mut rng)).collect();
+
+    let fragmented = time_gets(&octree, &queries);
+    octree.compact();
+    let compacted = time_gets(&octree, &queries);
+
+    println!("get on fragmented tree: {fragmented:.3}s");
+    println!("get on compacted tree:  {compacted:.3}s");
+    println!("speedup: {:.2}x", fragmented / compacted);
+}
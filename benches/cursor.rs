@@ -0,0 +1,56 @@
+//! Compares scanning a tree line by line with `Octree::get`/`Octree::insert` and with an `OctreeCursor`.
+//!
+//! Run with `cargo bench --bench cursor`.
+
+use std::{hint::black_box, time::Instant};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use voxel_octree::{pos, Octree, PosU8};
+
+fn scan(mut f: impl FnMut(PosU8)) {
+    for z in 0..=255 {
+        for y in 0..=255 {
+            for x in 0..=255 {
+                f(pos!(x, y, z));
+            }
+        }
+    }
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut octree = Octree::<u16, 128>::new();
+    for _ in 0..1_000_000 {
+        octree.insert(pos!(rng.gen(), rng.gen(), rng.gen()), rng.gen_range(0..8));
+    }
+
+    let start = Instant::now();
+    let mut set = 0u32;
+    scan(|pos| set += octree.get(pos).is_some() as u32);
+    let get_time = start.elapsed().as_secs_f64();
+    black_box(set);
+
+    let mut cursor_octree = octree.clone();
+    let start = Instant::now();
+    let mut cursor = cursor_octree.cursor();
+    let mut cursor_set = 0u32;
+    scan(|pos| cursor_set += cursor.get(pos).is_some() as u32);
+    let cursor_get_time = start.elapsed().as_secs_f64();
+    assert_eq!(set, cursor_set);
+
+    let start = Instant::now();
+    scan(|pos| octree.insert(pos, (pos.x / 16) as u16));
+    let insert_time = start.elapsed().as_secs_f64();
+
+    let start = Instant::now();
+    scan(|pos| cursor.insert(pos, (pos.x / 16) as u16));
+    let cursor_insert_time = start.elapsed().as_secs_f64();
+    assert!(*cursor.octree() == octree);
+
+    println!("scan get:           {get_time:.3}s");
+    println!("scan cursor get:    {cursor_get_time:.3}s");
+    println!("speedup: {:.2}x", get_time / cursor_get_time);
+    println!("scan insert:        {insert_time:.3}s");
+    println!("scan cursor insert: {cursor_insert_time:.3}s");
+    println!("speedup: {:.2}x", insert_time / cursor_insert_time);
+}
@@ -0,0 +1,33 @@
+//! Compares building a tree sequentially with `Octree::build_parallel`.
+//!
+//! Run with `cargo bench --bench parallel`.
+
+use std::{hint::black_box, time::Instant};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use voxel_octree::{pos, Octree, PosU8};
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let voxels: Vec<(PosU8, u16)> = (0..2_000_000)
+        .map(|_| (pos!(rng.gen(), rng.gen(), rng.gen()), rng.gen_range(0..8)))
+        .collect();
+
+    let start = Instant::now();
+    let sequential: Octree<u16, 128> = voxels.iter().cloned().collect();
+    let sequential_time = start.elapsed().as_secs_f64();
+    black_box(&sequential);
+
+    let start = Instant::now();
+    let parallel = Octree::<u16, 128>::build_parallel(&voxels);
+    let parallel_time = start.elapsed().as_secs_f64();
+    black_box(&parallel);
+
+    assert!(parallel == sequential);
+    // on a single core build_parallel falls back to the sequential build.
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    println!("available threads: {threads}");
+    println!("sequential build: {sequential_time:.3}s");
+    println!("parallel build:   {parallel_time:.3}s");
+    println!("speedup: {:.2}x", sequential_time / parallel_time);
+}
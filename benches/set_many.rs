@@ -0,0 +1,35 @@
+//! Compares inserting a batch of edits one by one in random order with `Octree::set_many`.
+//!
+//! Run with `cargo bench --bench set_many`.
+
+use std::{hint::black_box, time::Instant};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use voxel_octree::{pos, Octree, PosU8};
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let edits: Vec<(PosU8, u16)> = (0..2_000_000)
+        .map(|_| (pos!(rng.gen(), rng.gen(), rng.gen()), rng.gen_range(0..8)))
+        .collect();
+
+    let start = Instant::now();
+    let mut individual = Octree::<u16, 128>::new();
+    for (pos, val) in edits.iter() {
+        individual.insert(*pos, *val);
+    }
+    let individual_time = start.elapsed().as_secs_f64();
+    black_box(&individual);
+
+    let mut batch_edits = edits.clone();
+    let start = Instant::now();
+    let mut batch = Octree::<u16, 128>::new();
+    batch.set_many(&mut batch_edits);
+    let batch_time = start.elapsed().as_secs_f64();
+    black_box(&batch);
+
+    assert!(batch == individual);
+    println!("individual inserts: {individual_time:.3}s");
+    println!("set_many:           {batch_time:.3}s");
+    println!("speedup: {:.2}x", individual_time / batch_time);
+}
@@ -0,0 +1,31 @@
+//! Fills a whole tree voxel by voxel, such that almost every insertion has to check whether it completes a node.
+//!
+//! Run with `cargo bench --bench solid_fill`.
+
+use std::{hint::black_box, time::Instant};
+
+use voxel_octree::{Octree, PosU8};
+
+fn main() {
+    // x changes fastest, so nodes are completed one after another like in a scanline fill.
+    let start = Instant::now();
+    let mut scanline = Octree::<u16, 128>::new();
+    for pos in PosU8::iter_box(PosU8::ZERO, PosU8::MAX) {
+        scanline.insert(pos, 1);
+    }
+    let scanline_time = start.elapsed().as_secs_f64();
+    black_box(&scanline);
+    assert_eq!(scanline.node_count(), 1);
+
+    // a checkerboard of two values never completes a node, so every check fails.
+    let start = Instant::now();
+    let mut checkerboard = Octree::<u16, 128>::new();
+    for pos in PosU8::iter_box(PosU8::ZERO, PosU8::MAX) {
+        checkerboard.insert(pos, ((pos.x ^ pos.y ^ pos.z) & 1) as u16);
+    }
+    let checkerboard_time = start.elapsed().as_secs_f64();
+    black_box(&checkerboard);
+
+    println!("solid scanline fill: {scanline_time:.3}s");
+    println!("checkerboard fill:   {checkerboard_time:.3}s");
+}
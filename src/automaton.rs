@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+
+use crate::{Octree, PosU8};
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// number of set face neighbors of `pos` (0 to 6), neighbors outside of the tree count as empty.
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn neighbors_count(&self, pos: PosU8) -> u8 {
+        Self::assert_in_bounds(pos);
+        pos.neighbors_6_bounded(HALF_WIDTH)
+            .filter(|n| self.contains(*n))
+            .count() as u8
+    }
+
+    /// number of set voxels in the 3x3x3 cube around `pos` without `pos` itself (0 to 26),
+    /// neighbors outside of the tree count as empty.
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn neighbors_26_count(&self, pos: PosU8) -> u8 {
+        Self::assert_in_bounds(pos);
+        neighbors_26_bounded(pos, HALF_WIDTH)
+            .filter(|n| self.contains(*n))
+            .count() as u8
+    }
+
+    /// applies one generation of a cellular automaton: every cell is replaced by
+    /// `rule(current value, number of set face neighbors)`, where `None` means empty.
+    ///
+    /// All cells are evaluated on the state before the step, so updates do not see each other.
+    /// Only set voxels and their face neighbors are evaluated, an empty cell without set neighbors stays empty,
+    /// so `rule(None, 0)` must return `None`.
+    pub fn step<F: Fn(Option<V>, u8) -> Option<V>>(&mut self, rule: F) {
+        let mut candidates: HashSet<PosU8> = HashSet::new();
+        self.for_each_voxel(|pos, _| {
+            candidates.insert(pos);
+            candidates.extend(pos.neighbors_6_bounded(HALF_WIDTH));
+        });
+
+        let changes: Vec<(PosU8, Option<V>)> = candidates
+            .into_iter()
+            .filter_map(|pos| {
+                let current = self.get(pos);
+                let next = rule(current.clone(), self.neighbors_count(pos));
+                (next != current).then_some((pos, next))
+            })
+            .collect();
+        for (pos, next) in changes {
+            match next {
+                Some(val) => self.insert(pos, val),
+                None => {
+                    self.remove(pos);
+                }
+            }
+        }
+    }
+}
+
+/// the up to 26 positions of the 3x3x3 cube around `pos` that lie inside of a tree with `half_width`.
+fn neighbors_26_bounded(pos: PosU8, half_width: u8) -> impl Iterator<Item = PosU8> {
+    let side = half_width as i16 * 2;
+    (-1..=1i16)
+        .flat_map(|dx| (-1..=1i16).flat_map(move |dy| (-1..=1i16).map(move |dz| [dx, dy, dz])))
+        .filter(|d| *d != [0, 0, 0])
+        .filter_map(move |[dx, dy, dz]| {
+            let n = [pos.x as i16 + dx, pos.y as i16 + dy, pos.z as i16 + dz];
+            n.iter()
+                .all(|c| (0..side).contains(c))
+                .then(|| PosU8::new(n[0] as u8, n[1] as u8, n[2] as u8))
+        })
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn neighbors_count() {
+        let mut octree = Octree::<u32, 8>::new();
+        let center = pos!(5, 5, 5);
+        octree.insert(center, 1);
+        assert_eq!(octree.neighbors_count(center), 0);
+        octree.insert(pos!(6, 5, 5), 1);
+        octree.insert(pos!(5, 4, 5), 1);
+        octree.insert(pos!(5, 5, 6), 1);
+        // diagonal, only a Moore neighbor:
+        octree.insert(pos!(6, 6, 6), 1);
+        // not a neighbor at all:
+        octree.insert(pos!(7, 5, 5), 1);
+        assert_eq!(octree.neighbors_count(center), 3);
+        assert_eq!(octree.neighbors_26_count(center), 4);
+
+        // empty positions can be counted as well:
+        assert_eq!(octree.neighbors_count(pos!(6, 6, 5)), 2);
+
+        octree.fill_box(pos!(4, 4, 4), pos!(6, 6, 6), 2);
+        assert_eq!(octree.neighbors_count(center), 6);
+        assert_eq!(octree.neighbors_26_count(center), 26);
+    }
+
+    #[test]
+    pub fn neighbors_count_at_edges() {
+        let mut octree = Octree::<u32, 8>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(15, 15, 15), 1);
+        // a corner has 3 face and 7 Moore neighbors inside of the tree:
+        assert_eq!(octree.neighbors_count(pos!(0, 0, 0)), 3);
+        assert_eq!(octree.neighbors_26_count(pos!(0, 0, 0)), 7);
+        assert_eq!(octree.neighbors_count(pos!(15, 15, 15)), 3);
+        assert_eq!(octree.neighbors_26_count(pos!(15, 15, 15)), 7);
+        // on an edge:
+        assert_eq!(octree.neighbors_count(pos!(0, 7, 15)), 4);
+        assert_eq!(octree.neighbors_26_count(pos!(0, 7, 15)), 11);
+        // on a face:
+        assert_eq!(octree.neighbors_count(pos!(8, 0, 8)), 5);
+        assert_eq!(octree.neighbors_26_count(pos!(8, 0, 8)), 17);
+    }
+
+    #[test]
+    pub fn step_decay() {
+        // voxels with less than 2 set face neighbors decay:
+        let decay = |val: Option<u32>, neighbors: u8| val.filter(|_| neighbors >= 2);
+
+        let mut octree = Octree::<u32, 8>::new();
+        octree.fill_box(pos!(2, 3, 3), pos!(6, 3, 3), 1);
+        octree.insert(pos!(10, 10, 10), 2);
+        octree.step(decay);
+        // both ends of the line and the single voxel decay, but the updates don't cascade:
+        let mut voxels: Vec<(PosU8, u32)> = octree.iter().collect();
+        voxels.sort();
+        assert_eq!(
+            voxels,
+            vec![(pos!(3, 3, 3), 1), (pos!(4, 3, 3), 1), (pos!(5, 3, 3), 1)]
+        );
+        octree.step(decay);
+        assert_eq!(octree.iter().collect::<Vec<_>>(), vec![(pos!(4, 3, 3), 1)]);
+        octree.step(decay);
+        assert!(octree.is_empty());
+    }
+
+    #[test]
+    pub fn step_growth() {
+        // empty cells with at least 2 set face neighbors are filled:
+        let grow = |val: Option<u32>, neighbors: u8| val.or((neighbors >= 2).then_some(7));
+
+        let mut octree = Octree::<u32, 8>::new();
+        octree.insert(pos!(4, 4, 4), 1);
+        octree.insert(pos!(5, 5, 4), 1);
+        octree.step(grow);
+        assert_eq!(octree.len(), 4);
+        assert_eq!(octree.get(pos!(5, 4, 4)), Some(7));
+        assert_eq!(octree.get(pos!(4, 5, 4)), Some(7));
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{Node, Octree, PosU8, EMPTY_PTR};
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// inserts all `edits`, like calling [`Octree::insert`] for each of them in order.
+    ///
+    /// The edits are sorted by their Morton code first, such that the edits of each octant are next to each other.
+    /// Then the tree is descended only once per octant instead of once per edit, and every touched node is merged
+    /// a single time on the way back up.
+    ///
+    /// panics if any position is out of bounds.
+    pub fn set_many(&mut self, edits: &mut [(PosU8, V)]) {
+        for (pos, _) in edits.iter() {
+            Self::assert_in_bounds(*pos);
+        }
+        for (pos, val) in edits.iter() {
+            self.mark_dirty_if_changed(*pos, val);
+        }
+        // stable sort, so of multiple edits at the same position the last one still wins.
+        edits.sort_by_key(|(pos, _)| pos.to_morton());
+        if !edits.is_empty() {
+            self.set_many_in_node(0, HALF_WIDTH, edits);
+        }
+    }
+
+    /// applies the non-empty, Morton sorted `edits` that all lie inside the node at `node_ptr` with `half_width`.
+    fn set_many_in_node(&mut self, node_ptr: usize, half_width: u8, edits: &[(PosU8, V)]) {
+        if let Node::Full(ref full_val) = self.nodes[node_ptr] {
+            if edits.iter().all(|(_, val)| val == full_val) {
+                return;
+            }
+            let full_val = self.take_full_value(node_ptr);
+            let ptrs = std::array::from_fn(|_| {
+                self.insert_uniform_child(full_val.clone(), half_width / 2)
+            });
+            self.nodes[node_ptr] = Node::from_ptrs(ptrs);
+        }
+        let Node::Mixed(mut ptrs, _) = self.nodes[node_ptr] else {
+            unreachable!("Full nodes were split above");
+        };
+
+        let oct_idx = |pos: PosU8| Self::absolute_oct_index(pos, half_width);
+        for run in edits.chunk_by(|(a, _), (b, _)| oct_idx(*a) == oct_idx(*b)) {
+            let idx = oct_idx(run[0].0);
+            if half_width == 1 {
+                // points to leaf, only the last edit of the run is visible.
+                let val = run[run.len() - 1].1.clone();
+                if ptrs[idx] == EMPTY_PTR {
+                    ptrs[idx] = self.leafs.insert(val);
+                } else {
+                    self.leafs[ptrs[idx]] = val;
+                }
+            } else {
+                if ptrs[idx] == EMPTY_PTR {
+                    ptrs[idx] = self.nodes.insert(Node::empty());
+                }
+                self.set_many_in_node(ptrs[idx], half_width / 2, run);
+            }
+        }
+        self.nodes[node_ptr] = Node::from_ptrs(ptrs);
+        self.merge_path(vec![(node_ptr, half_width)]);
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn set_many_matches_insert() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 16>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(15, 15, 15), 1);
+        let mut expected = octree.clone();
+
+        let mut edits: Vec<(PosU8, u32)> = (0..3000)
+            .map(|_| {
+                let pos = pos!(
+                    rng.gen_range(0..32),
+                    rng.gen_range(0..32),
+                    rng.gen_range(0..32)
+                );
+                (pos, rng.gen_range(0..3))
+            })
+            .collect();
+        // duplicate positions, the later edit has to win:
+        edits.push((pos!(1, 2, 3), 7));
+        edits.push((pos!(1, 2, 3), 8));
+        // enough to merge into a Full node:
+        for x in 16..20 {
+            for y in 16..20 {
+                for z in 16..20 {
+                    edits.push((pos!(x, y, z), 9));
+                }
+            }
+        }
+        for (pos, val) in edits.iter() {
+            expected.insert(*pos, *val);
+        }
+
+        octree.set_many(&mut edits);
+        assert_eq!(octree.get(pos!(1, 2, 3)), Some(8));
+        assert_eq!(octree.encode(), expected.encode());
+        assert_eq!(octree.node_count(), expected.node_count());
+        assert_eq!(octree.leaf_count(), expected.leaf_count());
+
+        // a batch that fills the whole tree collapses to a single Full root:
+        let mut edits: Vec<(PosU8, u32)> = (0..8 * 8 * 8u16)
+            .map(|i| (pos!((i % 8) as u8, (i / 8 % 8) as u8, (i / 64) as u8), 5))
+            .collect();
+        let mut small = Octree::<u32, 4>::new();
+        small.set_many(&mut edits);
+        assert_eq!(small.encode(), Octree::<u32, 4>::filled(5).encode());
+        assert_eq!(small.node_count(), 1);
+    }
+
+    #[test]
+    pub fn set_many_empty_batch() {
+        let mut octree = Octree::<u32, 4>::new();
+        octree.set_many(&mut []);
+        assert!(octree.is_empty());
+        assert_eq!(octree.node_count(), 1);
+    }
+}
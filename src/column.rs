@@ -0,0 +1,159 @@
+use crate::{oct_offset, Node, Octree, PosU8, EMPTY_PTR};
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// the y coordinate of the topmost set voxel in the column at `x`, `z`, like the height of a heightmap.
+    ///
+    /// panics if `x` or `z` is out of bounds.
+    pub fn highest_set_y(&self, x: u8, z: u8) -> Option<u8> {
+        self.last_set_along_axis(PosU8::new(x, 0, z), 1)
+    }
+
+    /// the coordinate along `axis` (0 = x, 1 = y, 2 = z) of the first set voxel in the line through `pos` parallel
+    /// to `axis`, searching from 0 upwards. The coordinate of `pos` along `axis` is ignored.
+    ///
+    /// Empty children are skipped as a whole and a Full node ends the search, so this is much cheaper than
+    /// checking every voxel of the line.
+    ///
+    /// panics if `pos` is out of bounds or `axis` is not 0, 1 or 2.
+    pub fn first_set_along_axis(&self, pos: PosU8, axis: usize) -> Option<u8> {
+        self.set_along_axis(pos, axis, false)
+    }
+
+    /// like [`Octree::first_set_along_axis`], but searching from the highest coordinate downwards.
+    pub fn last_set_along_axis(&self, pos: PosU8, axis: usize) -> Option<u8> {
+        self.set_along_axis(pos, axis, true)
+    }
+
+    fn set_along_axis(&self, mut pos: PosU8, axis: usize, from_top: bool) -> Option<u8> {
+        assert!(axis < 3, "axis {axis} is not 0, 1 or 2");
+        // the coordinate along the axis does not matter, but has to pass the bounds check.
+        match axis {
+            0 => pos.x = 0,
+            1 => pos.y = 0,
+            _ => pos.z = 0,
+        }
+        Self::assert_in_bounds(pos);
+        self.set_along_axis_in_node(0, PosU8::ZERO, HALF_WIDTH, pos, axis, from_top)
+    }
+
+    fn set_along_axis_in_node(
+        &self,
+        node_ptr: usize,
+        origin: PosU8,
+        half_width: u8,
+        pos: PosU8,
+        axis: usize,
+        from_top: bool,
+    ) -> Option<u8> {
+        match self.nodes[node_ptr] {
+            Node::Full(_) => {
+                let start = component(origin, axis);
+                Some(if from_top {
+                    // 2 * half_width - 1 without overflowing for a half width of 128.
+                    start + (half_width - 1) + half_width
+                } else {
+                    start
+                })
+            }
+            Node::Mixed(ptrs, _) => {
+                // the line passes through two octants, which only differ in the bit of the axis.
+                let axis_bit = 4 >> axis;
+                let lower_idx = Self::absolute_oct_index(pos, half_width) & !axis_bit;
+                let order = if from_top {
+                    [lower_idx | axis_bit, lower_idx]
+                } else {
+                    [lower_idx, lower_idx | axis_bit]
+                };
+                order.into_iter().find_map(|idx| {
+                    let ptr = ptrs[idx];
+                    if ptr == EMPTY_PTR {
+                        return None;
+                    }
+                    let child_origin = origin + oct_offset(idx, half_width);
+                    if half_width == 1 {
+                        // points to leaf
+                        Some(component(child_origin, axis))
+                    } else {
+                        self.set_along_axis_in_node(
+                            ptr,
+                            child_origin,
+                            half_width / 2,
+                            pos,
+                            axis,
+                            from_top,
+                        )
+                    }
+                })
+            }
+        }
+    }
+}
+
+/// the coordinate of `pos` along `axis`.
+fn component(pos: PosU8, axis: usize) -> u8 {
+    match axis {
+        0 => pos.x,
+        1 => pos.y,
+        _ => pos.z,
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn highest_set_y_terrain() {
+        let mut octree = Octree::<u8, 16>::new();
+        // bedrock and a hill:
+        octree.fill_box(pos!(0, 0, 0), pos!(31, 7, 15), 1);
+        for x in 0..8u8 {
+            for y in 8..8 + x {
+                octree.insert(pos!(x, y, 3), 2);
+            }
+        }
+        // a floating block above a hole in the bedrock:
+        for y in 0..8 {
+            octree.remove(pos!(20, y, 10));
+        }
+        octree.insert(pos!(20, 30, 10), 3);
+
+        assert_eq!(octree.highest_set_y(0, 0), Some(7));
+        assert_eq!(octree.highest_set_y(0, 3), Some(7));
+        assert_eq!(octree.highest_set_y(5, 3), Some(12));
+        assert_eq!(octree.highest_set_y(7, 3), Some(14));
+        assert_eq!(octree.highest_set_y(20, 10), Some(30));
+        assert_eq!(octree.highest_set_y(5, 20), None);
+
+        for x in 0..32 {
+            for z in 0..32 {
+                let expected = (0..32).rev().find(|y| octree.contains(pos!(x, *y, z)));
+                assert_eq!(octree.highest_set_y(x, z), expected);
+            }
+        }
+    }
+
+    #[test]
+    pub fn set_along_axis() {
+        let mut octree = Octree::<u8, 8>::new();
+        octree.insert(pos!(3, 4, 5), 1);
+        octree.insert(pos!(12, 4, 5), 1);
+        octree.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 2);
+
+        assert_eq!(octree.first_set_along_axis(pos!(0, 4, 5), 0), Some(3));
+        assert_eq!(octree.last_set_along_axis(pos!(0, 4, 5), 0), Some(12));
+        // the coordinate along the axis is ignored:
+        assert_eq!(octree.first_set_along_axis(pos!(15, 4, 5), 0), Some(3));
+        assert_eq!(octree.first_set_along_axis(pos!(3, 4, 0), 2), Some(5));
+        assert_eq!(octree.first_set_along_axis(pos!(9, 10, 0), 2), Some(8));
+        assert_eq!(octree.last_set_along_axis(pos!(9, 10, 0), 2), Some(15));
+        assert_eq!(octree.first_set_along_axis(pos!(9, 0, 3), 1), None);
+
+        let filled = Octree::<u8, 128>::filled(1);
+        assert_eq!(filled.highest_set_y(255, 255), Some(255));
+        assert_eq!(filled.first_set_along_axis(pos!(0, 0, 0), 1), Some(0));
+    }
+}
@@ -0,0 +1,477 @@
+use std::hash::{Hash, Hasher};
+
+use crate::{oct_offset, Node, Octree, PosU8, Ptr, EMPTY_PTR};
+
+/// The content of a child pointer, independent of whether it points to a leaf or a node.
+/// Used to walk two trees in lockstep, even if their node structure differs.
+pub(crate) enum Slot<'a, V> {
+    Empty,
+    /// a leaf or a Full node.
+    Value(&'a V),
+    Mixed([Ptr; 8]),
+}
+
+impl<'a, V> Clone for Slot<'a, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, V> Copy for Slot<'a, V> {}
+
+impl<'a, V> Slot<'a, V> {
+    /// `Some(None)` for Empty, `Some(Some(val))` for Value and `None` for Mixed slots.
+    pub(crate) fn uniform(self) -> Option<Option<&'a V>> {
+        match self {
+            Slot::Empty => Some(None),
+            Slot::Value(val) => Some(Some(val)),
+            Slot::Mixed(_) => None,
+        }
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    pub(crate) fn root_slot(&self) -> Slot<'_, V> {
+        match self.nodes[0] {
+            Node::Full(ref val) => Slot::Value(val),
+            Node::Mixed(ptrs, _) => Slot::Mixed(ptrs),
+        }
+    }
+
+    /// slot of the child at `ptr` of a node with `node_half_width`.
+    pub(crate) fn child_slot(&self, ptr: Ptr, node_half_width: u8) -> Slot<'_, V> {
+        if ptr == EMPTY_PTR {
+            Slot::Empty
+        } else if node_half_width == 1 {
+            Slot::Value(&self.leafs[ptr])
+        } else {
+            match self.nodes[ptr] {
+                Node::Full(ref val) => Slot::Value(val),
+                Node::Mixed(ptrs, _) => Slot::Mixed(ptrs),
+            }
+        }
+    }
+
+    /// the 8 children of a slot of a node with `half_width`.
+    /// Empty and Value slots are split into 8 equal children.
+    pub(crate) fn child_slots<'a>(&'a self, slot: Slot<'a, V>, half_width: u8) -> [Slot<'a, V>; 8] {
+        match slot {
+            Slot::Empty => [Slot::Empty; 8],
+            Slot::Value(val) => [Slot::Value(val); 8],
+            Slot::Mixed(ptrs) => ptrs.map(|ptr| self.child_slot(ptr, half_width)),
+        }
+    }
+
+    fn slots_eq(&self, a: Slot<'_, V>, other: &Self, b: Slot<'_, V>, half_width: u8) -> bool {
+        match (a, b) {
+            (Slot::Empty, Slot::Empty) => true,
+            (Slot::Value(a), Slot::Value(b)) => a == b,
+            (Slot::Empty, Slot::Value(_)) | (Slot::Value(_), Slot::Empty) => false,
+            // at least one side is Mixed, compare the children:
+            _ => {
+                let a_children = self.child_slots(a, half_width);
+                let b_children = other.child_slots(b, half_width);
+                a_children
+                    .into_iter()
+                    .zip(b_children)
+                    .all(|(a, b)| self.slots_eq(a, other, b, half_width / 2))
+            }
+        }
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// overlays all set voxels of `other` onto this tree. Where both trees have a value, `conflict(self_val, other_val)`
+    /// decides which value is stored.
+    ///
+    /// Both trees are walked in lockstep, regions where `other` is empty are skipped without descending into them,
+    /// and `conflict` is called once per pair of overlapping uniform regions rather than once per voxel.
+    pub fn merge(&mut self, other: &Octree<V, HALF_WIDTH>, conflict: impl Fn(V, V) -> V) {
+        // (min, max, val) of boxes to fill, collected first because the slots borrow self.
+        let mut writes: Vec<(PosU8, PosU8, V)> = vec![];
+        self.collect_merge_writes(
+            self.root_slot(),
+            other,
+            other.root_slot(),
+            PosU8::ZERO,
+            Self::SIDE,
+            &conflict,
+            &mut writes,
+        );
+        for (min, max, val) in writes {
+            self.fill_box(min, max, val);
+        }
+    }
+
+    /// every position where the two trees disagree, with the value of this tree and of `other` there
+    /// (`None` for an empty voxel). Positions are sorted by their octant, not by their coordinates.
+    ///
+    /// Both trees are walked in lockstep, uniform regions that are equal in both trees are skipped as a whole,
+    /// so the cost grows with the size of the difference instead of the volume of the trees.
+    pub fn diff(&self, other: &Octree<V, HALF_WIDTH>) -> Vec<(PosU8, Option<V>, Option<V>)> {
+        let mut changes = vec![];
+        self.collect_diff(
+            self.root_slot(),
+            other,
+            other.root_slot(),
+            PosU8::ZERO,
+            Self::SIDE,
+            &mut changes,
+        );
+        changes
+    }
+
+    fn collect_diff(
+        &self,
+        a: Slot<'_, V>,
+        other: &Self,
+        b: Slot<'_, V>,
+        origin: PosU8,
+        width: u16,
+        changes: &mut Vec<(PosU8, Option<V>, Option<V>)>,
+    ) {
+        match (a.uniform(), b.uniform()) {
+            (Some(a), Some(b)) => {
+                if a == b {
+                    return;
+                }
+                for z in 0..width {
+                    for y in 0..width {
+                        for x in 0..width {
+                            let pos = origin + PosU8::new(x as u8, y as u8, z as u8);
+                            changes.push((pos, a.cloned(), b.cloned()));
+                        }
+                    }
+                }
+            }
+            // at least one side is Mixed, compare the children:
+            _ => {
+                let half_width = (width / 2) as u8;
+                let a_children = self.child_slots(a, half_width);
+                let b_children = other.child_slots(b, half_width);
+                for (i, (a, b)) in a_children.into_iter().zip(b_children).enumerate() {
+                    let child_origin = origin + oct_offset(i, half_width);
+                    self.collect_diff(a, other, b, child_origin, half_width as u16, changes);
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn collect_merge_writes(
+        &self,
+        a: Slot<'_, V>,
+        other: &Self,
+        b: Slot<'_, V>,
+        origin: PosU8,
+        width: u16,
+        conflict: &impl Fn(V, V) -> V,
+        writes: &mut Vec<(PosU8, PosU8, V)>,
+    ) {
+        let max = origin + PosU8::from([(width - 1) as u8; 3]);
+        match (a, b) {
+            (_, Slot::Empty) => {}
+            (Slot::Empty, Slot::Value(b)) => writes.push((origin, max, b.clone())),
+            (Slot::Value(a), Slot::Value(b)) => {
+                writes.push((origin, max, conflict(a.clone(), b.clone())))
+            }
+            // at least one side is Mixed, look at the children:
+            _ => {
+                let half_width = (width / 2) as u8;
+                let a_children = self.child_slots(a, half_width);
+                let b_children = other.child_slots(b, half_width);
+                for (i, (a, b)) in a_children.into_iter().zip(b_children).enumerate() {
+                    let child_origin = origin + oct_offset(i, half_width);
+                    self.collect_merge_writes(
+                        a,
+                        other,
+                        b,
+                        child_origin,
+                        half_width as u16,
+                        conflict,
+                        writes,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// two trees are equal if they store the same values at the same positions,
+/// no matter how their nodes are laid out.
+impl<V, const HALF_WIDTH: u8> PartialEq for Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.slots_eq(self.root_slot(), other, other.root_slot(), HALF_WIDTH)
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> Eq for Octree<V, HALF_WIDTH> where V: Clone + Eq {}
+
+/// hashes the values at their positions like [`PartialEq`] compares them, so equal trees hash equally,
+/// no matter how their nodes are laid out.
+impl<V, const HALF_WIDTH: u8> Hash for Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq + Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash_slot(self.root_slot(), HALF_WIDTH, state);
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq + Hash,
+{
+    /// hashes a uniform slot as its value, even if it is an unmerged Mixed node, and other slots as their
+    /// 8 children in octant order.
+    fn hash_slot<H: Hasher>(&self, slot: Slot<'_, V>, half_width: u8, state: &mut H) {
+        match self.uniform_slot_value(slot, half_width) {
+            Some(val) => {
+                state.write_u8(0);
+                val.hash(state);
+            }
+            None => {
+                state.write_u8(1);
+                for child in self.child_slots(slot, half_width) {
+                    self.hash_slot(child, half_width / 2, state);
+                }
+            }
+        }
+    }
+
+    /// like [`Slot::uniform`], but also finds the value of Mixed slots whose voxels are all the same.
+    fn uniform_slot_value<'a>(
+        &'a self,
+        slot: Slot<'a, V>,
+        half_width: u8,
+    ) -> Option<Option<&'a V>> {
+        if let Some(val) = slot.uniform() {
+            return Some(val);
+        }
+        let mut children = self.child_slots(slot, half_width).into_iter();
+        let first = self.uniform_slot_value(children.next()?, half_width / 2)?;
+        for child in children {
+            if self.uniform_slot_value(child, half_width / 2)? != first {
+                return None;
+            }
+        }
+        Some(first)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+
+    use rand::{seq::SliceRandom, thread_rng, Rng};
+
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn eq_independent_of_insertion_order() {
+        let mut rng = thread_rng();
+        let mut voxels: Vec<(PosU8, u32)> = (0..2000)
+            .map(|_| {
+                let pos = pos!(
+                    rng.gen_range(0..32),
+                    rng.gen_range(0..32),
+                    rng.gen_range(0..32)
+                );
+                (pos, (pos.x as u32 + pos.y as u32) % 3)
+            })
+            .collect();
+        let a: Octree<u32, 16> = voxels.iter().cloned().collect();
+        voxels.shuffle(&mut rng);
+        let mut b: Octree<u32, 16> = Octree::new();
+        // some detours that should not matter:
+        b.fill_box(pos!(0, 0, 0), pos!(31, 31, 31), 5);
+        b.clear();
+        b.insert(pos!(3, 3, 3), 1);
+        b.remove(pos!(3, 3, 3));
+        b.extend(voxels.iter().cloned());
+        assert!(a == b);
+
+        b.insert(pos!(31, 31, 31), 7);
+        assert!(a != b);
+        assert!(Octree::<u32, 16>::new() == Octree::<u32, 16>::new());
+        assert!(a != Octree::<u32, 16>::new());
+    }
+
+    #[test]
+    pub fn eq_with_unmerged_nodes() {
+        let mut a = Octree::<u32, 16>::new();
+        a.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), 1);
+
+        // the same voxels, but as individual leafs, because get_mut does not merge:
+        let mut b = a.clone();
+        *b.get_mut(pos!(3, 3, 3)).unwrap() = 1;
+        assert_ne!(a.leaf_count(), b.leaf_count());
+        assert!(a == b);
+
+        *b.get_mut(pos!(3, 3, 3)).unwrap() = 2;
+        assert!(a != b);
+    }
+
+    fn hash_of(octree: &Octree<u32, 16>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        octree.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    pub fn hash_independent_of_insertion_order() {
+        let mut rng = thread_rng();
+        let mut voxels: Vec<(PosU8, u32)> = (0..2000)
+            .map(|_| {
+                let pos = pos!(
+                    rng.gen_range(0..32),
+                    rng.gen_range(0..32),
+                    rng.gen_range(0..32)
+                );
+                (pos, (pos.x as u32 + pos.y as u32) % 3)
+            })
+            .collect();
+        let mut a: Octree<u32, 16> = voxels.iter().cloned().collect();
+        a.fill_box(pos!(16, 16, 16), pos!(31, 31, 31), 4);
+        voxels.shuffle(&mut rng);
+        let mut b: Octree<u32, 16> = Octree::new();
+        b.fill_box(pos!(16, 16, 16), pos!(31, 31, 31), 4);
+        b.extend(voxels.iter().cloned());
+        b.fill_box(pos!(16, 16, 16), pos!(31, 31, 31), 4);
+        assert!(a == b);
+        assert_eq!(hash_of(&a), hash_of(&b));
+
+        // unmerged nodes hash like the merged ones:
+        *b.get_mut(pos!(20, 20, 20)).unwrap() = 4;
+        assert_ne!(a.node_count(), b.node_count());
+        assert_eq!(hash_of(&a), hash_of(&b));
+
+        b.insert(pos!(20, 20, 20), 5);
+        assert_ne!(hash_of(&a), hash_of(&b));
+        assert_ne!(hash_of(&Octree::new()), hash_of(&Octree::filled(0)));
+    }
+
+    #[test]
+    pub fn merge_disjoint_is_union() {
+        let mut a = Octree::<u32, 8>::new();
+        a.insert(pos!(1, 2, 3), 1);
+        a.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 2);
+        let mut b = Octree::<u32, 8>::new();
+        b.insert(pos!(4, 5, 6), 3);
+        b.fill_box(pos!(0, 8, 0), pos!(7, 15, 7), 4);
+
+        let mut expected = a.clone();
+        expected.extend(b.iter());
+        a.merge(&b, |_, _| panic!("trees are disjoint"));
+        assert!(a == expected);
+        assert_eq!(a.encode(), expected.encode());
+    }
+
+    #[test]
+    pub fn merge_overlapping_applies_conflict() {
+        let mut rng = thread_rng();
+        let mut a = Octree::<u32, 8>::new();
+        let mut b = Octree::<u32, 8>::new();
+        for _ in 0..500 {
+            a.insert(
+                pos!(
+                    rng.gen_range(0..16),
+                    rng.gen_range(0..16),
+                    rng.gen_range(0..16)
+                ),
+                rng.gen_range(1..4),
+            );
+            b.insert(
+                pos!(
+                    rng.gen_range(0..16),
+                    rng.gen_range(0..16),
+                    rng.gen_range(0..16)
+                ),
+                rng.gen_range(1..4),
+            );
+        }
+        b.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), 10);
+
+        let mut merged = a.clone();
+        merged.merge(&b, |x, y| x * 100 + y);
+        for x in 0..16 {
+            for y in 0..16 {
+                for z in 0..16 {
+                    let pos = pos!(x, y, z);
+                    let expected = match (a.get(pos), b.get(pos)) {
+                        (Some(x), Some(y)) => Some(x * 100 + y),
+                        (x, y) => y.or(x),
+                    };
+                    assert_eq!(merged.get(pos), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn merge_empty_is_noop() {
+        let mut a = Octree::<u32, 8>::new();
+        a.insert(pos!(1, 2, 3), 1);
+        a.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 2);
+        let before = a.clone();
+        a.merge(&Octree::new(), |_, _| panic!("other is empty"));
+        assert!(a == before);
+        assert_eq!(a.encode(), before.encode());
+    }
+
+    #[test]
+    pub fn diff_single_voxel() {
+        let mut a = Octree::<u32, 16>::new();
+        a.fill_box(pos!(0, 0, 0), pos!(15, 15, 15), 1);
+        a.insert(pos!(20, 20, 20), 2);
+        let mut b = a.clone();
+        assert_eq!(a.diff(&b), vec![]);
+
+        b.insert(pos!(3, 4, 5), 9);
+        assert_eq!(a.diff(&b), vec![(pos!(3, 4, 5), Some(1), Some(9))]);
+        b.remove(pos!(20, 20, 20));
+        b.insert(pos!(31, 0, 0), 3);
+        let mut changes = a.diff(&b);
+        changes.sort_by_key(|c| c.0);
+        assert_eq!(
+            changes,
+            vec![
+                (pos!(3, 4, 5), Some(1), Some(9)),
+                (pos!(20, 20, 20), Some(2), None),
+                (pos!(31, 0, 0), None, Some(3)),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn diff_region() {
+        let mut a = Octree::<u32, 16>::new();
+        a.fill_box(pos!(0, 0, 0), pos!(15, 15, 15), 1);
+        let mut b = a.clone();
+        b.clear_box(pos!(4, 4, 4), pos!(7, 7, 7));
+        b.fill_box(pos!(16, 0, 0), pos!(17, 1, 1), 5);
+
+        let changes = a.diff(&b);
+        assert_eq!(changes.len(), 64 + 8);
+        for (pos, x, y) in changes.iter() {
+            assert_eq!(*x, a.get(*pos));
+            assert_eq!(*y, b.get(*pos));
+            assert_ne!(x, y);
+        }
+        // the reverse diff swaps the sides:
+        let reverse = b.diff(&a);
+        assert!(reverse
+            .iter()
+            .zip(changes.iter())
+            .all(|(r, c)| r.0 == c.0 && r.1 == c.2 && r.2 == c.1));
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{Node, Octree, PosU8, EMPTY_PTR};
+
+/// Caches the path to the last accessed voxel of an [`Octree`], see [`Octree::cursor`].
+///
+/// Consecutive accesses to nearby voxels share most of their path from the root, so instead of descending from
+/// the root every time, the cursor continues from the deepest cached node that contains the next position.
+/// All edits have to go through the cursor while it exists, so the cache can not get out of date.
+pub struct OctreeCursor<'a, V, const HALF_WIDTH: u8> {
+    octree: &'a mut Octree<V, HALF_WIDTH>,
+    /// `(node_ptr, origin, half_width)` of the Mixed nodes on the path from the root to the last accessed voxel.
+    path: Vec<(usize, PosU8, u8)>,
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// a cursor for spatially local accesses, like scanning the tree line by line.
+    pub fn cursor(&mut self) -> OctreeCursor<'_, V, HALF_WIDTH> {
+        OctreeCursor {
+            octree: self,
+            path: vec![],
+        }
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> OctreeCursor<'_, V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// the tree of the cursor, for reads that don't go through the cache.
+    pub fn octree(&self) -> &Octree<V, HALF_WIDTH> {
+        self.octree
+    }
+
+    /// drops the cached nodes that do not contain `pos`.
+    /// Returns `(node_ptr, pos relative to the node, half_width)` of the node to continue from.
+    fn deepest_cached_ancestor(&mut self, pos: PosU8) -> (usize, PosU8, u8) {
+        while let Some((node_ptr, origin, half_width)) = self.path.last().copied() {
+            let width = half_width as u16 * 2;
+            let inside = [(pos.x, origin.x), (pos.y, origin.y), (pos.z, origin.z)]
+                .iter()
+                .all(|(p, o)| *p >= *o && ((*p - *o) as u16) < width);
+            if inside {
+                return (node_ptr, pos - origin, half_width);
+            }
+            self.path.pop();
+        }
+        (0, pos, HALF_WIDTH)
+    }
+
+    /// like [`Octree::get_ref`], continuing from the deepest cached node containing `pos`.
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn get_ref(&mut self, pos: PosU8) -> Option<&V> {
+        Octree::<V, HALF_WIDTH>::assert_in_bounds(pos);
+        let (mut node_ptr, mut local, mut half_width) = self.deepest_cached_ancestor(pos);
+        loop {
+            let ptrs = match self.octree.nodes[node_ptr] {
+                Node::Full(ref val) => return Some(val),
+                Node::Mixed(ptrs, _) => ptrs,
+            };
+            if self.path.last().map(|(ptr, ..)| *ptr) != Some(node_ptr) {
+                self.path.push((node_ptr, pos - local, half_width));
+            }
+            let ptr = ptrs[Octree::<V, HALF_WIDTH>::oct_index(&mut local, half_width)];
+            if ptr == EMPTY_PTR {
+                return None;
+            } else if half_width == 1 {
+                // points to leaf
+                return Some(&self.octree.leafs[ptr]);
+            }
+            node_ptr = ptr;
+            half_width /= 2;
+        }
+    }
+
+    /// like [`Octree::get`], continuing from the deepest cached node containing `pos`.
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn get(&mut self, pos: PosU8) -> Option<V> {
+        self.get_ref(pos).cloned()
+    }
+
+    /// like [`Octree::insert`], continuing from the deepest cached node containing `pos`.
+    ///
+    /// The cached nodes above the insertion stay valid unless the insertion merged them into Full nodes.
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn insert(&mut self, pos: PosU8, val: V) {
+        Octree::<V, HALF_WIDTH>::assert_in_bounds(pos);
+        self.octree.mark_dirty_if_changed(pos, &val);
+        let (node_ptr, local, half_width) = self.deepest_cached_ancestor(pos);
+        self.octree.insert_below(node_ptr, half_width, local, val);
+        // the insertion can make the nodes above uniform, like Octree::insert merges them on its way down.
+        if self.path.len() > 1 {
+            let above = self.path[..self.path.len() - 1]
+                .iter()
+                .map(|(ptr, _, half_width)| (*ptr, *half_width))
+                .collect();
+            self.octree.merge_path(above);
+        }
+        // merged nodes are Full now and their Mixed children are deleted, so cut the path at the first Full node.
+        let still_mixed = self
+            .path
+            .iter()
+            .take_while(|(ptr, ..)| matches!(self.octree.nodes[*ptr], Node::Mixed(..)))
+            .count();
+        self.path.truncate(still_mixed);
+        // extend the path down to pos again, for the next access nearby.
+        self.get_ref(pos);
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn cursor_get_matches_get() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 16>::new();
+        for _ in 0..2000 {
+            let pos = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            octree.insert(pos, rng.gen_range(0..3));
+        }
+        octree.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), 5);
+        let expected = octree.clone();
+
+        let mut cursor = octree.cursor();
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    let pos = pos!(x, y, z);
+                    assert_eq!(cursor.get(pos), expected.get(pos));
+                }
+            }
+        }
+        // random jumps:
+        for _ in 0..2000 {
+            let pos = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            assert_eq!(cursor.get(pos), expected.get(pos));
+        }
+    }
+
+    #[test]
+    pub fn cursor_insert_matches_insert() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 8>::new();
+        let mut expected = Octree::<u32, 8>::new();
+        let mut cursor = octree.cursor();
+        for _ in 0..5000 {
+            let pos = pos!(
+                rng.gen_range(0..16),
+                rng.gen_range(0..16),
+                rng.gen_range(0..16)
+            );
+            // few values, such that nodes are merged and split all the time:
+            let val = rng.gen_range(0..2);
+            if rng.gen_bool(0.5) {
+                assert_eq!(cursor.get(pos), expected.get(pos));
+            }
+            cursor.insert(pos, val);
+            expected.insert(pos, val);
+        }
+        // filling everything merges up to the root through the cached path:
+        for z in 0..16 {
+            for y in 0..16 {
+                for x in 0..16 {
+                    cursor.insert(pos!(x, y, z), 1);
+                    expected.insert(pos!(x, y, z), 1);
+                }
+            }
+        }
+        assert_eq!(cursor.octree().encode(), expected.encode());
+        assert_eq!(octree.node_count(), 1);
+        assert_eq!(octree.leaf_count(), 0);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{oct_offset, Node, Octree, PosU8, EMPTY_PTR};
+
+/// Error returned by [`Octree::from_dense`] and [`Octree::from_rle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DenseError {
+    /// the grid or the runs do not have exactly `(2 * HALF_WIDTH)³` cells.
+    WrongLength { expected: usize, found: usize },
+}
+
+impl std::fmt::Display for DenseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DenseError::WrongLength { expected, found } => {
+                write!(f, "dense grid has {found} cells, expected {expected}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DenseError {}
+
+/// a cube of the dense grid after it was built, before it is stored in the tree.
+pub(crate) enum Built<V> {
+    Empty,
+    /// every voxel of the cube has this value, no nodes were allocated yet.
+    Uniform(V),
+    /// a Mixed node that is already stored in the tree.
+    Node(usize),
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// index of `pos` in a dense grid, x changes fastest.
+    fn dense_index(pos: PosU8) -> usize {
+        let side = Self::SIDE as usize;
+        pos.x as usize + pos.y as usize * side + pos.z as usize * side * side
+    }
+
+    /// all voxels as a flat grid of length `(2 * HALF_WIDTH)³`, x changes fastest, then y, then z.
+    pub fn to_dense(&self) -> Vec<Option<V>> {
+        let side = Self::SIDE as usize;
+        let mut dense = vec![None; side * side * side];
+        self.for_each_voxel(|pos, val| dense[Self::dense_index(pos)] = Some(val.clone()));
+        dense
+    }
+
+    /// builds a tree from a flat grid in the layout of [`Octree::to_dense`].
+    ///
+    /// The tree is built bottom-up and uniform cubes are merged before any node is allocated for them,
+    /// so this is much cheaper than inserting the voxels one by one.
+    pub fn from_dense(dense: &[Option<V>]) -> Result<Self, DenseError> {
+        let side = Self::SIDE as usize;
+        let expected = side * side * side;
+        if dense.len() != expected {
+            return Err(DenseError::WrongLength {
+                expected,
+                found: dense.len(),
+            });
+        }
+        Ok(Self::from_fn(|pos| dense[Self::dense_index(pos)].clone()))
+    }
+
+    /// builds a tree by evaluating `f` at every position, `None` leaves the voxel empty.
+    /// E.g. for voxelizing a signed distance function or a noise field.
+    ///
+    /// `f` is called in octant order. The tree is built bottom-up and uniform cubes are merged before any node
+    /// is allocated for them, so a uniform output never needs more than a single node.
+    pub fn from_fn<F: FnMut(PosU8) -> Option<V>>(mut f: F) -> Self {
+        let mut octree = Self::new();
+        let children = octree.build_children(&mut f, PosU8::ZERO, HALF_WIDTH);
+        octree.nodes[0] = match octree.store_built(children, HALF_WIDTH) {
+            Built::Empty => Node::empty(),
+            Built::Uniform(val) => Node::Full(val),
+            Built::Node(ptr) => octree.nodes.remove(ptr),
+        };
+        octree
+    }
+
+    /// builds the 8 children of the node at `origin` with `half_width`.
+    pub(crate) fn build_children<F: FnMut(PosU8) -> Option<V>>(
+        &mut self,
+        f: &mut F,
+        origin: PosU8,
+        half_width: u8,
+    ) -> [Built<V>; 8] {
+        std::array::from_fn(|i| {
+            let child_origin = origin + oct_offset(i, half_width);
+            if half_width == 1 {
+                match f(child_origin) {
+                    Some(val) => Built::Uniform(val),
+                    None => Built::Empty,
+                }
+            } else {
+                let children = self.build_children(f, child_origin, half_width / 2);
+                self.store_built(children, half_width / 2)
+            }
+        })
+    }
+
+    /// merges the `children` of a node with `half_width` if they are uniform, stores them as a new Mixed node otherwise.
+    pub(crate) fn store_built(&mut self, children: [Built<V>; 8], half_width: u8) -> Built<V> {
+        if children.iter().all(|c| matches!(c, Built::Empty)) {
+            return Built::Empty;
+        }
+        if let Built::Uniform(first) = &children[0] {
+            if children
+                .iter()
+                .all(|c| matches!(c, Built::Uniform(val) if val == first))
+            {
+                return Built::Uniform(first.clone());
+            }
+        }
+        let ptrs = children.map(|child| match child {
+            Built::Empty => EMPTY_PTR,
+            // points to leaf
+            Built::Uniform(val) if half_width == 1 => self.leafs.insert(val),
+            Built::Uniform(val) => self.nodes.insert(Node::Full(val)),
+            Built::Node(ptr) => ptr,
+        });
+        Built::Node(self.nodes.insert(Node::from_ptrs(ptrs)))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use super::DenseError;
+    use crate::{pos, Node, Octree, PosU8};
+
+    #[test]
+    pub fn dense_round_trip() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 8>::new();
+        for _ in 0..500 {
+            let pos = pos!(
+                rng.gen_range(0..16),
+                rng.gen_range(0..16),
+                rng.gen_range(0..16)
+            );
+            octree.insert(pos, rng.gen_range(0..3));
+        }
+        octree.fill_box(pos!(8, 0, 0), pos!(15, 7, 7), 5);
+
+        let dense = octree.to_dense();
+        assert_eq!(dense.len(), 16 * 16 * 16);
+        assert_eq!(dense[1 + 2 * 16 + 3 * 256], octree.get(pos!(1, 2, 3)));
+        assert_eq!(dense.iter().flatten().count() as u64, octree.len());
+
+        let decoded = Octree::<u32, 8>::from_dense(&dense).unwrap();
+        assert!(decoded == octree);
+        assert_eq!(decoded.encode(), octree.encode());
+        assert_eq!(decoded.node_count(), octree.node_count());
+        assert_eq!(decoded.leaf_count(), octree.leaf_count());
+    }
+
+    #[test]
+    pub fn dense_uniform_collapses() {
+        let dense = vec![Some(4u32); 8 * 8 * 8];
+        let octree = Octree::<u32, 4>::from_dense(&dense).unwrap();
+        assert!(matches!(octree.nodes[0], Node::Full(_)));
+        assert_eq!(octree.node_count(), 1);
+        assert_eq!(octree.leaf_count(), 0);
+
+        let empty = Octree::<u32, 4>::from_dense(&vec![None; 512]).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.node_count(), 1);
+    }
+
+    #[test]
+    pub fn dense_wrong_length() {
+        assert_eq!(
+            Octree::<u32, 4>::from_dense(&[None, Some(1)]),
+            Err(DenseError::WrongLength {
+                expected: 512,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    pub fn from_fn_constant_collapses() {
+        let octree = Octree::<u32, 8>::from_fn(|_| Some(2));
+        assert!(matches!(octree.nodes[0], Node::Full(_)));
+        assert_eq!(octree.node_count(), 1);
+        assert_eq!(octree.leaf_count(), 0);
+        assert_eq!(octree.len(), 16 * 16 * 16);
+
+        let empty = Octree::<u32, 8>::from_fn(|_| None);
+        assert!(empty.is_empty());
+        assert_eq!(empty.node_count(), 1);
+    }
+
+    #[test]
+    pub fn from_fn_checkerboard() {
+        let checker = |pos: PosU8| Some(((pos.x + pos.y + pos.z) % 2) as u32);
+        let octree = Octree::<u32, 4>::from_fn(checker);
+        assert_eq!(octree.leaf_count(), 512);
+        // every node is Mixed, nothing could be merged:
+        assert_eq!(octree.node_count(), 1 + 8 + 64);
+        for (pos, val) in octree.iter() {
+            assert_eq!(Some(val), checker(pos));
+        }
+
+        // a sphere like the ones fill_sphere writes:
+        let center = pos!(8, 8, 8);
+        let octree = Octree::<u32, 8>::from_fn(|pos| (pos.dist_squared(center) <= 25).then_some(1));
+        let mut expected = Octree::<u32, 8>::new();
+        expected.fill_sphere(center, 5, 1);
+        assert_eq!(octree.encode(), expected.encode());
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{Octree, PosU8};
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// starts recording the edited parts of the tree, for remeshing only the changed parts of the tree.
+    /// Does nothing if dirty tracking is enabled already.
+    ///
+    /// Single voxel edits record the voxel only if its value changes. Region wide operations like
+    /// [`Octree::fill_box`] record the aligned cubes they write and [`Octree::clear`] records the whole tree.
+    /// [`Octree::get_mut`] records the voxel whenever it hands out a reference.
+    pub fn enable_dirty_tracking(&mut self) {
+        self.dirty.get_or_insert_with(|| Box::new(Octree::new()));
+    }
+
+    /// the parts of the tree edited since the last call, as `(origin, width)` of octree aligned cubes, sorted by
+    /// the Morton code of their origin. Returns nothing if dirty tracking is not enabled.
+    ///
+    /// 8 dirty sibling cubes are coalesced into their parent cube, so the cubes cover the edited voxels
+    /// with as few cubes as possible.
+    pub fn take_dirty_regions(&mut self) -> Vec<(PosU8, u16)> {
+        let Some(dirty) = &mut self.dirty else {
+            return vec![];
+        };
+        let mut regions: Vec<(PosU8, u16)> = dirty
+            .iter_regions()
+            .map(|(origin, width, ())| (origin, width))
+            .collect();
+        dirty.clear();
+        regions.sort_by_key(|(pos, _)| pos.to_morton());
+        regions
+    }
+
+    /// records an edit of the voxel at `pos` if dirty tracking is enabled.
+    pub(crate) fn mark_dirty(&mut self, pos: PosU8) {
+        if let Some(dirty) = &mut self.dirty {
+            dirty.insert(pos, ());
+        }
+    }
+
+    /// records an edit of the voxel at `pos` if dirty tracking is enabled and `val` differs from its value.
+    pub(crate) fn mark_dirty_if_changed(&mut self, pos: PosU8, val: &V) {
+        if self.dirty.is_some() && self.get_ref(pos) != Some(val) {
+            self.mark_dirty(pos);
+        }
+    }
+
+    /// records an edit of the aligned cube at `origin` with side `width` if dirty tracking is enabled.
+    pub(crate) fn mark_dirty_cube(&mut self, origin: PosU8, width: u16) {
+        if let Some(dirty) = &mut self.dirty {
+            let max = |c: u8| (c as u16 + width - 1) as u8;
+            dirty.fill_box(
+                origin,
+                PosU8::new(max(origin.x), max(origin.y), max(origin.z)),
+                (),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn dirty_regions_cover_edits() {
+        let mut octree = Octree::<u32, 8>::new();
+        octree.insert(pos!(1, 1, 1), 1);
+        assert_eq!(octree.take_dirty_regions(), vec![]);
+
+        octree.enable_dirty_tracking();
+        octree.insert(pos!(3, 4, 5), 1);
+        octree.remove(pos!(1, 1, 1));
+        // removing an empty voxel is no edit:
+        octree.remove(pos!(9, 9, 9));
+        octree.entry(pos!(15, 0, 15)).or_insert(2);
+        // a whole 2x2x2 cube and a 4x4x4 cube:
+        for pos in PosU8::iter_box(pos!(8, 8, 8), pos!(9, 9, 9)) {
+            octree.insert(pos, 3);
+        }
+        let mut edits: Vec<(PosU8, u32)> = PosU8::iter_box(pos!(4, 0, 0), pos!(7, 3, 3))
+            .map(|pos| (pos, 4))
+            .collect();
+        octree.set_many(&mut edits);
+
+        let regions = octree.take_dirty_regions();
+        assert_eq!(
+            regions,
+            vec![
+                (pos!(1, 1, 1), 1),
+                (pos!(3, 4, 5), 1),
+                (pos!(4, 0, 0), 4),
+                (pos!(15, 0, 15), 1),
+                (pos!(8, 8, 8), 2),
+            ]
+        );
+        assert_eq!(octree.take_dirty_regions(), vec![]);
+
+        octree.cursor().insert(pos!(2, 2, 2), 5);
+        assert_eq!(octree.take_dirty_regions(), vec![(pos!(2, 2, 2), 1)]);
+
+        // like removing an empty voxel, setting a voxel to its own value is no edit:
+        octree.insert(pos!(2, 2, 2), 5);
+        octree.entry(pos!(3, 4, 5)).or_insert(7);
+        octree.set_many(&mut [(pos!(15, 0, 15), 2)]);
+        assert_eq!(octree.take_dirty_regions(), vec![]);
+    }
+
+    #[test]
+    pub fn dirty_regions_cover_region_edits() {
+        let mut octree = Octree::<u32, 8>::new();
+        octree.enable_dirty_tracking();
+        octree.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), 1);
+        octree.fill_box(pos!(8, 0, 0), pos!(9, 1, 1), 2);
+        assert_eq!(
+            octree.take_dirty_regions(),
+            vec![(pos!(0, 0, 0), 8), (pos!(8, 0, 0), 2)]
+        );
+
+        octree.clear();
+        assert_eq!(octree.take_dirty_regions(), vec![(pos!(0, 0, 0), 16)]);
+
+        // clearing an empty box is no edit:
+        octree.clear_box(pos!(0, 0, 0), pos!(3, 3, 3));
+        assert_eq!(octree.take_dirty_regions(), vec![]);
+
+        let mut prefab = Octree::<u32, 2>::new();
+        prefab.insert(pos!(1, 1, 1), 3);
+        octree.paste_subtree(pos!(4, 4, 4), &prefab);
+        assert_eq!(octree.take_dirty_regions(), vec![(pos!(4, 4, 4), 4)]);
+
+        octree.fill_box(pos!(0, 0, 0), pos!(1, 1, 1), 4);
+        octree.take_dirty_regions();
+        octree.replace_all(4, 5);
+        assert_eq!(octree.take_dirty_regions(), vec![(pos!(0, 0, 0), 2)]);
+        octree.retain(|pos, _| pos != pos!(1, 1, 1));
+        assert_eq!(octree.take_dirty_regions(), vec![(pos!(0, 0, 0), 2)]);
+        octree.retain(|pos, _| pos != pos!(5, 5, 5));
+        assert_eq!(octree.take_dirty_regions(), vec![(pos!(5, 5, 5), 1)]);
+
+        let mut other = Octree::<u32, 8>::new();
+        other.fill_box(pos!(12, 12, 12), pos!(15, 15, 15), 6);
+        octree.merge(&other, |a, _| a);
+        assert_eq!(octree.take_dirty_regions(), vec![(pos!(12, 12, 12), 4)]);
+
+        octree.flood_fill(pos!(12, 12, 12), 7);
+        octree.draw_line(pos!(0, 8, 0), pos!(0, 9, 0), 8);
+        assert_eq!(
+            octree.take_dirty_regions(),
+            vec![
+                (pos!(0, 8, 0), 1),
+                (pos!(0, 9, 0), 1),
+                (pos!(12, 12, 12), 4)
+            ]
+        );
+    }
+}
@@ -0,0 +1,589 @@
+use std::io::{self, Read, Write};
+
+use crate::{Node, Octree, EMPTY_PTR};
+
+/// One node of the portable encoding of an [`Octree`], see [`Octree::encode`].
+///
+/// Unlike the slab indices used internally, this only depends on the logical structure of the tree:
+/// two trees holding the same voxels always have the same encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodedNode<V> {
+    /// a Full node, or a leaf if it is the child of a node with half width 1.
+    Full(V),
+    /// a Mixed node, bit `i` of the mask is set if child `i` exists.
+    /// The existing children follow in preorder.
+    Mixed(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// the encoding ended before the tree was complete.
+    UnexpectedEnd,
+    /// the encoding continues after the tree is complete.
+    TrailingData,
+    /// a Mixed node below the root has no children.
+    EmptyNode,
+    /// a Mixed node appears where a leaf is expected.
+    NodeBelowLeafLevel,
+    /// the bytes do not start with [`MAGIC`].
+    BadMagic,
+    /// the bytes encode a tree with a different `HALF_WIDTH`.
+    HalfWidthMismatch { expected: u8, found: u8 },
+    /// a node starts with an unknown tag byte.
+    InvalidTag(u8),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => write!(f, "unexpected end of encoded octree"),
+            DecodeError::TrailingData => write!(f, "trailing data after encoded octree"),
+            DecodeError::EmptyNode => write!(f, "encoded octree contains an empty node"),
+            DecodeError::NodeBelowLeafLevel => {
+                write!(f, "encoded octree contains a node below the leaf level")
+            }
+            DecodeError::BadMagic => write!(f, "bytes are not an encoded octree"),
+            DecodeError::HalfWidthMismatch { expected, found } => write!(
+                f,
+                "encoded octree has half width {found}, expected {expected}"
+            ),
+            DecodeError::InvalidTag(tag) => write!(f, "invalid node tag {tag}"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// first bytes of the output of [`Octree::to_bytes`].
+pub const MAGIC: [u8; 4] = *b"VOXO";
+
+const TAG_FULL: u8 = 0;
+const TAG_MIXED: u8 = 1;
+
+/// Voxel values with a fixed size little endian representation, needed for [`Octree::to_bytes`].
+pub trait LeBytes: Sized {
+    const SIZE: usize;
+
+    fn write_le_bytes(&self, out: &mut Vec<u8>);
+
+    /// `bytes` always has a length of `SIZE`.
+    fn from_le_bytes(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_le_bytes {
+    ($($t:ty),*) => {
+        $(
+            impl LeBytes for $t {
+                const SIZE: usize = std::mem::size_of::<$t>();
+
+                fn write_le_bytes(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn from_le_bytes(bytes: &[u8]) -> Self {
+                    <$t>::from_le_bytes(bytes.try_into().unwrap())
+                }
+            }
+        )*
+    };
+}
+
+impl_le_bytes!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+/// reads encoded nodes from bytes, stops at the first malformed node and remembers the error.
+struct ByteNodeReader<'a, V> {
+    bytes: &'a [u8],
+    error: Option<DecodeError>,
+    _marker: std::marker::PhantomData<V>,
+}
+
+impl<'a, V: LeBytes> Iterator for ByteNodeReader<'a, V> {
+    type Item = EncodedNode<V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (tag, rest) = self.bytes.split_first()?;
+        let (node, len) = match *tag {
+            TAG_FULL if rest.len() >= V::SIZE => (
+                EncodedNode::Full(V::from_le_bytes(&rest[..V::SIZE])),
+                V::SIZE,
+            ),
+            TAG_MIXED if !rest.is_empty() => (EncodedNode::Mixed(rest[0]), 1),
+            TAG_FULL | TAG_MIXED => {
+                self.error = Some(DecodeError::UnexpectedEnd);
+                return None;
+            }
+            tag => {
+                self.error = Some(DecodeError::InvalidTag(tag));
+                return None;
+            }
+        };
+        self.bytes = &rest[len..];
+        Some(node)
+    }
+}
+
+/// reads encoded nodes from a stream, stops at the first malformed node or io error and remembers the error.
+struct IoNodeReader<R, V> {
+    reader: R,
+    error: Option<io::Error>,
+    /// buffer for the bytes of one value.
+    scratch: Vec<u8>,
+    _marker: std::marker::PhantomData<V>,
+}
+
+impl<R: Read, V: LeBytes> IoNodeReader<R, V> {
+    fn read_node(&mut self) -> io::Result<EncodedNode<V>> {
+        let mut tag = [0u8];
+        self.reader.read_exact(&mut tag)?;
+        match tag[0] {
+            TAG_FULL => {
+                self.scratch.resize(V::SIZE, 0);
+                self.reader.read_exact(&mut self.scratch)?;
+                Ok(EncodedNode::Full(V::from_le_bytes(&self.scratch)))
+            }
+            TAG_MIXED => {
+                let mut mask = [0u8];
+                self.reader.read_exact(&mut mask)?;
+                Ok(EncodedNode::Mixed(mask[0]))
+            }
+            tag => Err(invalid_data(DecodeError::InvalidTag(tag))),
+        }
+    }
+}
+
+impl<R: Read, V: LeBytes> Iterator for IoNodeReader<R, V> {
+    type Item = EncodedNode<V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_node() {
+            Ok(node) => Some(node),
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+}
+
+fn invalid_data(err: DecodeError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq + LeBytes,
+{
+    /// encodes the tree into a compact binary format:
+    /// [`MAGIC`], the `HALF_WIDTH` as one byte, followed by the nodes of [`Octree::encode`].
+    /// Each node is a tag byte followed by the little endian value (Full) or the child mask (Mixed).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.write_to(&mut bytes)
+            .expect("writing to a Vec does not fail");
+        bytes
+    }
+
+    /// writes the format of [`Octree::to_bytes`] to `w`, node by node while traversing the tree,
+    /// without building the whole encoding in memory first.
+    ///
+    /// Every node is a small write of its own, so `w` should be buffered, e.g. by a [`std::io::BufWriter`].
+    pub fn write_to<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(&MAGIC)?;
+        w.write_all(&[HALF_WIDTH])?;
+        let mut scratch = Vec::with_capacity(V::SIZE);
+        self.write_node(&mut w, &mut scratch, 0, HALF_WIDTH)
+    }
+
+    fn write_node<W: Write>(
+        &self,
+        w: &mut W,
+        scratch: &mut Vec<u8>,
+        node_ptr: usize,
+        half_width: u8,
+    ) -> io::Result<()> {
+        match self.nodes[node_ptr] {
+            Node::Full(ref val) => write_full(w, scratch, val),
+            Node::Mixed(ptrs, _) => {
+                let mut mask: u8 = 0;
+                for (i, ptr) in ptrs.iter().enumerate() {
+                    if *ptr != EMPTY_PTR {
+                        mask |= 1 << i;
+                    }
+                }
+                w.write_all(&[TAG_MIXED, mask])?;
+                for ptr in ptrs {
+                    if ptr == EMPTY_PTR {
+                        continue;
+                    }
+                    if half_width == 1 {
+                        write_full(w, scratch, &self.leafs[ptr])?;
+                    } else {
+                        self.write_node(w, scratch, ptr, half_width / 2)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// reads one tree in the format of [`Octree::to_bytes`] from `r`, node by node.
+    ///
+    /// Exactly the bytes of the tree are consumed, so more data can follow in the stream. Because of that,
+    /// trailing data is not an error here, unlike in [`Octree::from_bytes`]. Malformed input results in an
+    /// [`io::ErrorKind::InvalidData`] error wrapping the [`DecodeError`], truncated input in an
+    /// [`io::ErrorKind::UnexpectedEof`] error.
+    ///
+    /// Every node is a small read of its own, so `r` should be buffered, e.g. by a [`std::io::BufReader`].
+    pub fn read_from<R: Read>(mut r: R) -> io::Result<Self> {
+        let mut header = [0u8; MAGIC.len() + 1];
+        r.read_exact(&mut header)?;
+        if header[..MAGIC.len()] != MAGIC {
+            return Err(invalid_data(DecodeError::BadMagic));
+        }
+        let half_width = header[MAGIC.len()];
+        if half_width != HALF_WIDTH {
+            return Err(invalid_data(DecodeError::HalfWidthMismatch {
+                expected: HALF_WIDTH,
+                found: half_width,
+            }));
+        }
+        let mut reader = IoNodeReader::<R, V> {
+            reader: r,
+            error: None,
+            scratch: Vec::with_capacity(V::SIZE),
+            _marker: std::marker::PhantomData,
+        };
+        let mut octree = Self::new();
+        // not Octree::decode, which would read past the end of the tree to check for trailing data.
+        let root = octree.decode_node(&mut reader, HALF_WIDTH, true);
+        // an io error or malformed node is reported as the cause, instead of the resulting unexpected end:
+        if let Some(err) = reader.error {
+            return Err(err);
+        }
+        octree.nodes[0] = root.map_err(invalid_data)?;
+        Ok(octree)
+    }
+
+    /// decodes the output of [`Octree::to_bytes`]. Malformed or truncated input results in an error, never in a panic.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() < MAGIC.len() || bytes[..MAGIC.len()] != MAGIC {
+            return Err(DecodeError::BadMagic);
+        }
+        let half_width = *bytes.get(MAGIC.len()).ok_or(DecodeError::UnexpectedEnd)?;
+        if half_width != HALF_WIDTH {
+            return Err(DecodeError::HalfWidthMismatch {
+                expected: HALF_WIDTH,
+                found: half_width,
+            });
+        }
+        let mut reader = ByteNodeReader::<V> {
+            bytes: &bytes[MAGIC.len() + 1..],
+            error: None,
+            _marker: std::marker::PhantomData,
+        };
+        let octree = Self::decode(reader.by_ref());
+        // a malformed node is reported as the cause, instead of the resulting unexpected end:
+        match reader.error {
+            Some(err) => Err(err),
+            None => octree,
+        }
+    }
+}
+
+/// writes a Full node or leaf with the value `val`.
+fn write_full<W: Write, V: LeBytes>(w: &mut W, scratch: &mut Vec<u8>, val: &V) -> io::Result<()> {
+    scratch.clear();
+    scratch.push(TAG_FULL);
+    val.write_le_bytes(scratch);
+    w.write_all(scratch)
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// encodes the tree in preorder, starting at the root.
+    ///
+    /// Empty children of Mixed nodes only cost one bit in the mask of their parent.
+    ///
+    /// The tree does not implement serde's `Serialize`/`Deserialize` yet, [`Octree::to_bytes`] and
+    /// [`Octree::write_to`] store this encoding for persisting trees or sending them over the network.
+    pub fn encode(&self) -> Vec<EncodedNode<V>> {
+        let mut encoded = vec![];
+        self.encode_node(0, HALF_WIDTH, &mut encoded);
+        encoded
+    }
+
+    fn encode_node(&self, node_ptr: usize, half_width: u8, encoded: &mut Vec<EncodedNode<V>>) {
+        match self.nodes[node_ptr] {
+            Node::Full(ref val) => encoded.push(EncodedNode::Full(val.clone())),
+            Node::Mixed(ptrs, _) => {
+                let mut mask: u8 = 0;
+                for (i, ptr) in ptrs.iter().enumerate() {
+                    if *ptr != EMPTY_PTR {
+                        mask |= 1 << i;
+                    }
+                }
+                encoded.push(EncodedNode::Mixed(mask));
+                for ptr in ptrs {
+                    if ptr == EMPTY_PTR {
+                        continue;
+                    }
+                    if half_width == 1 {
+                        encoded.push(EncodedNode::Full(self.leafs[ptr].clone()));
+                    } else {
+                        self.encode_node(ptr, half_width / 2, encoded);
+                    }
+                }
+            }
+        }
+    }
+
+    /// rebuilds a tree from the output of [`Octree::encode`].
+    pub fn decode(encoded: impl IntoIterator<Item = EncodedNode<V>>) -> Result<Self, DecodeError> {
+        let mut encoded = encoded.into_iter();
+        let mut octree = Self::new();
+        let root = octree.decode_node(&mut encoded, HALF_WIDTH, true)?;
+        octree.nodes[0] = root;
+        if encoded.next().is_some() {
+            return Err(DecodeError::TrailingData);
+        }
+        Ok(octree)
+    }
+
+    /// decodes the next node and all of its children, returns the node to be inserted by the caller.
+    fn decode_node(
+        &mut self,
+        encoded: &mut impl Iterator<Item = EncodedNode<V>>,
+        half_width: u8,
+        is_root: bool,
+    ) -> Result<Node<V>, DecodeError> {
+        match encoded.next().ok_or(DecodeError::UnexpectedEnd)? {
+            EncodedNode::Full(val) => Ok(Node::Full(val)),
+            EncodedNode::Mixed(mask) => {
+                if mask == 0 && !is_root {
+                    return Err(DecodeError::EmptyNode);
+                }
+                let mut ptrs = [EMPTY_PTR; 8];
+                for (i, ptr) in ptrs.iter_mut().enumerate() {
+                    if mask & (1 << i) == 0 {
+                        continue;
+                    }
+                    *ptr = if half_width == 1 {
+                        match encoded.next().ok_or(DecodeError::UnexpectedEnd)? {
+                            EncodedNode::Full(val) => self.leafs.insert(val),
+                            EncodedNode::Mixed(_) => return Err(DecodeError::NodeBelowLeafLevel),
+                        }
+                    } else {
+                        let child = self.decode_node(encoded, half_width / 2, false)?;
+                        self.nodes.insert(child)
+                    };
+                }
+                Ok(Node::from_ptrs(ptrs))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::io::{Cursor, ErrorKind};
+
+    use rand::{thread_rng, Rng};
+
+    use crate::{pos, DecodeError, EncodedNode, Octree, PosU8};
+
+    #[test]
+    pub fn encode_decode_roundtrip() {
+        let mut octree = Octree::<u32, 16>::new();
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let pos = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            octree.insert(pos, rng.gen_range(0..3));
+        }
+        for x in 0..16 {
+            for y in 0..16 {
+                for z in 0..16 {
+                    octree.insert(pos!(x, y, z), 5);
+                }
+            }
+        }
+
+        let encoded = octree.encode();
+        let decoded = Octree::<u32, 16>::decode(encoded.clone()).unwrap();
+        for x in 0..32 {
+            for y in 0..32 {
+                for z in 0..32 {
+                    assert_eq!(decoded.get(pos!(x, y, z)), octree.get(pos!(x, y, z)));
+                }
+            }
+        }
+        assert_eq!(decoded.leafs.len(), octree.leafs.len());
+        assert_eq!(decoded.nodes.len(), octree.nodes.len());
+        assert_eq!(decoded.encode(), encoded);
+    }
+
+    #[test]
+    pub fn encode_small_tree() {
+        let mut octree = Octree::<u32, 1>::new();
+        assert_eq!(octree.encode(), vec![EncodedNode::Mixed(0)]);
+        octree.insert(pos!(0, 0, 1), 3);
+        octree.insert(pos!(1, 1, 0), 4);
+        assert_eq!(
+            octree.encode(),
+            vec![
+                EncodedNode::Mixed(0b0100_0010),
+                EncodedNode::Full(3),
+                EncodedNode::Full(4)
+            ]
+        );
+    }
+
+    #[test]
+    pub fn decode_errors() {
+        type Tree = Octree<u32, 2>;
+        assert_eq!(
+            Tree::decode(vec![]).unwrap_err(),
+            DecodeError::UnexpectedEnd
+        );
+        assert_eq!(
+            Tree::decode(vec![EncodedNode::Mixed(1)]).unwrap_err(),
+            DecodeError::UnexpectedEnd
+        );
+        assert_eq!(
+            Tree::decode(vec![EncodedNode::Full(1), EncodedNode::Full(1)]).unwrap_err(),
+            DecodeError::TrailingData
+        );
+        assert_eq!(
+            Tree::decode(vec![EncodedNode::Mixed(1), EncodedNode::Mixed(0)]).unwrap_err(),
+            DecodeError::EmptyNode
+        );
+        assert_eq!(
+            Tree::decode(vec![
+                EncodedNode::Mixed(1),
+                EncodedNode::Mixed(1),
+                EncodedNode::Mixed(1)
+            ])
+            .unwrap_err(),
+            DecodeError::NodeBelowLeafLevel
+        );
+    }
+
+    #[test]
+    pub fn bytes_roundtrip() {
+        let mut octree = Octree::<u16, 16>::new();
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let pos = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            octree.insert(pos, rng.gen_range(0..3));
+        }
+        let bytes = octree.to_bytes();
+        assert_eq!(bytes[..4], *b"VOXO");
+        assert_eq!(bytes[4], 16);
+        let decoded = Octree::<u16, 16>::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.encode(), octree.encode());
+    }
+
+    #[test]
+    pub fn stream_roundtrip() {
+        let mut octree = Octree::<u32, 16>::new();
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let pos = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            octree.insert(pos, rng.gen_range(0..3));
+        }
+        octree.fill_box(pos!(0, 0, 0), pos!(15, 15, 15), 7);
+
+        let mut stream = Cursor::new(Vec::new());
+        octree.write_to(&mut stream).unwrap();
+        assert_eq!(*stream.get_ref(), octree.to_bytes());
+        // a second tree directly after the first one:
+        Octree::<u32, 16>::filled(3).write_to(&mut stream).unwrap();
+
+        stream.set_position(0);
+        let decoded = Octree::<u32, 16>::read_from(&mut stream).unwrap();
+        assert_eq!(decoded.encode(), octree.encode());
+        let second = Octree::<u32, 16>::read_from(&mut stream).unwrap();
+        assert_eq!(second.encode(), Octree::<u32, 16>::filled(3).encode());
+        assert_eq!(stream.position() as usize, stream.get_ref().len());
+    }
+
+    #[test]
+    pub fn stream_corrupted() {
+        let mut octree = Octree::<u16, 4>::new();
+        octree.insert(pos!(1, 2, 3), 1);
+        octree.insert(pos!(7, 2, 3), 2);
+        let bytes = octree.to_bytes();
+
+        for len in 0..bytes.len() {
+            let err = Octree::<u16, 4>::read_from(&bytes[..len]).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        }
+        let mut bad_tag = bytes.clone();
+        bad_tag[5] = 7;
+        let err = Octree::<u16, 4>::read_from(&bad_tag[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            err.into_inner().unwrap().downcast_ref::<DecodeError>(),
+            Some(&DecodeError::InvalidTag(7))
+        );
+        let err = Octree::<u16, 8>::read_from(&bytes[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let err = Octree::<u16, 4>::read_from(&b"nope!"[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    pub fn bytes_corrupted() {
+        let mut octree = Octree::<f32, 4>::new();
+        octree.insert(pos!(1, 2, 3), 1.5);
+        octree.insert(pos!(7, 2, 3), -1.0);
+        let bytes = octree.to_bytes();
+
+        // every truncation is an error:
+        for len in 0..bytes.len() {
+            assert!(Octree::<f32, 4>::from_bytes(&bytes[..len]).is_err());
+        }
+        assert_eq!(
+            Octree::<f32, 4>::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+            DecodeError::UnexpectedEnd
+        );
+        assert_eq!(
+            Octree::<f32, 8>::from_bytes(&bytes).unwrap_err(),
+            DecodeError::HalfWidthMismatch {
+                expected: 8,
+                found: 4
+            }
+        );
+        assert_eq!(
+            Octree::<f32, 4>::from_bytes(b"nope").unwrap_err(),
+            DecodeError::BadMagic
+        );
+
+        let mut bad_tag = bytes.clone();
+        bad_tag[5] = 7;
+        assert_eq!(
+            Octree::<f32, 4>::from_bytes(&bad_tag).unwrap_err(),
+            DecodeError::InvalidTag(7)
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.extend([0, 0, 0, 0, 0]);
+        assert_eq!(
+            Octree::<f32, 4>::from_bytes(&trailing).unwrap_err(),
+            DecodeError::TrailingData
+        );
+    }
+}
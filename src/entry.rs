@@ -0,0 +1,347 @@
+use crate::{Node, Octree, PosU8, Ptr, EMPTY_PTR};
+
+/// a view into a single voxel of an [`Octree`], see [`Octree::entry`].
+///
+/// The entry caches the path from the root to the voxel, so reading and then writing or removing it
+/// needs only a single descent. Writes keep the tree in its merged form just like [`Octree::insert`]
+/// and [`Octree::remove`] do: a voxel that completes a uniform node is merged into a Full node, and
+/// nodes that become empty are removed.
+pub enum Entry<'a, V, const HALF_WIDTH: u8> {
+    Occupied(OccupiedEntry<'a, V, HALF_WIDTH>),
+    Vacant(VacantEntry<'a, V, HALF_WIDTH>),
+}
+
+/// an [`Entry`] for a voxel that is set, either by a leaf of its own or by a Full node covering it.
+pub struct OccupiedEntry<'a, V, const HALF_WIDTH: u8> {
+    octree: &'a mut Octree<V, HALF_WIDTH>,
+    pos: PosU8,
+    /// `(node_ptr, half_width, oct_idx)` of the Mixed nodes from the root downwards.
+    path: Vec<(Ptr, u8, usize)>,
+    slot: OccupiedSlot,
+}
+
+/// where the value of the voxel of an [`OccupiedEntry`] is stored.
+enum OccupiedSlot {
+    /// a leaf of its own.
+    Leaf(Ptr),
+    /// the value of a Full node covering the voxel.
+    Full(FullNode),
+}
+
+/// the Full node covering the voxel of an [`OccupiedEntry`].
+struct FullNode {
+    node_ptr: Ptr,
+    half_width: u8,
+    /// position of the voxel relative to the Full node.
+    pos: PosU8,
+}
+
+/// an [`Entry`] for a voxel that is empty.
+pub struct VacantEntry<'a, V, const HALF_WIDTH: u8> {
+    octree: &'a mut Octree<V, HALF_WIDTH>,
+    pos: PosU8,
+    /// `(node_ptr, half_width, oct_idx)` of the Mixed nodes from the root downwards,
+    /// the last one has an empty child at `oct_idx`.
+    path: Vec<(Ptr, u8, usize)>,
+    /// position of the voxel relative to the empty child.
+    child_pos: PosU8,
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// the [`Entry`] of the voxel at `pos`, for in-place reading and editing.
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn entry(&mut self, pos: PosU8) -> Entry<'_, V, HALF_WIDTH> {
+        Self::assert_in_bounds(pos);
+        let mut path: Vec<(Ptr, u8, usize)> = vec![];
+        let mut rel_pos = pos;
+        let mut node_ptr: usize = 0;
+        let mut half_width: u8 = HALF_WIDTH;
+        loop {
+            match self.nodes[node_ptr] {
+                Node::Full(_) => {
+                    return Entry::Occupied(OccupiedEntry {
+                        octree: self,
+                        pos,
+                        path,
+                        slot: OccupiedSlot::Full(FullNode {
+                            node_ptr,
+                            half_width,
+                            pos: rel_pos,
+                        }),
+                    });
+                }
+                Node::Mixed(ptrs, _) => {
+                    let idx = Self::oct_index(&mut rel_pos, half_width);
+                    path.push((node_ptr, half_width, idx));
+                    let ptr = ptrs[idx];
+                    if ptr == EMPTY_PTR {
+                        return Entry::Vacant(VacantEntry {
+                            octree: self,
+                            pos,
+                            path,
+                            child_pos: rel_pos,
+                        });
+                    } else if half_width == 1 {
+                        // points to leaf
+                        return Entry::Occupied(OccupiedEntry {
+                            octree: self,
+                            pos,
+                            path,
+                            slot: OccupiedSlot::Leaf(ptr),
+                        });
+                    } else {
+                        half_width /= 2;
+                        node_ptr = ptr;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a, V, const HALF_WIDTH: u8> Entry<'a, V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    pub fn pos(&self) -> PosU8 {
+        match self {
+            Entry::Occupied(entry) => entry.pos,
+            Entry::Vacant(entry) => entry.pos,
+        }
+    }
+
+    /// the current value, or `val` after inserting it if the voxel is empty.
+    pub fn or_insert(self, val: V) -> V {
+        self.or_insert_with(|| val)
+    }
+
+    /// the current value, or `f()` after inserting it if the voxel is empty.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, f: F) -> V {
+        match self {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => {
+                let val = f();
+                entry.insert(val.clone());
+                val
+            }
+        }
+    }
+}
+
+impl<'a, V, const HALF_WIDTH: u8> OccupiedEntry<'a, V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    pub fn pos(&self) -> PosU8 {
+        self.pos
+    }
+
+    /// the value of the voxel, which is the value of the Full node if the voxel is covered by one.
+    pub fn get(&self) -> &V {
+        match &self.slot {
+            OccupiedSlot::Leaf(leaf_ptr) => &self.octree.leafs[*leaf_ptr],
+            OccupiedSlot::Full(full) => match &self.octree.nodes[full.node_ptr] {
+                Node::Full(val) => val,
+                Node::Mixed(..) => {
+                    unreachable!("the entry borrows the tree, so the node is still Full")
+                }
+            },
+        }
+    }
+
+    /// sets the voxel to `val` and returns the old value.
+    ///
+    /// Setting a voxel inside of a Full node to a different value splits the Full node up,
+    /// setting a leaf to the value of all its siblings merges them into a Full node.
+    pub fn insert(self, val: V) -> V {
+        let octree = self.octree;
+        match self.slot {
+            OccupiedSlot::Full(full) => {
+                if matches!(&octree.nodes[full.node_ptr], Node::Full(full_val) if *full_val == val)
+                {
+                    return val;
+                }
+                octree.mark_dirty(self.pos);
+                let full_val = octree.take_full_value(full.node_ptr);
+                let mut pos = full.pos;
+                let idx = Octree::<V, HALF_WIDTH>::oct_index(&mut pos, full.half_width);
+                let child_node_ptrs = octree.insert_mixed_child_nodes_for_full_node_split(
+                    full_val.clone(),
+                    idx,
+                    Some(val),
+                    pos,
+                    full.half_width,
+                );
+                octree.nodes[full.node_ptr] = Node::from_ptrs(child_node_ptrs);
+                full_val
+            }
+            OccupiedSlot::Leaf(leaf_ptr) => {
+                if octree.leafs[leaf_ptr] != val {
+                    octree.mark_dirty(self.pos);
+                }
+                let old = std::mem::replace(&mut octree.leafs[leaf_ptr], val);
+                octree.merge_path(self.path.iter().map(|(ptr, hw, _)| (*ptr, *hw)).collect());
+                old
+            }
+        }
+    }
+
+    /// empties the voxel and returns its value.
+    ///
+    /// Removing from inside of a Full node splits it up, nodes that become empty are removed from the tree.
+    pub fn remove(self) -> V {
+        let octree = self.octree;
+        octree.mark_dirty(self.pos);
+        match self.slot {
+            OccupiedSlot::Full(full) => {
+                let full_val = octree.take_full_value(full.node_ptr);
+                let mut pos = full.pos;
+                let idx = Octree::<V, HALF_WIDTH>::oct_index(&mut pos, full.half_width);
+                let child_node_ptrs = octree.insert_mixed_child_nodes_for_full_node_split(
+                    full_val.clone(),
+                    idx,
+                    None,
+                    pos,
+                    full.half_width,
+                );
+                octree.nodes[full.node_ptr] = Node::from_ptrs(child_node_ptrs);
+                full_val
+            }
+            OccupiedSlot::Leaf(leaf_ptr) => {
+                let val = octree.leafs.remove(leaf_ptr);
+                let (parent_ptr, _, idx) = *self.path.last().expect("a leaf has a parent node");
+                let Node::Mixed(mut parent_ptrs, _) = octree.nodes[parent_ptr] else {
+                    unreachable!("path only contains Mixed nodes");
+                };
+                parent_ptrs[idx] = EMPTY_PTR;
+                octree.nodes[parent_ptr] = Node::from_ptrs(parent_ptrs);
+                let path: Vec<(usize, usize)> =
+                    self.path.iter().map(|(ptr, _, idx)| (*ptr, *idx)).collect();
+                octree.collapse_empty_path(&path);
+                val
+            }
+        }
+    }
+}
+
+impl<'a, V, const HALF_WIDTH: u8> VacantEntry<'a, V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    pub fn pos(&self) -> PosU8 {
+        self.pos
+    }
+
+    /// sets the voxel to `val`.
+    ///
+    /// Nothing is returned, because the new voxel might get merged into a Full node with its siblings right away.
+    pub fn insert(self, val: V) {
+        let octree = self.octree;
+        octree.mark_dirty(self.pos);
+        let (node_ptr, half_width, idx) = *self.path.last().expect("the root is always Mixed here");
+        let Node::Mixed(mut ptrs, _) = octree.nodes[node_ptr] else {
+            unreachable!("path only contains Mixed nodes");
+        };
+        ptrs[idx] = octree.insert_nodes_below_empty_ptr(self.child_pos, val, half_width / 2);
+        octree.nodes[node_ptr] = Node::from_ptrs(ptrs);
+        octree.merge_path(self.path.iter().map(|(ptr, hw, _)| (*ptr, *hw)).collect());
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::Entry;
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn vacant_entry() {
+        let mut octree = Octree::<u32, 8>::new();
+        let Entry::Vacant(entry) = octree.entry(pos!(3, 4, 5)) else {
+            panic!("voxel should be empty");
+        };
+        assert_eq!(entry.pos(), pos!(3, 4, 5));
+        entry.insert(7);
+        assert_eq!(octree.get(pos!(3, 4, 5)), Some(7));
+        assert_eq!(octree.len(), 1);
+
+        // completing a node merges it:
+        let mut expected = Octree::<u32, 8>::new();
+        expected.fill_box(pos!(2, 2, 2), pos!(3, 3, 3), 1);
+        let mut octree = Octree::<u32, 8>::new();
+        for (pos, _) in expected.iter().skip(1) {
+            octree.insert(pos, 1);
+        }
+        assert_eq!(octree.entry(pos!(2, 2, 2)).or_insert(1), 1);
+        assert_eq!(octree.encode(), expected.encode());
+    }
+
+    #[test]
+    pub fn occupied_entry() {
+        let mut octree = Octree::<u32, 8>::new();
+        octree.insert(pos!(1, 1, 1), 3);
+        octree.insert(pos!(9, 9, 9), 4);
+
+        let Entry::Occupied(entry) = octree.entry(pos!(1, 1, 1)) else {
+            panic!("voxel should be set");
+        };
+        assert_eq!(*entry.get(), 3);
+        assert_eq!(entry.insert(5), 3);
+        assert_eq!(octree.get(pos!(1, 1, 1)), Some(5));
+
+        let Entry::Occupied(entry) = octree.entry(pos!(1, 1, 1)) else {
+            panic!("voxel should be set");
+        };
+        assert_eq!(entry.remove(), 5);
+        assert_eq!(octree.get(pos!(1, 1, 1)), None);
+
+        // the removed voxel's nodes are collapsed like with remove:
+        let mut expected = Octree::<u32, 8>::new();
+        expected.insert(pos!(9, 9, 9), 4);
+        assert_eq!(octree.encode(), expected.encode());
+        assert_eq!(octree.node_count(), expected.node_count());
+
+        assert_eq!(octree.entry(pos!(9, 9, 9)).or_insert_with(|| panic!()), 4);
+    }
+
+    #[test]
+    pub fn occupied_entry_in_full_node() {
+        let mut octree = Octree::<u32, 8>::new();
+        octree.fill_box(PosU8::ZERO, pos!(7, 7, 7), 2);
+        let nodes_before = octree.node_count();
+
+        let Entry::Occupied(entry) = octree.entry(pos!(5, 6, 7)) else {
+            panic!("voxel inside of a Full node should be set");
+        };
+        assert_eq!(*entry.get(), 2);
+        // same value, nothing changes:
+        assert_eq!(entry.insert(2), 2);
+        assert_eq!(octree.node_count(), nodes_before);
+
+        let Entry::Occupied(entry) = octree.entry(pos!(5, 6, 7)) else {
+            panic!("voxel inside of a Full node should be set");
+        };
+        assert_eq!(entry.insert(3), 2);
+        assert_eq!(octree.get(pos!(5, 6, 7)), Some(3));
+        assert_eq!(octree.get(pos!(5, 6, 6)), Some(2));
+        assert_eq!(octree.len(), 512);
+
+        let mut expected = Octree::<u32, 8>::new();
+        expected.fill_box(PosU8::ZERO, pos!(7, 7, 7), 2);
+        let Entry::Occupied(entry) = expected.entry(pos!(0, 0, 0)) else {
+            panic!("voxel inside of a Full node should be set");
+        };
+        assert_eq!(entry.remove(), 2);
+        assert_eq!(expected.len(), 511);
+        assert_eq!(expected.get(pos!(0, 0, 0)), None);
+
+        // a Full root:
+        let mut octree = Octree::<u32, 2>::new();
+        octree.fill_box(PosU8::ZERO, pos!(3, 3, 3), 1);
+        assert!(matches!(octree.entry(pos!(3, 3, 3)), Entry::Occupied(_)));
+        assert_eq!(octree.entry(pos!(3, 3, 3)).or_insert(9), 1);
+    }
+}
@@ -0,0 +1,121 @@
+//! Small geometry helpers working on `[f32; 3]`, e.g. for broad-phase culling against
+//! [`crate::Octree::bounding_box`]. Boxes are given by their `min` and `max` corners.
+
+/// slab test of the ray `origin + t * dir` against the box `min..max`.
+///
+/// Returns `(t_enter, t_exit, enter_axis)` if the ray's line hits the box, where `enter_axis` is
+/// the axis of the face through which the ray enters. `t_enter` is negative if `origin` is inside
+/// of the box, and both are negative if the box lies behind the origin.
+pub(crate) fn ray_box(
+    origin: [f32; 3],
+    dir: [f32; 3],
+    min: [f32; 3],
+    max: [f32; 3],
+) -> Option<(f32, f32, usize)> {
+    let mut t_enter = f32::NEG_INFINITY;
+    let mut t_exit = f32::INFINITY;
+    let mut enter_axis = 0;
+    for axis in 0..3 {
+        if dir[axis] == 0.0 {
+            // parallel to the slab, either always or never inside of it.
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (min[axis] - origin[axis]) / dir[axis];
+        let t2 = (max[axis] - origin[axis]) / dir[axis];
+        let (t_near, t_far) = if t1 < t2 { (t1, t2) } else { (t2, t1) };
+        if t_near > t_enter {
+            t_enter = t_near;
+            enter_axis = axis;
+        }
+        t_exit = t_exit.min(t_far);
+    }
+    if t_enter > t_exit {
+        None
+    } else {
+        Some((t_enter, t_exit, enter_axis))
+    }
+}
+
+/// slab test of the ray `origin + t * dir` against the box `min..max`, `dir` does not need to be normalized.
+///
+/// Returns `(t_enter, t_exit)` if the ray hits the box. If `origin` is inside of the box, `t_enter` is negative.
+/// `None` if the box is missed or lies completely behind the origin.
+pub fn ray_aabb(
+    origin: [f32; 3],
+    dir: [f32; 3],
+    min: [f32; 3],
+    max: [f32; 3],
+) -> Option<(f32, f32)> {
+    let (t_enter, t_exit, _) = ray_box(origin, dir, min, max)?;
+    (t_exit >= 0.0).then_some((t_enter, t_exit))
+}
+
+/// true if the boxes `a_min..a_max` and `b_min..b_max` overlap, touching faces count as overlapping.
+pub fn aabbs_intersect(a_min: [f32; 3], a_max: [f32; 3], b_min: [f32; 3], b_max: [f32; 3]) -> bool {
+    (0..3).all(|axis| a_min[axis] <= b_max[axis] && b_min[axis] <= a_max[axis])
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::{aabbs_intersect, ray_aabb};
+
+    #[test]
+    pub fn ray_aabb_hit() {
+        let hit = ray_aabb([-2.0, 0.5, 0.5], [1.0, 0.0, 0.0], [0.0; 3], [1.0; 3]);
+        assert_eq!(hit, Some((2.0, 3.0)));
+        // not normalized, t is in multiples of dir:
+        let hit = ray_aabb([-2.0, 0.5, 0.5], [2.0, 0.0, 0.0], [0.0; 3], [1.0; 3]);
+        assert_eq!(hit, Some((1.0, 1.5)));
+    }
+
+    #[test]
+    pub fn ray_aabb_miss() {
+        // passes above the box:
+        assert_eq!(
+            ray_aabb([-2.0, 1.5, 0.5], [1.0, 0.0, 0.0], [0.0; 3], [1.0; 3]),
+            None
+        );
+        // the box is behind the origin:
+        assert_eq!(
+            ray_aabb([3.0, 0.5, 0.5], [1.0, 0.0, 0.0], [0.0; 3], [1.0; 3]),
+            None
+        );
+        // diagonal past the corner:
+        assert_eq!(
+            ray_aabb([-1.0, 0.0, 2.5], [1.0, 1.0, 0.0], [0.0; 3], [1.0; 3]),
+            None
+        );
+    }
+
+    #[test]
+    pub fn ray_aabb_inside() {
+        let (t_enter, t_exit) =
+            ray_aabb([0.5, 0.25, 0.5], [0.0, 1.0, 0.0], [0.0; 3], [1.0; 3]).unwrap();
+        assert!(t_enter < 0.0);
+        assert_eq!(t_exit, 0.75);
+    }
+
+    #[test]
+    pub fn aabbs_intersect_cases() {
+        assert!(aabbs_intersect([0.0; 3], [2.0; 3], [1.0; 3], [3.0; 3]));
+        // touching faces:
+        assert!(aabbs_intersect(
+            [0.0; 3],
+            [1.0; 3],
+            [1.0, 0.0, 0.0],
+            [2.0, 1.0, 1.0]
+        ));
+        // contained:
+        assert!(aabbs_intersect([0.0; 3], [4.0; 3], [1.0; 3], [2.0; 3]));
+        // separated along z only:
+        assert!(!aabbs_intersect(
+            [0.0; 3],
+            [1.0; 3],
+            [0.0, 0.0, 1.5],
+            [1.0, 1.0, 2.0]
+        ));
+    }
+}
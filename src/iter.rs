@@ -0,0 +1,647 @@
+use std::ops::ControlFlow;
+
+use slab::Slab;
+
+use crate::{
+    oct_offset,
+    region::{box_coverage, Coverage},
+    Node, Octree, PosU8, EMPTY_PTR,
+};
+
+/// Iterator over all set voxels of an [`Octree`], see [`Octree::iter`].
+///
+/// Full nodes are expanded into all of the voxels they cover.
+pub struct OctreeIter<'a, V, const HALF_WIDTH: u8> {
+    octree: &'a Octree<V, HALF_WIDTH>,
+    /// nodes on the path from the root to the node currently visited.
+    stack: Vec<Frame>,
+    /// the Full node currently being expanded.
+    full: Option<FullRegion<V>>,
+}
+
+struct Frame {
+    node_ptr: usize,
+    half_width: u8,
+    origin: PosU8,
+    /// the next child of a Mixed node to visit.
+    child_index: usize,
+}
+
+struct FullRegion<V> {
+    origin: PosU8,
+    width: u16,
+    val: V,
+    /// index of the next voxel in the region, x changes fastest.
+    next: u32,
+}
+
+impl Frame {
+    fn root(half_width: u8) -> Self {
+        Frame {
+            node_ptr: 0,
+            half_width,
+            origin: PosU8::ZERO,
+            child_index: 0,
+        }
+    }
+}
+
+impl<V: Clone> FullRegion<V> {
+    /// the next voxel of the region, `None` once all voxels were returned.
+    fn next_voxel(&mut self) -> Option<(PosU8, V)> {
+        let width = self.width as u32;
+        if self.next == width * width * width {
+            return None;
+        }
+        let offset = PosU8 {
+            x: (self.next % width) as u8,
+            y: (self.next / width % width) as u8,
+            z: (self.next / (width * width)) as u8,
+        };
+        self.next += 1;
+        Some((self.origin + offset, self.val.clone()))
+    }
+}
+
+/// a uniform part of the tree, found by [`next_uniform`].
+enum Uniform {
+    /// the leaf at the ptr, with its position.
+    Leaf(PosU8, usize),
+    /// the Full node at the ptr, covering the cube with the origin and width.
+    Full(PosU8, u16, usize),
+}
+
+/// advances the depth-first traversal on `stack` to the next leaf or Full node, visiting the children of each
+/// Mixed node in octant order. Full nodes are popped when they are returned, so they are never visited again.
+fn next_uniform<V>(nodes: &Slab<Node<V>>, stack: &mut Vec<Frame>) -> Option<Uniform> {
+    loop {
+        let frame = stack.last_mut()?;
+        match nodes[frame.node_ptr] {
+            Node::Full(_) => {
+                let full = Uniform::Full(frame.origin, frame.half_width as u16 * 2, frame.node_ptr);
+                stack.pop();
+                return Some(full);
+            }
+            Node::Mixed(ptrs, _) => {
+                if frame.child_index == 8 {
+                    stack.pop();
+                    continue;
+                }
+                let idx = frame.child_index;
+                frame.child_index += 1;
+                let ptr = ptrs[idx];
+                if ptr == EMPTY_PTR {
+                    continue;
+                }
+                let child_origin = frame.origin + oct_offset(idx, frame.half_width);
+                if frame.half_width == 1 {
+                    // points to leaf
+                    return Some(Uniform::Leaf(child_origin, ptr));
+                }
+                let child_half_width = frame.half_width / 2;
+                stack.push(Frame {
+                    node_ptr: ptr,
+                    half_width: child_half_width,
+                    origin: child_origin,
+                    child_index: 0,
+                });
+            }
+        }
+    }
+}
+
+impl<'a, V, const HALF_WIDTH: u8> OctreeIter<'a, V, HALF_WIDTH> {
+    pub(crate) fn new(octree: &'a Octree<V, HALF_WIDTH>) -> Self {
+        OctreeIter {
+            octree,
+            stack: vec![Frame::root(HALF_WIDTH)],
+            full: None,
+        }
+    }
+}
+
+impl<'a, V: Clone, const HALF_WIDTH: u8> Iterator for OctreeIter<'a, V, HALF_WIDTH> {
+    type Item = (PosU8, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(full) = &mut self.full {
+                if let Some(voxel) = full.next_voxel() {
+                    return Some(voxel);
+                }
+                self.full = None;
+            }
+            match next_uniform(&self.octree.nodes, &mut self.stack)? {
+                Uniform::Leaf(pos, ptr) => return Some((pos, self.octree.leafs[ptr].clone())),
+                Uniform::Full(origin, width, node_ptr) => {
+                    let Node::Full(ref val) = self.octree.nodes[node_ptr] else {
+                        unreachable!("next_uniform returns Full nodes");
+                    };
+                    self.full = Some(FullRegion {
+                        origin,
+                        width,
+                        val: val.clone(),
+                        next: 0,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Owning iterator over all set voxels of an [`Octree`], moving the values out of the tree.
+///
+/// Leafs are moved out, Full nodes are expanded into clones of their value.
+pub struct OctreeIntoIter<V, const HALF_WIDTH: u8> {
+    octree: Octree<V, HALF_WIDTH>,
+    /// nodes on the path from the root to the node currently visited.
+    stack: Vec<Frame>,
+    /// the Full node currently being expanded.
+    full: Option<FullRegion<V>>,
+}
+
+impl<V: Clone, const HALF_WIDTH: u8> Iterator for OctreeIntoIter<V, HALF_WIDTH> {
+    type Item = (PosU8, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(full) = &mut self.full {
+                if let Some(voxel) = full.next_voxel() {
+                    return Some(voxel);
+                }
+                self.full = None;
+            }
+            match next_uniform(&self.octree.nodes, &mut self.stack)? {
+                Uniform::Leaf(pos, ptr) => return Some((pos, self.octree.leafs.remove(ptr))),
+                Uniform::Full(origin, width, node_ptr) => {
+                    // the node is never visited again, so its value can be moved out.
+                    let Node::Full(val) =
+                        std::mem::replace(&mut self.octree.nodes[node_ptr], Node::empty())
+                    else {
+                        unreachable!("next_uniform returns Full nodes");
+                    };
+                    self.full = Some(FullRegion {
+                        origin,
+                        width,
+                        val,
+                        next: 0,
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl<V: Clone, const HALF_WIDTH: u8> IntoIterator for Octree<V, HALF_WIDTH> {
+    type Item = (PosU8, V);
+    type IntoIter = OctreeIntoIter<V, HALF_WIDTH>;
+
+    /// yields the voxels in the same order as [`Octree::iter`].
+    fn into_iter(self) -> Self::IntoIter {
+        OctreeIntoIter {
+            octree: self,
+            stack: vec![Frame::root(HALF_WIDTH)],
+            full: None,
+        }
+    }
+}
+
+impl<'a, V: Clone + PartialEq, const HALF_WIDTH: u8> IntoIterator for &'a Octree<V, HALF_WIDTH> {
+    type Item = (PosU8, V);
+    type IntoIter = OctreeIter<'a, V, HALF_WIDTH>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the uniform regions of an [`Octree`], see [`Octree::iter_regions`].
+pub struct OctreeRegionIter<'a, V, const HALF_WIDTH: u8> {
+    octree: &'a Octree<V, HALF_WIDTH>,
+    /// nodes on the path from the root to the node currently visited.
+    stack: Vec<Frame>,
+}
+
+impl<'a, V, const HALF_WIDTH: u8> OctreeRegionIter<'a, V, HALF_WIDTH> {
+    pub(crate) fn new(octree: &'a Octree<V, HALF_WIDTH>) -> Self {
+        OctreeRegionIter {
+            octree,
+            stack: vec![Frame::root(HALF_WIDTH)],
+        }
+    }
+}
+
+impl<'a, V: Clone, const HALF_WIDTH: u8> Iterator for OctreeRegionIter<'a, V, HALF_WIDTH> {
+    /// (origin, width, value)
+    type Item = (PosU8, u16, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match next_uniform(&self.octree.nodes, &mut self.stack)? {
+            Uniform::Leaf(pos, ptr) => Some((pos, 1, self.octree.leafs[ptr].clone())),
+            Uniform::Full(origin, width, node_ptr) => {
+                let Node::Full(ref val) = self.octree.nodes[node_ptr] else {
+                    unreachable!("next_uniform returns Full nodes");
+                };
+                Some((origin, width, val.clone()))
+            }
+        }
+    }
+}
+
+/// Iterator over the set voxels inside of a box, see [`Octree::iter_box`].
+///
+/// Octants that do not intersect the box are never descended into.
+pub struct OctreeBoxIter<'a, V, const HALF_WIDTH: u8> {
+    octree: &'a Octree<V, HALF_WIDTH>,
+    min: PosU8,
+    max: PosU8,
+    /// nodes on the path from the root to the node currently visited.
+    stack: Vec<Frame>,
+    /// the part of a Full node inside of the box that is currently being expanded.
+    full: Option<ClippedRegion<V>>,
+    /// number of nodes pushed onto the stack so far.
+    visited_nodes: usize,
+}
+
+struct ClippedRegion<V> {
+    min: PosU8,
+    size: [u32; 3],
+    val: V,
+    /// index of the next voxel in the region, x changes fastest.
+    next: u32,
+}
+
+impl<'a, V, const HALF_WIDTH: u8> OctreeBoxIter<'a, V, HALF_WIDTH> {
+    pub(crate) fn new(octree: &'a Octree<V, HALF_WIDTH>, min: PosU8, max: PosU8) -> Self {
+        let box_is_empty = min.x > max.x || min.y > max.y || min.z > max.z;
+        let stack = if box_is_empty {
+            vec![]
+        } else {
+            vec![Frame::root(HALF_WIDTH)]
+        };
+        OctreeBoxIter {
+            octree,
+            min,
+            max,
+            visited_nodes: stack.len(),
+            stack,
+            full: None,
+        }
+    }
+}
+
+impl<'a, V: Clone, const HALF_WIDTH: u8> Iterator for OctreeBoxIter<'a, V, HALF_WIDTH> {
+    type Item = (PosU8, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(full) = &mut self.full {
+                let [size_x, size_y, size_z] = full.size;
+                if full.next < size_x * size_y * size_z {
+                    let offset = PosU8 {
+                        x: (full.next % size_x) as u8,
+                        y: (full.next / size_x % size_y) as u8,
+                        z: (full.next / (size_x * size_y)) as u8,
+                    };
+                    full.next += 1;
+                    return Some((full.min + offset, full.val.clone()));
+                }
+                self.full = None;
+            }
+
+            let frame = self.stack.last_mut()?;
+            match self.octree.nodes[frame.node_ptr] {
+                Node::Full(ref val) => {
+                    let width = frame.half_width as u16 * 2;
+                    let last = frame.origin + PosU8::from([(width - 1) as u8; 3]);
+                    let min = frame.origin.max(self.min);
+                    let max = last.min(self.max);
+                    self.full = Some(ClippedRegion {
+                        min,
+                        size: [
+                            (max.x - min.x) as u32 + 1,
+                            (max.y - min.y) as u32 + 1,
+                            (max.z - min.z) as u32 + 1,
+                        ],
+                        val: val.clone(),
+                        next: 0,
+                    });
+                    self.stack.pop();
+                }
+                Node::Mixed(ptrs, _) => {
+                    if frame.child_index == 8 {
+                        self.stack.pop();
+                        continue;
+                    }
+                    let idx = frame.child_index;
+                    frame.child_index += 1;
+                    let ptr = ptrs[idx];
+                    if ptr == EMPTY_PTR {
+                        continue;
+                    }
+                    let child_origin = frame.origin + oct_offset(idx, frame.half_width);
+                    let coverage =
+                        box_coverage(self.min, self.max, child_origin, frame.half_width as u16);
+                    if coverage == Coverage::Outside {
+                        continue;
+                    }
+                    if frame.half_width == 1 {
+                        // points to leaf
+                        return Some((child_origin, self.octree.leafs[ptr].clone()));
+                    }
+                    let child_half_width = frame.half_width / 2;
+                    self.stack.push(Frame {
+                        node_ptr: ptr,
+                        half_width: child_half_width,
+                        origin: child_origin,
+                        child_index: 0,
+                    });
+                    self.visited_nodes += 1;
+                }
+            }
+        }
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// calls `f(origin, width, value)` once for each Full node and once for each leaf (with a width of 1),
+    /// like [`Octree::iter_regions`] but without cloning values.
+    pub fn for_each<F: FnMut(PosU8, u16, &V)>(&self, mut f: F) {
+        self.for_each_in_node(0, PosU8::ZERO, HALF_WIDTH, &mut f);
+    }
+
+    pub(crate) fn for_each_in_node<F: FnMut(PosU8, u16, &V)>(
+        &self,
+        node_ptr: usize,
+        origin: PosU8,
+        half_width: u8,
+        f: &mut F,
+    ) {
+        let _ =
+            self.try_for_each_in_node(node_ptr, origin, half_width, &mut |origin, width, val| {
+                f(origin, width, val);
+                ControlFlow::<()>::Continue(())
+            });
+    }
+
+    /// like [`Octree::for_each`], but stops the traversal as soon as `f` returns [`ControlFlow::Break`],
+    /// e.g. to check if any voxel has some value without visiting the rest of the tree.
+    pub fn try_for_each<B, F: FnMut(PosU8, u16, &V) -> ControlFlow<B>>(
+        &self,
+        mut f: F,
+    ) -> ControlFlow<B> {
+        self.try_for_each_in_node(0, PosU8::ZERO, HALF_WIDTH, &mut f)
+    }
+
+    fn try_for_each_in_node<B, F: FnMut(PosU8, u16, &V) -> ControlFlow<B>>(
+        &self,
+        node_ptr: usize,
+        origin: PosU8,
+        half_width: u8,
+        f: &mut F,
+    ) -> ControlFlow<B> {
+        match self.nodes[node_ptr] {
+            Node::Full(ref val) => f(origin, half_width as u16 * 2, val),
+            Node::Mixed(ptrs, _) => {
+                for (i, ptr) in ptrs.into_iter().enumerate() {
+                    if ptr == EMPTY_PTR {
+                        continue;
+                    }
+                    let child_origin = origin + oct_offset(i, half_width);
+                    if half_width == 1 {
+                        f(child_origin, 1, &self.leafs[ptr])?;
+                    } else {
+                        self.try_for_each_in_node(ptr, child_origin, half_width / 2, f)?;
+                    }
+                }
+                ControlFlow::Continue(())
+            }
+        }
+    }
+
+    /// calls `f(pos, value)` for every set voxel, Full nodes are expanded into all of their voxels.
+    pub fn for_each_voxel<F: FnMut(PosU8, &V)>(&self, mut f: F) {
+        self.for_each(|origin, width, val| {
+            for z in 0..width {
+                for y in 0..width {
+                    for x in 0..width {
+                        let offset = PosU8::new(x as u8, y as u8, z as u8);
+                        f(origin + offset, val);
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::ops::ControlFlow;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn iter_leafs() {
+        let mut octree = Octree::<u32, 16>::new();
+        let mut expected = vec![
+            (pos!(0, 0, 0), 1),
+            (pos!(31, 31, 31), 2),
+            (pos!(5, 17, 3), 3),
+            (pos!(5, 17, 4), 4),
+            (pos!(16, 0, 15), 5),
+        ];
+        for (pos, val) in expected.iter() {
+            octree.insert(*pos, *val);
+        }
+        let mut voxels: Vec<(PosU8, u32)> = octree.iter().collect();
+        voxels.sort();
+        expected.sort();
+        assert_eq!(voxels, expected);
+
+        assert_eq!(Octree::<u32, 16>::new().iter().count(), 0);
+    }
+
+    #[test]
+    pub fn iter_expands_full_nodes() {
+        let mut octree = Octree::<u32, 16>::new();
+        let mut expected = vec![];
+        for x in 8..16 {
+            for y in 0..8 {
+                for z in 8..16 {
+                    octree.insert(pos!(x, y, z), 7);
+                    expected.push((pos!(x, y, z), 7));
+                }
+            }
+        }
+        octree.insert(pos!(3, 3, 3), 8);
+        expected.push((pos!(3, 3, 3), 8));
+        assert_eq!(octree.leafs.len(), 1);
+
+        let mut voxels: Vec<(PosU8, u32)> = octree.iter().collect();
+        voxels.sort();
+        expected.sort();
+        assert_eq!(voxels, expected);
+    }
+
+    #[test]
+    pub fn iter_regions() {
+        let mut octree = Octree::<u32, 16>::new();
+        for x in 0..32 {
+            for y in 0..32 {
+                for z in 0..32 {
+                    octree.insert(pos!(x, y, z), 7);
+                }
+            }
+        }
+        // the whole tree is a single Full node:
+        let regions: Vec<(PosU8, u16, u32)> = octree.iter_regions().collect();
+        assert_eq!(regions, vec![(PosU8::ZERO, 32, 7)]);
+    }
+
+    #[test]
+    pub fn iter_regions_mixed() {
+        let mut octree = Octree::<u32, 16>::new();
+        for x in 8..16 {
+            for y in 0..8 {
+                for z in 8..16 {
+                    octree.insert(pos!(x, y, z), 7);
+                }
+            }
+        }
+        octree.insert(pos!(3, 3, 3), 8);
+
+        let mut regions: Vec<(PosU8, u16, u32)> = octree.iter_regions().collect();
+        regions.sort();
+        assert_eq!(regions, vec![(pos!(3, 3, 3), 1, 8), (pos!(8, 0, 8), 8, 7)]);
+        assert_eq!(Octree::<u32, 16>::new().iter_regions().count(), 0);
+    }
+
+    #[test]
+    pub fn into_iter_matches_iter() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<String, 8>::new();
+        for _ in 0..300 {
+            let pos = pos!(
+                rng.gen_range(0..16),
+                rng.gen_range(0..16),
+                rng.gen_range(0..16)
+            );
+            octree.insert(pos, format!("block {}", rng.gen_range(0..3)));
+        }
+        octree.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), "stone".to_string());
+
+        let borrowed: Vec<(PosU8, String)> = octree.iter().collect();
+        let by_ref: Vec<(PosU8, String)> = (&octree).into_iter().collect();
+        assert_eq!(by_ref, borrowed);
+        let owned: Vec<(PosU8, String)> = octree.into_iter().collect();
+        assert_eq!(owned, borrowed);
+        assert_eq!(owned.len(), borrowed.len());
+
+        let empty = Octree::<String, 8>::new();
+        assert_eq!(empty.into_iter().count(), 0);
+    }
+
+    #[test]
+    pub fn try_for_each_stops_early() {
+        let mut octree = Octree::<u32, 16>::new();
+        for x in 0..32 {
+            octree.insert(pos!(x, x, x), 1);
+            octree.insert(pos!(x, 31 - x, x), 2);
+        }
+        octree.insert(pos!(1, 0, 0), 3);
+
+        let mut visited = 0;
+        let found = octree.try_for_each(|origin, _, val| {
+            visited += 1;
+            if *val == 3 {
+                ControlFlow::Break(origin)
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(found, ControlFlow::Break(pos!(1, 0, 0)));
+        assert!(visited < octree.node_count());
+        assert!(visited < octree.leaf_count());
+
+        let mut visited = 0;
+        let found = octree.try_for_each(|_, _, val| {
+            visited += 1;
+            if *val == 4 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(found, ControlFlow::Continue(()));
+        assert_eq!(visited, octree.leaf_count());
+    }
+
+    #[test]
+    pub fn for_each() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.fill_box(pos!(3, 1, 7), pos!(20, 9, 30), 1);
+        octree.fill_sphere(pos!(25, 25, 25), 5, 2);
+        octree.insert(pos!(0, 31, 0), 3);
+
+        let mut volume: u64 = 0;
+        let mut regions = vec![];
+        octree.for_each(|origin, width, val| {
+            volume += (width as u64).pow(3);
+            regions.push((origin, width, *val));
+        });
+        assert_eq!(volume, octree.len());
+        assert_eq!(regions, octree.iter_regions().collect::<Vec<_>>());
+
+        let mut voxels = vec![];
+        octree.for_each_voxel(|pos, val| voxels.push((pos, *val)));
+        let mut expected: Vec<(PosU8, u32)> = octree.iter().collect();
+        voxels.sort();
+        expected.sort();
+        assert_eq!(voxels, expected);
+    }
+
+    #[test]
+    pub fn iter_box() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 16>::new();
+        for _ in 0..2000 {
+            let pos = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            octree.insert(pos, rng.gen_range(0..3));
+        }
+        octree.fill_box(pos!(16, 16, 16), pos!(31, 31, 31), 7);
+
+        let (min, max) = (pos!(3, 10, 12), pos!(20, 17, 29));
+        let inside = |pos: PosU8| pos.min(min) == min && pos.max(max) == max;
+        let mut expected: Vec<(PosU8, u32)> =
+            octree.iter().filter(|(pos, _)| inside(*pos)).collect();
+        let mut found: Vec<(PosU8, u32)> = octree.iter_box(min, max).collect();
+        expected.sort();
+        found.sort();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    pub fn iter_box_prunes_octants() {
+        let mut octree = Octree::<u32, 16>::new();
+        // one voxel in every octant of the root:
+        for i in 0..8 {
+            let offset = crate::oct_offset(i, 16);
+            octree.insert(offset + pos!(1, 1, 1), i as u32);
+        }
+        let mut iter = octree.iter_box(pos!(0, 0, 0), pos!(3, 3, 3));
+        assert_eq!(iter.next(), Some((pos!(1, 1, 1), 0)));
+        assert_eq!(iter.next(), None);
+        // only the root and the nodes on the path to the voxel in octant 0:
+        assert_eq!(iter.visited_nodes, 5);
+        assert!(iter.visited_nodes < octree.node_count());
+    }
+}
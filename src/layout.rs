@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use slab::Slab;
+
+use crate::{Node, Octree, Ptr, EMPTY_PTR};
+
+/// mirrors the layout of a slot in a [`Slab`], which is either a value or the index of the next vacant slot.
+#[allow(dead_code)]
+enum SlabSlot<T> {
+    Vacant(usize),
+    Occupied(T),
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// creates an empty tree with room for `nodes` nodes and `leafs` values before reallocating.
+    pub fn with_capacity(nodes: usize, leafs: usize) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::HALF_WIDTH_IS_POWER_OF_TWO;
+        let mut octree = Octree {
+            nodes: Slab::with_capacity(nodes.max(1)),
+            leafs: Slab::with_capacity(leafs),
+            dirty: None,
+        };
+        octree.clear();
+        octree
+    }
+
+    /// the capacities of the node and leaf slabs, `(nodes, leafs)`.
+    pub fn capacity(&self) -> (usize, usize) {
+        (self.nodes.capacity(), self.leafs.capacity())
+    }
+
+    /// an estimate of the heap memory used by the tree in bytes, including unused capacity.
+    ///
+    /// This is only an estimate: it assumes each slab slot is an enum of the value and a free list index, and it does not count
+    /// heap memory owned by the values themselves, e.g. the contents of a `String`.
+    pub fn memory_bytes(&self) -> usize {
+        let (nodes, leafs) = self.capacity();
+        nodes * std::mem::size_of::<SlabSlot<Node<V>>>()
+            + leafs * std::mem::size_of::<SlabSlot<V>>()
+    }
+
+    /// moves all nodes and leafs to the front of their slabs, filling the holes left by removals,
+    /// and releases the unused capacity.
+    pub fn shrink_to_fit(&mut self) {
+        let mut node_moves: HashMap<Ptr, Ptr> = HashMap::new();
+        let mut leaf_moves: HashMap<Ptr, Ptr> = HashMap::new();
+        self.nodes.compact(|_, from, to| {
+            node_moves.insert(from, to);
+            true
+        });
+        self.leafs.compact(|_, from, to| {
+            leaf_moves.insert(from, to);
+            true
+        });
+        // the root is never moved, because slot 0 is always occupied.
+        self.rekey_subtree(0, HALF_WIDTH, &node_moves, &leaf_moves);
+        self.nodes.shrink_to_fit();
+        self.leafs.shrink_to_fit();
+    }
+
+    /// rebuilds the slabs such that the children of each node are stored next to each other,
+    /// with the nodes ordered depth-first from the root. This improves the cache locality of
+    /// traversals after lots of random edits, and also removes all holes from the slabs.
+    pub fn compact(&mut self) {
+        let mut compacted = Octree {
+            nodes: Slab::with_capacity(self.nodes.len()),
+            leafs: Slab::with_capacity(self.leafs.len()),
+            // compacting moves no voxel, so the dirty cubes stay dirty.
+            dirty: self.dirty.take(),
+        };
+        let root = match self.nodes[0] {
+            Node::Full(ref val) => Node::Full(val.clone()),
+            // placeholder, the children are filled in below.
+            Node::Mixed(..) => Node::empty(),
+        };
+        let root_ptr = compacted.nodes.insert(root);
+        assert_eq!(root_ptr, 0);
+        if let Node::Mixed(ptrs, _) = self.nodes[0] {
+            self.compact_children(&ptrs, HALF_WIDTH, 0, &mut compacted);
+        }
+        *self = compacted;
+    }
+
+    /// copies the children `ptrs` of a node with `half_width` into `compacted`,
+    /// where the already copied node is at `compacted_ptr`.
+    fn compact_children(
+        &self,
+        ptrs: &[Ptr; 8],
+        half_width: u8,
+        compacted_ptr: Ptr,
+        compacted: &mut Self,
+    ) {
+        // first insert all children next to each other, then descend into them.
+        let mut compacted_ptrs = [EMPTY_PTR; 8];
+        for (ptr, compacted_child) in ptrs.iter().zip(compacted_ptrs.iter_mut()) {
+            if *ptr == EMPTY_PTR {
+                continue;
+            }
+            *compacted_child = if half_width == 1 {
+                compacted.leafs.insert(self.leafs[*ptr].clone())
+            } else {
+                let node = match self.nodes[*ptr] {
+                    Node::Full(ref val) => Node::Full(val.clone()),
+                    Node::Mixed(..) => Node::empty(),
+                };
+                compacted.nodes.insert(node)
+            };
+        }
+        compacted.nodes[compacted_ptr] = Node::from_ptrs(compacted_ptrs);
+        if half_width == 1 {
+            return;
+        }
+        for (ptr, compacted_child) in ptrs.iter().zip(compacted_ptrs) {
+            if *ptr == EMPTY_PTR {
+                continue;
+            }
+            if let Node::Mixed(child_ptrs, _) = self.nodes[*ptr] {
+                self.compact_children(&child_ptrs, half_width / 2, compacted_child, compacted);
+            }
+        }
+    }
+
+    /// rewrites the pointers of the node at `node_ptr` and all nodes below it to the moved locations.
+    fn rekey_subtree(
+        &mut self,
+        node_ptr: Ptr,
+        half_width: u8,
+        node_moves: &HashMap<Ptr, Ptr>,
+        leaf_moves: &HashMap<Ptr, Ptr>,
+    ) {
+        let moved = |moves: &HashMap<Ptr, Ptr>, ptr: Ptr| *moves.get(&ptr).unwrap_or(&ptr);
+        match self.nodes[node_ptr] {
+            // the value is stored inline, there is no pointer to rewrite.
+            Node::Full(_) => {}
+            Node::Mixed(ptrs, _) => {
+                let child_moves = if half_width == 1 {
+                    leaf_moves
+                } else {
+                    node_moves
+                };
+                let ptrs = ptrs.map(|ptr| {
+                    if ptr == EMPTY_PTR {
+                        ptr
+                    } else {
+                        moved(child_moves, ptr)
+                    }
+                });
+                self.nodes[node_ptr] = Node::from_ptrs(ptrs);
+                if half_width > 1 {
+                    for ptr in ptrs {
+                        if ptr != EMPTY_PTR {
+                            self.rekey_subtree(ptr, half_width / 2, node_moves, leaf_moves);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn with_capacity() {
+        let mut octree = Octree::<u32, 16>::with_capacity(100, 200);
+        assert!(octree.nodes.capacity() >= 100);
+        assert!(octree.leafs.capacity() >= 200);
+        assert!(octree.is_empty());
+        octree.insert(pos!(1, 2, 3), 4);
+        assert_eq!(octree.get(pos!(1, 2, 3)), Some(4));
+    }
+
+    #[test]
+    pub fn shrink_to_fit() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 16>::new();
+        let mut positions = vec![];
+        for _ in 0..3000 {
+            let pos = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            octree.insert(pos, rng.gen_range(0..4));
+            positions.push(pos);
+        }
+        for pos in positions.iter().step_by(2) {
+            octree.remove(*pos);
+        }
+        octree.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), 9);
+        let before = octree.clone();
+        let nodes_capacity = octree.nodes.capacity();
+        let leafs_capacity = octree.leafs.capacity();
+
+        octree.shrink_to_fit();
+        assert!(octree == before);
+        assert_eq!(octree.encode(), before.encode());
+        assert_eq!(octree.nodes.capacity(), octree.node_count());
+        assert_eq!(octree.leafs.capacity(), octree.leaf_count());
+        assert!(octree.nodes.capacity() < nodes_capacity);
+        assert!(octree.leafs.capacity() < leafs_capacity);
+        for x in 0..32 {
+            for y in 0..32 {
+                for z in 0..32 {
+                    assert_eq!(octree.get(pos!(x, y, z)), before.get(pos!(x, y, z)));
+                }
+            }
+        }
+
+        // the tree stays usable:
+        octree.insert(pos!(31, 31, 31), 100);
+        octree.remove(pos!(0, 0, 0));
+        assert_eq!(octree.get(pos!(31, 31, 31)), Some(100));
+        assert_eq!(octree.get(pos!(0, 0, 0)), None);
+    }
+
+    #[test]
+    pub fn compact() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 16>::new();
+        for _ in 0..5 {
+            for _ in 0..1000 {
+                let pos = pos!(
+                    rng.gen_range(0..32),
+                    rng.gen_range(0..32),
+                    rng.gen_range(0..32)
+                );
+                if rng.gen_bool(0.3) {
+                    octree.remove(pos);
+                } else {
+                    octree.insert(pos, rng.gen_range(0..3));
+                }
+            }
+        }
+        octree.fill_box(pos!(16, 0, 0), pos!(23, 7, 7), 4);
+        let before = octree.clone();
+
+        octree.compact();
+        assert!(octree == before);
+        assert_eq!(octree.encode(), before.encode());
+        // no holes left:
+        assert_eq!(octree.nodes.capacity(), octree.node_count());
+        assert_eq!(octree.leafs.capacity(), octree.leaf_count());
+        let max_node_ptr = octree.nodes.iter().map(|(ptr, _)| ptr).max().unwrap();
+        assert_eq!(max_node_ptr, octree.node_count() - 1);
+
+        // the children of every node are stored next to each other:
+        for (_, node) in octree.nodes.iter() {
+            if let crate::Node::Mixed(ptrs, _) = node {
+                let children: Vec<usize> = ptrs
+                    .iter()
+                    .cloned()
+                    .filter(|ptr| *ptr != crate::EMPTY_PTR)
+                    .collect();
+                for pair in children.windows(2) {
+                    assert_eq!(pair[0] + 1, pair[1]);
+                }
+            }
+        }
+
+        let mut full = Octree::<u32, 16>::new();
+        full.fill_box(PosU8::ZERO, pos!(31, 31, 31), 1);
+        full.compact();
+        assert_eq!(full.get(pos!(4, 5, 6)), Some(1));
+        assert_eq!(full.node_count(), 1);
+    }
+
+    #[test]
+    pub fn memory_bytes() {
+        let mut octree = Octree::<u32, 16>::new();
+        let empty_bytes = octree.memory_bytes();
+        assert!(empty_bytes > 0);
+        for x in 0..32 {
+            octree.insert(pos!(x, x, x), x as u32);
+        }
+        let (nodes, leafs) = octree.capacity();
+        assert!(nodes >= octree.node_count());
+        assert!(leafs >= octree.leaf_count());
+        let full_bytes = octree.memory_bytes();
+        assert!(full_bytes > empty_bytes);
+
+        for x in 0..16 {
+            octree.remove(pos!(x, x, x));
+        }
+        assert_eq!(octree.memory_bytes(), full_bytes);
+        octree.shrink_to_fit();
+        assert!(octree.memory_bytes() < full_bytes);
+        assert_eq!(
+            octree.capacity(),
+            (octree.node_count(), octree.leaf_count())
+        );
+    }
+}
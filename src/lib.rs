@@ -1,29 +1,100 @@
-use std::{collections::VecDeque, ops::Deref};
+use std::collections::HashMap;
 
 use slab::Slab;
 
-use pos::PosU8;
+pub use cursor::OctreeCursor;
+pub use dense::DenseError;
+pub use encoding::{DecodeError, EncodedNode, LeBytes, MAGIC};
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+pub use iter::{OctreeBoxIter, OctreeIntoIter, OctreeIter, OctreeRegionIter};
+pub use lod::LodMode;
+pub use mesh::{Face, Quad};
+pub use node_ref::NodeRef;
+pub use pos::{PosOutOfRange, PosU8};
+pub use sentinel::{OctreeSentinel, Voxel};
+pub use stats::OctreeStats;
+pub use subtree::RegionValue;
+pub use wide::{OctreeU16, PosU16};
 
+mod automaton;
+mod batch;
+mod column;
+mod compare;
+mod cursor;
+mod dense;
+mod dirty;
+mod encoding;
+mod entry;
+pub mod geom;
+mod iter;
+mod layout;
+mod lod;
+mod mesh;
+mod node_ref;
+mod parallel;
 mod pos;
+mod raycast;
+mod region;
+mod resize;
+mod rle;
+mod sentinel;
+mod stats;
+mod subtree;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod transform;
+mod validate;
+mod wide;
 
 type Ptr = usize;
 
 const EMPTY_PTR: Ptr = usize::MAX;
 
+/// Error returned by the `try_*` methods of the [`Octree`] for positions outside of the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfBounds {
+    pub pos: PosU8,
+    /// side width of the tree, all coordinates need to be smaller than this.
+    pub side: u16,
+}
+
+impl std::fmt::Display for OutOfBounds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "pos {:?} out of bounds for octree of side {}",
+            self.pos, self.side
+        )
+    }
+}
+
+impl std::error::Error for OutOfBounds {}
+
 /// `V` is the type of voxel stored in the tree.
 ///
 /// `HALF_WIDTH` is half of the cube side width of the tree.
 /// E.g. if the tree should span a volume of 256x256x256, then the `HALF_WIDTH` = 128.
-#[derive(Debug, Clone)]
+///
+/// `HALF_WIDTH` has to be a power of two, such that it can be halved down to 1. Anything else fails to compile:
+///
+/// ```compile_fail
+/// let octree = voxel_octree::Octree::<u32, 10>::new();
+/// ```
+#[derive(Clone)]
 pub struct Octree<V, const HALF_WIDTH: u8> {
     // the first element of the octree is always at ptr = 0 in the slab
-    nodes: Slab<Node>,
+    nodes: Slab<Node<V>>,
+    /// values of the voxels that are not covered by a Full node, the children of Mixed nodes with half width 1.
     leafs: Slab<V>,
+    /// voxels edited since the last [`Octree::take_dirty_regions`] as a tree of their own, which merges them
+    /// into aligned cubes. `None` unless dirty tracking is enabled.
+    dirty: Option<Box<Octree<(), HALF_WIDTH>>>,
 }
 
 #[derive(Debug, Clone, Copy)]
-pub enum Node {
-    Full(Ptr),
+pub enum Node<V> {
+    /// every voxel of the node has this value, stored inline instead of in a leaf.
+    Full(V),
     Mixed(
         /// each index is one of 8 space partitions
         /// -x-y-z: 0
@@ -35,40 +106,143 @@ pub enum Node {
         /// +x+y-z: 6
         /// +x+y+z: 7
         [Ptr; 8],
-        // non_empty_ptrs: usize,
+        /// presence mask, bit `i` is set if child `i` is not empty.
+        /// Always built by [`Node::from_ptrs`], so it can not get out of sync with the pointers.
+        u8,
     ),
 }
 
-impl Node {
+impl<V> Node<V> {
     pub fn empty() -> Self {
-        Node::Mixed([EMPTY_PTR; 8])
+        Node::Mixed([EMPTY_PTR; 8], 0)
+    }
+
+    /// a `Mixed` node with the children `ptrs` and the matching presence mask.
+    pub fn from_ptrs(ptrs: [Ptr; 8]) -> Self {
+        let mut mask: u8 = 0;
+        for (i, ptr) in ptrs.iter().enumerate() {
+            if *ptr != EMPTY_PTR {
+                mask |= 1 << i;
+            }
+        }
+        Node::Mixed(ptrs, mask)
     }
 
     pub fn new_from_ptr(ptr: Ptr, ptr_index: usize) -> Self {
         let mut ptrs = [EMPTY_PTR; 8];
         ptrs[ptr_index] = ptr;
-        Node::Mixed(ptrs)
+        Node::from_ptrs(ptrs)
+    }
+
+    /// true for a `Mixed` node where all 8 children are empty.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Node::Full(_) => false,
+            Node::Mixed(_, mask) => *mask == 0,
+        }
+    }
+}
+
+/// inverse of the oct_index calculation: offset of the child node at `idx` relative to the origin of its parent node.
+fn oct_offset(idx: usize, half_width: u8) -> PosU8 {
+    PosU8 {
+        x: if idx & 4 != 0 { half_width } else { 0 },
+        y: if idx & 2 != 0 { half_width } else { 0 },
+        z: if idx & 1 != 0 { half_width } else { 0 },
     }
 }
 
 impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
 where
-    V: Copy + PartialEq + std::fmt::Debug,
+    V: Clone + PartialEq,
 {
+    /// side width of the cube spanned by the tree.
+    pub const SIDE: u16 = HALF_WIDTH as u16 * 2;
+
+    /// evaluated by every constructor, so a tree with an invalid `HALF_WIDTH` can not be created.
+    const HALF_WIDTH_IS_POWER_OF_TWO: () = assert!(
+        HALF_WIDTH.is_power_of_two(),
+        "HALF_WIDTH of an Octree must be a power of two"
+    );
+
     pub fn new() -> Self {
-        let mut nodes = Slab::<Node>::new();
-        let root_ptr = nodes.insert(Node::Mixed([EMPTY_PTR; 8]));
+        #[allow(clippy::let_unit_value)]
+        let () = Self::HALF_WIDTH_IS_POWER_OF_TWO;
+        let mut nodes = Slab::<Node<V>>::new();
+        let root_ptr = nodes.insert(Node::empty());
         assert_eq!(root_ptr, 0);
         Octree {
             nodes,
             leafs: Slab::<V>::new(),
+            dirty: None,
         }
     }
 
+    /// a tree where every voxel is set to `val`, stored as a single Full root node.
+    /// The first edit splits the root like any other Full node, only along the path to the edited voxel.
+    pub fn filled(val: V) -> Self {
+        let mut octree = Self::new();
+        octree.nodes[0] = Node::Full(val);
+        octree
+    }
+
+    /// same as [`Octree::filled`], for starting from a solid tree that gets carved.
+    pub fn with_root_full(val: V) -> Self {
+        Self::filled(val)
+    }
+
+    /// removes all voxels, keeping the allocated capacity of the tree.
+    pub fn clear(&mut self) {
+        self.mark_dirty_cube(PosU8::ZERO, Self::SIDE);
+        self.nodes.clear();
+        self.leafs.clear();
+        let root_ptr = self.nodes.insert(Node::empty());
+        assert_eq!(root_ptr, 0);
+    }
+
+    /// true if all coordinates of `pos` are smaller than [`Self::SIDE`].
+    pub fn in_bounds(pos: PosU8) -> bool {
+        (pos.x as u16) < Self::SIDE && (pos.y as u16) < Self::SIDE && (pos.z as u16) < Self::SIDE
+    }
+
+    fn check_bounds(pos: PosU8) -> Result<(), OutOfBounds> {
+        if Self::in_bounds(pos) {
+            Ok(())
+        } else {
+            Err(OutOfBounds {
+                pos,
+                side: Self::SIDE,
+            })
+        }
+    }
+
+    /// panics with a descriptive message if `pos` lies outside of the tree.
+    #[inline]
+    fn assert_in_bounds(pos: PosU8) {
+        if let Err(err) = Self::check_bounds(pos) {
+            panic!("{err}");
+        }
+    }
+
+    /// the octant (x = 4, y = 2, z = 1) containing the absolute position `pos` of a node with `half_width`.
+    /// Nodes are aligned to their width, so the `half_width` bit of each coordinate selects the octant.
+    #[inline]
+    fn absolute_oct_index(pos: PosU8, half_width: u8) -> usize {
+        ((pos.x & half_width != 0) as usize) << 2
+            | ((pos.y & half_width != 0) as usize) << 1
+            | (pos.z & half_width != 0) as usize
+    }
+
     /// pos is modified to be the new pos in the now half sized child node
     #[inline]
     fn oct_index(pos: &mut PosU8, half_width: u8) -> usize {
-        let idx = match (pos.x < half_width, pos.y < half_width, pos.z < half_width) {
+        debug_assert!(
+            (pos.x as u16) < half_width as u16 * 2
+                && (pos.y as u16) < half_width as u16 * 2
+                && (pos.z as u16) < half_width as u16 * 2,
+            "pos {pos:?} out of bounds for node of half width {half_width}"
+        );
+        match (pos.x < half_width, pos.y < half_width, pos.z < half_width) {
             (true, true, true) => 0,
             (true, true, false) => {
                 pos.z -= half_width;
@@ -103,21 +277,62 @@ where
                 pos.z -= half_width;
                 7
             }
-        };
-        println!("calculate oct_index: pos: {pos:?} {half_width:?} -> {idx}");
-        return idx;
+        }
     }
 
-    pub fn get(&mut self, mut pos: PosU8) -> Option<V> {
-        let mut node_ptr: usize = 0;
-        let mut half_width: u8 = HALF_WIDTH;
+    /// the octant indices (x = 4, y = 2, z = 1) of the nodes from the root down to the voxel at `pos`, one per level.
+    ///
+    /// The path only depends on `pos`, not on the current structure of the tree: it always has `log2(2 * HALF_WIDTH)`
+    /// entries, even if `pos` lies inside of a Full node or an empty octant higher up.
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn get_path(&self, mut pos: PosU8) -> Vec<usize> {
+        Self::assert_in_bounds(pos);
+        let mut path = Vec::with_capacity(Self::SIDE.trailing_zeros() as usize);
+        let mut half_width = HALF_WIDTH;
+        while half_width > 0 {
+            path.push(Self::oct_index(&mut pos, half_width));
+            half_width /= 2;
+        }
+        path
+    }
+
+    /// like [`Octree::get`], but returns an error instead of panicking if `pos` is out of bounds.
+    pub fn try_get(&self, pos: PosU8) -> Result<Option<V>, OutOfBounds> {
+        Self::check_bounds(pos)?;
+        Ok(self.get(pos))
+    }
+
+    /// returns a clone of the value at `pos`, see [`Octree::get_ref`] to avoid the clone.
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn get(&self, pos: PosU8) -> Option<V> {
+        self.get_ref(pos).cloned()
+    }
+
+    /// true if the voxel at `pos` is set, without cloning its value.
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn contains(&self, pos: PosU8) -> bool {
+        self.get_ref(pos).is_some()
+    }
+
+    /// returns a reference to the value at `pos`, for voxels inside a Full node this is the value of the Full node.
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn get_ref(&self, pos: PosU8) -> Option<&V> {
+        Self::assert_in_bounds(pos);
+        self.get_ref_below(0, HALF_WIDTH, pos)
+    }
+
+    /// the value at `pos`, relative to the Mixed or Full node at `node_ptr` with `half_width`.
+    fn get_ref_below(&self, mut node_ptr: usize, mut half_width: u8, mut pos: PosU8) -> Option<&V> {
         loop {
-            let node = self.nodes[node_ptr];
-            match node {
-                Node::Full(leaf_ptr) => {
-                    return Some(self.leafs[leaf_ptr]);
+            match &self.nodes[node_ptr] {
+                Node::Full(val) => {
+                    return Some(val);
                 }
-                Node::Mixed(ptrs) => {
+                Node::Mixed(ptrs, _) => {
                     let idx = Self::oct_index(&mut pos, half_width);
                     // ptr points to node or leaf
                     let ptr = ptrs[idx];
@@ -125,7 +340,7 @@ where
                         return None;
                     } else if half_width == 1 {
                         // points to leaf
-                        return Some(self.leafs[ptr]);
+                        return Some(&self.leafs[ptr]);
                     } else {
                         // points to node
                         half_width /= 2;
@@ -149,15 +364,21 @@ where
     // }
 
     /// should be optimized to return false very quickly in 99% of cases.
+    ///
+    /// Unless all siblings of the inserted child exist, this is rejected by the presence `mask` of the node
+    /// before looking at any child.
     fn insertion_would_make_node_full(
         &self,
         ptrs: &[usize; 8],
+        mask: u8,
         insert_idx: usize,
         insert_val: &V,
         mut insert_pos: PosU8,
         mut node_half_width: u8,
     ) -> bool {
-        // dbg!(ptrs, insert_idx, insert_val, insert_pos, node_half_width);
+        if mask | (1 << insert_idx) != u8::MAX {
+            return false;
+        }
         if node_half_width == 1 {
             // Full leafs, except the one to be inserted need to be val_to_insert:
             ptrs.iter().enumerate().all(|(i, ptr)| {
@@ -170,14 +391,14 @@ where
                 if insert_node_ptr == EMPTY_PTR {
                     return false;
                 }
-                let insert_node = self.nodes[insert_node_ptr];
-                match &insert_node {
-                    Node::Full(leaf_ptr) => &self.leafs[*leaf_ptr] == insert_val,
-                    Node::Mixed(ptrs) => {
+                match &self.nodes[insert_node_ptr] {
+                    Node::Full(val) => val == insert_val,
+                    Node::Mixed(ptrs, mask) => {
                         node_half_width /= 2;
                         let oct_idx_for_insert = Self::oct_index(&mut insert_pos, node_half_width);
                         self.insertion_would_make_node_full(
                             ptrs,
+                            *mask,
                             oct_idx_for_insert,
                             insert_val,
                             insert_pos,
@@ -195,10 +416,10 @@ where
                     } else if *ptr == EMPTY_PTR {
                         false
                     } else {
-                        let Node::Full(leaf_ptr) = &self.nodes[*ptr] else {
+                        let Node::Full(val) = &self.nodes[*ptr] else {
                             return false;
                         };
-                        &self.leafs[*leaf_ptr] == insert_val
+                        val == insert_val
                     }
                 })
             };
@@ -208,73 +429,105 @@ where
         }
     }
 
+    /// returns the value of the 8 children, if all of them are leafs or Full nodes of the same value.
+    fn uniform_child_value(&self, ptrs: &[usize; 8], node_half_width: u8) -> Option<&V> {
+        let mut uniform_val: Option<&V> = None;
+        for ptr in ptrs {
+            if *ptr == EMPTY_PTR {
+                return None;
+            }
+            let val = if node_half_width == 1 {
+                &self.leafs[*ptr]
+            } else {
+                let Node::Full(val) = &self.nodes[*ptr] else {
+                    return None;
+                };
+                val
+            };
+            match uniform_val {
+                Some(uniform_val) if uniform_val != val => return None,
+                _ => uniform_val = Some(val),
+            }
+        }
+        uniform_val
+    }
+
     fn delete_mixed_child_nodes(&mut self, ptrs: &[usize; 8], node_half_width: u8) {
-        if node_half_width == 1 {
-            for ptr in ptrs {
-                if *ptr != EMPTY_PTR {
-                    self.leafs.remove(*ptr);
-                }
+        for ptr in ptrs {
+            if *ptr != EMPTY_PTR {
+                self.delete_child(*ptr, node_half_width);
             }
+        }
+    }
+
+    /// deletes the child at `ptr` of a node with `node_half_width`, together with everything below it.
+    fn delete_child(&mut self, ptr: usize, node_half_width: u8) {
+        if node_half_width == 1 {
+            self.leafs.remove(ptr);
         } else {
-            for ptr in ptrs {
-                if *ptr != EMPTY_PTR {
-                    let node = self.nodes.remove(*ptr);
-                    match node {
-                        Node::Full(leaf_ptr) => {
-                            self.leafs.remove(leaf_ptr);
-                        }
-                        Node::Mixed(ptrs) => {
-                            self.delete_mixed_child_nodes(&ptrs, node_half_width / 2)
-                        }
-                    }
-                }
+            if let Node::Mixed(ptrs, _) = self.nodes.remove(ptr) {
+                self.delete_mixed_child_nodes(&ptrs, node_half_width / 2);
             }
         }
     }
 
-    /// returns pointer to child nodes
+    /// returns pointer to child nodes.
+    ///
+    /// All children get the `majority_val`, except for the child at `insert_idx`,
+    /// which is split further until the `insert_val` can be placed at `insert_pos`.
+    /// An `insert_val` of `None` leaves the voxel at `insert_pos` empty.
     fn insert_mixed_child_nodes_for_full_node_split(
         &mut self,
         majority_val: V,
         insert_idx: usize,
-        insert_val: V,
+        insert_val: Option<V>,
         mut insert_pos: PosU8,
         node_half_with: u8,
     ) -> [usize; 8] {
-        let mut ptrs = [0; 8];
-        if node_half_with == 1 {
-            // insert 8 leafs:
-            for i in 0..8 {
-                let val = if i == insert_idx {
-                    insert_val
+        assert!(
+            node_half_with.is_power_of_two(),
+            "half width {node_half_with} of a split node is not a power of two"
+        );
+        let child_half_width = node_half_with / 2;
+        let mut insert_val = Some(insert_val);
+        std::array::from_fn(|i| {
+            if i == insert_idx {
+                // recursively split until the leaf level is reached:
+                let insert_val = insert_val.take().expect("only one child is split");
+                if child_half_width == 0 {
+                    insert_val.map_or(EMPTY_PTR, |val| self.leafs.insert(val))
                 } else {
-                    majority_val
-                };
-                println!("inserted leaf because full_node_split: {insert_val:?}");
-                ptrs[i] = self.leafs.insert(val);
-            }
-        } else {
-            // insert 7 Full nodes and recursively insert a mixed node until leaf is reached:
-            for i in 0..8 {
-                let node = if i == insert_idx {
-                    let child_insert_idx = Self::oct_index(&mut insert_pos, node_half_with);
-                    let ptrs = self.insert_mixed_child_nodes_for_full_node_split(
-                        majority_val,
+                    let child_insert_idx = Self::oct_index(&mut insert_pos, child_half_width);
+                    let child_ptrs = self.insert_mixed_child_nodes_for_full_node_split(
+                        majority_val.clone(),
                         child_insert_idx,
                         insert_val,
                         insert_pos,
-                        node_half_with / 2,
+                        child_half_width,
                     );
-                    Node::Mixed(ptrs)
-                } else {
-                    println!("inserted FullNode leaf from full_node_split: {insert_val:?}");
-                    let leaf = self.leafs.insert(majority_val);
-                    Node::Full(leaf)
-                };
-                ptrs[i] = self.nodes.insert(node);
+                    self.nodes.insert(Node::from_ptrs(child_ptrs))
+                }
+            } else {
+                self.insert_uniform_child(majority_val.clone(), child_half_width)
             }
+        })
+    }
+
+    /// moves the value out of the Full node at `node_ptr`, leaving an empty node to be replaced by the caller.
+    fn take_full_value(&mut self, node_ptr: usize) -> V {
+        match std::mem::replace(&mut self.nodes[node_ptr], Node::empty()) {
+            Node::Full(val) => val,
+            Node::Mixed(..) => unreachable!("node {node_ptr} is not Full"),
+        }
+    }
+
+    /// inserts a child of `half_width` that has `val` everywhere, a leaf for half width 0 and a Full node otherwise.
+    fn insert_uniform_child(&mut self, val: V, half_width: u8) -> usize {
+        if half_width == 0 {
+            self.leafs.insert(val)
+        } else {
+            self.nodes.insert(Node::Full(val))
         }
-        ptrs
     }
 
     // pub fn delete_node_recursively(&mut self, ptr: usize, nod)
@@ -286,18 +539,18 @@ where
         val: V,
         node_half_width: u8,
     ) -> usize {
-        // dbg!(("insert_nodes_below_empty_ptr", pos, val, node_half_width));
+        assert!(
+            node_half_width == 0 || node_half_width.is_power_of_two(),
+            "half width {node_half_width} below an empty pointer is not a power of two"
+        );
+        // same base case as insert_mixed_child_nodes_for_full_node_split: half width 0 is the leaf level.
         if node_half_width == 0 {
-            println!("insert leaf insert_nodes_below_empty_ptr {pos:?} {val:?}");
-            let leaf_ptr = self.leafs.insert(val);
-            dbg!(leaf_ptr);
-            return leaf_ptr;
+            self.leafs.insert(val)
         } else {
             let oct_idx = Self::oct_index(&mut pos, node_half_width);
             let child_ptr = self.insert_nodes_below_empty_ptr(pos, val, node_half_width / 2);
             let node = Node::new_from_ptr(child_ptr, oct_idx);
-            let node_ptr = self.nodes.insert(node);
-            node_ptr
+            self.nodes.insert(node)
         }
         // let oct_idx = Self::oct_index(&mut pos, node_half_width);
         // let ptr = if node_half_width == 1 {
@@ -312,44 +565,87 @@ where
         // node_ptr
     }
 
-    pub fn insert(&mut self, mut pos: PosU8, val: V) {
-        let original_pos = pos;
-        let mut node_ptr: usize = 0;
-        let mut half_width: u8 = HALF_WIDTH;
+    /// like [`Octree::insert`], but returns an error instead of panicking if `pos` is out of bounds.
+    pub fn try_insert(&mut self, pos: PosU8, val: V) -> Result<(), OutOfBounds> {
+        Self::check_bounds(pos)?;
+        self.insert(pos, val);
+        Ok(())
+    }
+
+    /// panics if `pos` is out of bounds.
+    pub fn insert(&mut self, pos: PosU8, val: V) {
+        Self::assert_in_bounds(pos);
+        self.mark_dirty_if_changed(pos, &val);
+        self.insert_below(0, HALF_WIDTH, pos, val);
+    }
+
+    /// like [`Octree::insert`], but returns the value that was stored at `pos` before, like [`HashMap::insert`].
+    /// Returns `None` if the voxel was empty and the value of the Full node if `pos` lay inside of one.
+    ///
+    /// panics if `pos` is out of bounds.
+    ///
+    /// [`HashMap::insert`]: std::collections::HashMap::insert
+    pub fn insert_returning_old(&mut self, pos: PosU8, val: V) -> Option<V> {
+        Self::assert_in_bounds(pos);
+        self.mark_dirty_if_changed(pos, &val);
+        self.insert_below(0, HALF_WIDTH, pos, val)
+    }
+
+    /// inserts `val` at `pos`, relative to the Mixed or Full node at `node_ptr` with `half_width`,
+    /// and returns the value that was stored there before.
+    ///
+    /// The nodes above `node_ptr` are not merged, see [`Octree::merge_path`].
+    fn insert_below(
+        &mut self,
+        mut node_ptr: usize,
+        mut half_width: u8,
+        mut pos: PosU8,
+        val: V,
+    ) -> Option<V> {
         loop {
-            let node = self.nodes[node_ptr];
-            match node {
-                Node::Full(leaf_ptr) => {
-                    let full_val = self.leafs[leaf_ptr];
-                    if full_val != val {
+            match self.nodes[node_ptr] {
+                Node::Full(ref full_val) => {
+                    if *full_val != val {
+                        let full_val = self.take_full_value(node_ptr);
+                        let old = full_val.clone();
                         let insert_idx = Self::oct_index(&mut pos, half_width);
                         // create child nodes resulting from split:
                         let child_node_ptrs = self.insert_mixed_child_nodes_for_full_node_split(
-                            full_val, insert_idx, val, pos, half_width,
+                            full_val,
+                            insert_idx,
+                            Some(val),
+                            pos,
+                            half_width,
                         );
-                        // remove the leaf:
-                        self.leafs.remove(leaf_ptr);
                         // replace the current node with a Mixed Node.
-                        self.nodes[node_ptr] = Node::Mixed(child_node_ptrs)
+                        self.nodes[node_ptr] = Node::from_ptrs(child_node_ptrs);
+                        return Some(old);
                     } else {
                         // ignore, full_val and val are the same, no edit needed
+                        return Some(val);
                     }
-                    return;
                 }
-                Node::Mixed(mut ptrs) => {
+                Node::Mixed(mut ptrs, mask) => {
                     let idx = Self::oct_index(&mut pos, half_width);
 
-                    let node_would_be_full =
-                        self.insertion_would_make_node_full(&ptrs, idx, &val, pos, half_width);
+                    let node_would_be_full = self
+                        .insertion_would_make_node_full(&ptrs, mask, idx, &val, pos, half_width);
 
                     if node_would_be_full {
+                        let ptr = ptrs[idx];
+                        let old = if ptr == EMPTY_PTR {
+                            None
+                        } else if half_width == 1 {
+                            // points to leaf
+                            Some(self.leafs[ptr].clone())
+                        } else {
+                            self.get_ref_below(ptr, half_width / 2, pos).cloned()
+                        };
                         // recursively delete Full child nodes,
                         self.delete_mixed_child_nodes(&ptrs, half_width);
                         // replace the current node with a Full Node.
-                        println!("inserted leaf because node_would_be_full: {original_pos:?},{pos:?} {val:?}");
-                        let leaf_ptr = self.leafs.insert(val);
-                        self.nodes[node_ptr] = Node::Full(leaf_ptr);
-                        return;
+                        self.nodes[node_ptr] = Node::Full(val);
+                        return old;
                     } else {
                         let ptr = ptrs[idx];
                         if ptr == EMPTY_PTR {
@@ -358,14 +654,11 @@ where
                                 self.insert_nodes_below_empty_ptr(pos, val, half_width / 2);
                             // update the node pointer in this node
                             ptrs[idx] = inserted_node_ptr;
-                            self.nodes[node_ptr] = Node::Mixed(ptrs);
-                            return;
+                            self.nodes[node_ptr] = Node::from_ptrs(ptrs);
+                            return None;
                         } else if half_width == 1 {
                             // edit leaf node
-                            let leaf = &mut self.leafs[ptr];
-                            let _old_val = std::mem::replace(leaf, val);
-                            println!("edit leaf: {_old_val:?} -> {val:?}");
-                            return;
+                            return Some(std::mem::replace(&mut self.leafs[ptr], val));
                         } else {
                             // go one level deeper. Go to next loop iteration.
                             half_width /= 2;
@@ -377,68 +670,357 @@ where
         }
     }
 
-    pub fn remove(&mut self, pos: PosU8) -> ! {
-        todo!()
+    /// returns the value at `pos`, or inserts `f()` there and returns it if the voxel is empty.
+    /// Unlike a [`Octree::get`] followed by an [`Octree::insert`], the tree is only descended once.
+    ///
+    /// `f` is not called if `pos` lies inside of a Full node, the voxel is set to the Full node's value already.
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, mut pos: PosU8, f: F) -> V {
+        Self::assert_in_bounds(pos);
+        let target = pos;
+        // (node_ptr, half_width) of all Mixed nodes on the path to pos, merged after an insertion.
+        let mut path: Vec<(usize, u8)> = vec![];
+        let mut node_ptr: usize = 0;
+        let mut half_width: u8 = HALF_WIDTH;
+        loop {
+            match self.nodes[node_ptr] {
+                Node::Full(ref val) => {
+                    return val.clone();
+                }
+                Node::Mixed(mut ptrs, _) => {
+                    path.push((node_ptr, half_width));
+                    let idx = Self::oct_index(&mut pos, half_width);
+                    let ptr = ptrs[idx];
+                    if ptr == EMPTY_PTR {
+                        let val = f();
+                        self.mark_dirty(target);
+                        ptrs[idx] =
+                            self.insert_nodes_below_empty_ptr(pos, val.clone(), half_width / 2);
+                        self.nodes[node_ptr] = Node::from_ptrs(ptrs);
+                        // the inserted voxel can complete a uniform node.
+                        self.merge_path(path);
+                        return val;
+                    } else if half_width == 1 {
+                        // points to leaf
+                        return self.leafs[ptr].clone();
+                    } else {
+                        half_width /= 2;
+                        node_ptr = ptr;
+                    }
+                }
+            }
+        }
     }
 
-    pub fn get_mut(&mut self, pos: PosU8) -> ! {
-        todo!()
+    /// sets the voxel at `pos` to `val` only if it is empty, returns true if it was written.
+    /// Like [`Octree::get_or_insert_with`], the tree is only descended once.
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn fill_if_empty(&mut self, pos: PosU8, val: V) -> bool {
+        let mut written = false;
+        self.get_or_insert_with(pos, || {
+            written = true;
+            val
+        });
+        written
     }
 
-    pub fn to_string(&self) -> String {
-        const INDENT: &str = "   ";
-        let mut lines: Vec<String> = vec![];
+    /// removes the voxel at `pos` and returns the value that was stored there.
+    ///
+    /// Removing from inside a Full node splits it up, such that only the voxel at `pos` is empty.
+    /// Mixed nodes that become completely empty are removed from the tree (except for the root).
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn remove(&mut self, mut pos: PosU8) -> Option<V> {
+        Self::assert_in_bounds(pos);
+        let target = pos;
+        // (node_ptr, oct_idx) of all Mixed nodes we descended through, needed to collapse empty nodes afterwards.
+        let mut path: Vec<(usize, usize)> = vec![];
+        let mut node_ptr: usize = 0;
+        let mut half_width: u8 = HALF_WIDTH;
+        loop {
+            match self.nodes[node_ptr] {
+                Node::Full(_) => {
+                    let full_val = self.take_full_value(node_ptr);
+                    let remove_idx = Self::oct_index(&mut pos, half_width);
+                    // create child nodes resulting from split, leaving the voxel at pos empty:
+                    let child_node_ptrs = self.insert_mixed_child_nodes_for_full_node_split(
+                        full_val.clone(),
+                        remove_idx,
+                        None,
+                        pos,
+                        half_width,
+                    );
+                    // replace the current node with a Mixed Node.
+                    self.nodes[node_ptr] = Node::from_ptrs(child_node_ptrs);
+                    self.mark_dirty(target);
+                    // a split node is never empty, so nothing to collapse.
+                    return Some(full_val);
+                }
+                Node::Mixed(mut ptrs, _) => {
+                    let idx = Self::oct_index(&mut pos, half_width);
+                    path.push((node_ptr, idx));
+                    let ptr = ptrs[idx];
+                    if ptr == EMPTY_PTR {
+                        return None;
+                    } else if half_width == 1 {
+                        // remove leaf
+                        let val = self.leafs.remove(ptr);
+                        ptrs[idx] = EMPTY_PTR;
+                        self.nodes[node_ptr] = Node::from_ptrs(ptrs);
+                        self.collapse_empty_path(&path);
+                        self.mark_dirty(target);
+                        return Some(val);
+                    } else {
+                        // go one level deeper.
+                        half_width /= 2;
+                        node_ptr = ptr;
+                    }
+                }
+            }
+        }
+    }
 
-        // prefix, ptr, half_width, ident
-        let mut frontier: VecDeque<(String, usize, u8, usize)> =
-            vec![("".to_string(), 0, HALF_WIDTH, 0)].into();
-
-        while let Some((prefix, ptr, half_width, indent)) = frontier.pop_back() {
-            let node = self.nodes[ptr];
-            lines.push(format!(
-                "{}{prefix}Node {ptr} ({half_width}):",
-                INDENT.repeat(indent)
-            ));
-
-            match node {
-                Node::Full(leaf_ptr) => {
-                    let leaf = self.leafs[leaf_ptr];
-                    lines.push(format!("{}All: {leaf:?}", INDENT.repeat(indent + 1)));
-                }
-                Node::Mixed(ptrs) => {
-                    let mut empties: Vec<usize> = vec![];
-                    for (i, child_ptr) in ptrs.into_iter().enumerate() {
-                        if child_ptr == EMPTY_PTR {
-                            empties.push(i);
-                        } else if half_width == 1 {
-                            let leaf = self.leafs[child_ptr];
-                            // dbg!(child_ptr, self.leafs[child_ptr]);
-                            lines.push(format!("{}{i}: Leaf: {leaf:?}", INDENT.repeat(indent + 1)));
-                        } else {
-                            frontier.push_back((
-                                format!("{i}: "),
-                                child_ptr,
-                                half_width / 2,
-                                indent + 1,
-                            ))
-                        }
+    /// removes nodes that became empty, bottom-up. `path` holds `(node_ptr, oct_idx)` of Mixed nodes from the root
+    /// downwards, where `oct_idx` is the child that leads to the next node. The root node always stays.
+    fn collapse_empty_path(&mut self, path: &[(usize, usize)]) {
+        for i in (1..path.len()).rev() {
+            let node_ptr = path[i].0;
+            if !self.nodes[node_ptr].is_empty() {
+                break;
+            }
+            self.nodes.remove(node_ptr);
+            let (parent_ptr, idx) = path[i - 1];
+            let Node::Mixed(mut parent_ptrs, _) = self.nodes[parent_ptr] else {
+                unreachable!("we only descend through Mixed nodes");
+            };
+            parent_ptrs[idx] = EMPTY_PTR;
+            self.nodes[parent_ptr] = Node::from_ptrs(parent_ptrs);
+        }
+    }
+
+    /// exchanges the values of the voxels at `a` and `b`, an empty voxel moves like a value.
+    /// Nothing is written if both voxels hold the same value or are both empty.
+    ///
+    /// panics if `a` or `b` is out of bounds.
+    pub fn swap(&mut self, a: PosU8, b: PosU8) {
+        Self::assert_in_bounds(a);
+        Self::assert_in_bounds(b);
+        let val_a = self.get(a);
+        let val_b = self.get(b);
+        if val_a == val_b {
+            return;
+        }
+        for (pos, val) in [(a, val_b), (b, val_a)] {
+            match val {
+                Some(val) => self.insert(pos, val),
+                None => {
+                    self.remove(pos);
+                }
+            }
+        }
+    }
+
+    /// returns a mutable reference to the value of the voxel at `pos`.
+    ///
+    /// If `pos` lies inside of a Full node, the Full node is split up first, such that the voxel at `pos` gets a leaf of its own.
+    ///
+    /// Mutating the value does NOT re-run the merge check, so e.g. setting a leaf to the value of its 7 siblings
+    /// leaves 8 equal leafs instead of a Full node. Call [`Octree::remerge_around`] afterwards to merge them again.
+    pub fn get_mut(&mut self, mut pos: PosU8) -> Option<&mut V> {
+        Self::assert_in_bounds(pos);
+        let target = pos;
+        let mut node_ptr: usize = 0;
+        let mut half_width: u8 = HALF_WIDTH;
+        loop {
+            match self.nodes[node_ptr] {
+                Node::Full(_) => {
+                    let full_val = self.take_full_value(node_ptr);
+                    let mut split_pos = pos;
+                    let split_idx = Self::oct_index(&mut split_pos, half_width);
+                    // split such that there is a path of Mixed nodes down to a leaf at pos:
+                    let child_node_ptrs = self.insert_mixed_child_nodes_for_full_node_split(
+                        full_val.clone(),
+                        split_idx,
+                        Some(full_val),
+                        split_pos,
+                        half_width,
+                    );
+                    self.nodes[node_ptr] = Node::from_ptrs(child_node_ptrs);
+                    // the next iteration descends through the now Mixed node.
+                }
+                Node::Mixed(ptrs, _) => {
+                    let idx = Self::oct_index(&mut pos, half_width);
+                    let ptr = ptrs[idx];
+                    if ptr == EMPTY_PTR {
+                        return None;
+                    } else if half_width == 1 {
+                        // points to leaf, the value may get changed through the reference.
+                        self.mark_dirty(target);
+                        return Some(&mut self.leafs[ptr]);
+                    } else {
+                        // points to node
+                        half_width /= 2;
+                        node_ptr = ptr;
                     }
-                    if !empties.is_empty() {
-                        lines.push(format!(
-                            "{}{}: Empty",
-                            INDENT.repeat(indent + 1),
-                            empties
-                                .iter()
-                                .cloned()
-                                .map(|e| format!("{e}"))
-                                .collect::<Vec<_>>()
-                                .join(", ")
-                        ));
+                }
+            }
+        }
+    }
+
+    /// merges every Mixed node of the tree whose children all have the same value into a Full node, bottom-up,
+    /// and frees the merged children.
+    ///
+    /// Like [`Octree::remerge_around`], but for edits all over the tree, e.g. after many [`Octree::get_mut`] calls.
+    pub fn remerge(&mut self) {
+        self.merge_subtree(0, HALF_WIDTH);
+    }
+
+    /// merges all nodes on the path from the root to `pos` whose children all have the same value into Full nodes.
+    ///
+    /// `insert` keeps the tree merged on its own, this is only needed after values were changed via [`Octree::get_mut`].
+    pub fn remerge_around(&mut self, mut pos: PosU8) {
+        Self::assert_in_bounds(pos);
+        // (node_ptr, half_width) of all Mixed nodes on the path to pos.
+        let mut path: Vec<(usize, u8)> = vec![];
+        let mut node_ptr: usize = 0;
+        let mut half_width: u8 = HALF_WIDTH;
+        while let Node::Mixed(ptrs, _) = self.nodes[node_ptr] {
+            path.push((node_ptr, half_width));
+            let ptr = ptrs[Self::oct_index(&mut pos, half_width)];
+            if ptr == EMPTY_PTR || half_width == 1 {
+                break;
+            }
+            half_width /= 2;
+            node_ptr = ptr;
+        }
+
+        self.merge_path(path);
+    }
+
+    /// merges the Mixed nodes of `path`, given as `(node_ptr, half_width)` from the root downwards, into Full nodes
+    /// as long as their children all have the same value.
+    fn merge_path(&mut self, mut path: Vec<(usize, u8)>) {
+        // merge bottom-up, because merging a node can make its parent uniform as well.
+        while let Some((node_ptr, half_width)) = path.pop() {
+            let Node::Mixed(ptrs, _) = self.nodes[node_ptr] else {
+                unreachable!("path only contains Mixed nodes");
+            };
+            let Some(val) = self.uniform_child_value(&ptrs, half_width).cloned() else {
+                break;
+            };
+            self.delete_mixed_child_nodes(&ptrs, half_width);
+            self.nodes[node_ptr] = Node::Full(val);
+        }
+    }
+
+    /// number of Mixed nodes on the longest path from the root.
+    /// A tree that is empty or consists of a single Full node has a depth of 0.
+    pub fn depth(&self) -> u32 {
+        if self.is_empty() {
+            return 0;
+        }
+        let mut max_depth: u32 = 0;
+        // node_ptr, half_width, depth of the node
+        let mut stack: Vec<(usize, u8, u32)> = vec![(0, HALF_WIDTH, 0)];
+        while let Some((node_ptr, half_width, depth)) = stack.pop() {
+            let Node::Mixed(ptrs, _) = self.nodes[node_ptr] else {
+                max_depth = max_depth.max(depth);
+                continue;
+            };
+            max_depth = max_depth.max(depth + 1);
+            if half_width == 1 {
+                continue;
+            }
+            for ptr in ptrs {
+                if ptr != EMPTY_PTR {
+                    stack.push((ptr, half_width / 2, depth + 1));
+                }
+            }
+        }
+        max_depth
+    }
+
+    /// iterates over all set voxels. Full nodes are expanded into every voxel they cover.
+    pub fn iter(&self) -> OctreeIter<'_, V, HALF_WIDTH> {
+        OctreeIter::new(self)
+    }
+
+    /// iterates over all set voxels inside of the box `min..=max`, without descending into octants
+    /// outside of the box.
+    ///
+    /// panics if `min` or `max` is out of bounds.
+    pub fn iter_box(&self, min: PosU8, max: PosU8) -> OctreeBoxIter<'_, V, HALF_WIDTH> {
+        Self::assert_in_bounds(min);
+        Self::assert_in_bounds(max);
+        OctreeBoxIter::new(self, min, max)
+    }
+
+    /// iterates over uniform regions instead of single voxels: yields `(origin, width, value)`
+    /// once for each Full node and once for each leaf (with a width of 1).
+    pub fn iter_regions(&self) -> OctreeRegionIter<'_, V, HALF_WIDTH> {
+        OctreeRegionIter::new(self)
+    }
+
+    /// all set voxels as a map, Full nodes are expanded into an entry for every voxel they cover.
+    pub fn to_hashmap(&self) -> HashMap<PosU8, V> {
+        let mut map = HashMap::with_capacity(self.len() as usize);
+        self.for_each_voxel(|pos, val| {
+            map.insert(pos, val.clone());
+        });
+        map
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq + std::fmt::Debug,
+{
+    fn node_lines(
+        &self,
+        lines: &mut Vec<String>,
+        prefix: &str,
+        ptr: usize,
+        half_width: u8,
+        indent: usize,
+    ) {
+        const INDENT: &str = "   ";
+        lines.push(format!(
+            "{}{prefix}Node {ptr} ({half_width}):",
+            INDENT.repeat(indent)
+        ));
+        match self.nodes[ptr] {
+            Node::Full(ref leaf) => {
+                lines.push(format!("{}All: {leaf:?}", INDENT.repeat(indent + 1)));
+            }
+            Node::Mixed(ptrs, _) => {
+                let mut empties: Vec<String> = vec![];
+                for (i, child_ptr) in ptrs.into_iter().enumerate() {
+                    if child_ptr == EMPTY_PTR {
+                        empties.push(i.to_string());
+                    } else if half_width == 1 {
+                        let leaf = &self.leafs[child_ptr];
+                        lines.push(format!("{}{i}: Leaf: {leaf:?}", INDENT.repeat(indent + 1)));
+                    } else {
+                        self.node_lines(
+                            lines,
+                            &format!("{i}: "),
+                            child_ptr,
+                            half_width / 2,
+                            indent + 1,
+                        );
                     }
                 }
+                if !empties.is_empty() {
+                    lines.push(format!(
+                        "{}{}: Empty",
+                        INDENT.repeat(indent + 1),
+                        empties.join(", ")
+                    ));
+                }
             }
         }
-        lines.join("\n")
     }
 
     pub fn print(&self) {
@@ -447,21 +1029,116 @@ where
     }
 }
 
-// pub struct OctreeInnerAndLeafIter {}
+/// structural queries, these need no bounds on `V`.
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH> {
+    /// number of set voxels, a Full node counts every voxel it covers.
+    pub fn len(&self) -> u64 {
+        let mut len: u64 = 0;
+        // node_ptr, half_width
+        let mut stack: Vec<(usize, u8)> = vec![(0, HALF_WIDTH)];
+        while let Some((node_ptr, half_width)) = stack.pop() {
+            match self.nodes[node_ptr] {
+                Node::Full(_) => len += (half_width as u64 * 2).pow(3),
+                Node::Mixed(ptrs, _) => {
+                    for ptr in ptrs {
+                        if ptr == EMPTY_PTR {
+                            continue;
+                        }
+                        if half_width == 1 {
+                            len += 1;
+                        } else {
+                            stack.push((ptr, half_width / 2));
+                        }
+                    }
+                }
+            }
+        }
+        len
+    }
+
+    /// true if no voxel is set.
+    pub fn is_empty(&self) -> bool {
+        self.nodes[0].is_empty()
+    }
 
-// pub struct OctreeCoarseIter {}
+    /// number of nodes in the tree, including the root.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
 
-// pub struct OctreeCourseIterItem{
+    /// number of values stored in the leaf slab, one per voxel below a Mixed node with half width 1.
+    /// Full nodes store their value inline and are not counted.
+    pub fn leaf_count(&self) -> usize {
+        self.leafs.len()
+    }
+}
 
-// }
+/// a depth first rendering of the node structure, children are visited in octant order.
+/// The empty children of a Mixed node are summarized in one line after its other children.
+impl<V, const HALF_WIDTH: u8> std::fmt::Display for Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq + std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines: Vec<String> = vec![];
+        self.node_lines(&mut lines, "", 0, HALF_WIDTH, 0);
+        f.write_str(&lines.join("\n"))
+    }
+}
 
-// impl Iterator for OctreeCoarseIter {
-//     type Item = ();
+/// only a summary, use [`std::fmt::Display`] to see the node structure.
+impl<V, const HALF_WIDTH: u8> std::fmt::Debug for Octree<V, HALF_WIDTH> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Octree<{HALF_WIDTH}> {{ voxels: {}, nodes: {} }}",
+            self.len(),
+            self.node_count()
+        )
+    }
+}
 
-//     fn next(&mut self) -> Option<Self::Item> {
-//         todo!()
-//     }
-// }
+impl<V, const HALF_WIDTH: u8> Default for Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// inserts the voxels one after another, later voxels overwrite earlier ones at the same position.
+impl<V, const HALF_WIDTH: u8> FromIterator<(PosU8, V)> for Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    fn from_iter<I: IntoIterator<Item = (PosU8, V)>>(iter: I) -> Self {
+        let mut octree = Self::new();
+        octree.extend(iter);
+        octree
+    }
+}
+
+/// inserts the voxels one after another, overwriting existing voxels.
+impl<V, const HALF_WIDTH: u8> Extend<(PosU8, V)> for Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    fn extend<I: IntoIterator<Item = (PosU8, V)>>(&mut self, iter: I) {
+        for (pos, val) in iter {
+            self.insert(pos, val);
+        }
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> From<HashMap<PosU8, V>> for Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    fn from(map: HashMap<PosU8, V>) -> Self {
+        map.into_iter().collect()
+    }
+}
 
 /*
 
@@ -471,9 +1148,11 @@ Octree should store different data in leaves than in
 
 #[cfg(test)]
 pub mod test {
+    use std::collections::HashMap;
+
     use rand::{thread_rng, Rng};
 
-    use crate::{pos, pos::PosU8};
+    use crate::{oct_offset, pos, pos::PosU8, Node, OutOfBounds, EMPTY_PTR};
 
     use super::Octree;
 
@@ -499,7 +1178,7 @@ pub mod test {
         assert_eq!(octree.leafs.len(), 7);
         octree.insert(PosU8 { x: 1, y: 1, z: 1 }, "Hello");
         // octree should now have just a leaf, because an all node was created:
-        assert_eq!(octree.leafs.len(), 1);
+        assert_eq!(octree.leafs.len(), 0);
 
         octree.insert(PosU8 { x: 1, y: 1, z: 1 }, "lol");
         // should have 8 leafs again:
@@ -519,11 +1198,11 @@ pub mod test {
                 }
             }
         }
-        assert_eq!(octree.leafs.len(), 1);
+        assert_eq!(octree.leafs.len(), 0);
         // set one field of the cube differently:
         octree.insert(pos!(13, 5, 9), "Ok");
 
-        assert_eq!(octree.leafs.len(), 7 + 7 + 7 + 1); // just 22 nodes to store an 8x8x8 chunk which has 512 values in it
+        assert_eq!(octree.leafs.len(), 7 + 1); // the 7 + 7 Full nodes above store their values inline
 
         assert_eq!(octree.get(pos!(13, 5, 9)), Some("Ok"));
         assert_eq!(octree.get(pos!(0, 1, 2)), None);
@@ -546,4 +1225,786 @@ pub mod test {
             assert_eq!(octree.get(pos), Some(r))
         }
     }
+
+    #[test]
+    pub fn insert_returning_old() {
+        let mut octree = Octree::<u32, 8>::new();
+        // first insert and overwrite:
+        assert_eq!(octree.insert_returning_old(pos!(1, 2, 3), 1), None);
+        assert_eq!(octree.insert_returning_old(pos!(1, 2, 3), 2), Some(1));
+        assert_eq!(octree.insert_returning_old(pos!(1, 2, 3), 2), Some(2));
+        assert_eq!(octree.get(pos!(1, 2, 3)), Some(2));
+
+        // overwriting inside of a Full node splits it and returns its value:
+        octree.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 5);
+        assert_eq!(octree.insert_returning_old(pos!(9, 10, 11), 6), Some(5));
+        assert_eq!(octree.get(pos!(9, 10, 11)), Some(6));
+        assert_eq!(octree.get(pos!(9, 10, 12)), Some(5));
+        assert_eq!(octree.insert_returning_old(pos!(12, 12, 12), 5), Some(5));
+
+        // the insertion that merges the Full node again:
+        let node_count = octree.node_count();
+        assert_eq!(octree.insert_returning_old(pos!(9, 10, 11), 5), Some(6));
+        assert!(octree.node_count() < node_count);
+        let mut expected = Octree::<u32, 8>::new();
+        expected.insert(pos!(1, 2, 3), 2);
+        expected.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 5);
+        assert_eq!(octree.encode(), expected.encode());
+
+        // a merge that fills an empty voxel:
+        let mut octree = Octree::<u32, 8>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(1, 1, 1), 3);
+        octree.remove(pos!(1, 1, 1));
+        assert_eq!(octree.insert_returning_old(pos!(1, 1, 1), 3), None);
+        assert_eq!(octree.leaf_count(), 0);
+    }
+
+    #[test]
+    pub fn remove_leafs() {
+        let mut octree = Octree::<&'static str, 16>::new();
+        octree.insert(pos!(0, 0, 0), "Hello");
+        octree.insert(pos!(0, 1, 0), "Hello");
+        octree.insert(pos!(20, 3, 9), "Moin");
+        assert_eq!(octree.leafs.len(), 3);
+
+        assert_eq!(octree.remove(pos!(0, 1, 0)), Some("Hello"));
+        assert_eq!(octree.leafs.len(), 2);
+        assert_eq!(octree.get(pos!(0, 1, 0)), None);
+        assert_eq!(octree.get(pos!(0, 0, 0)), Some("Hello"));
+
+        // removing an empty voxel does nothing:
+        assert_eq!(octree.remove(pos!(0, 1, 0)), None);
+        assert_eq!(octree.remove(pos!(31, 31, 31)), None);
+        assert_eq!(octree.leafs.len(), 2);
+
+        assert_eq!(octree.remove(pos!(20, 3, 9)), Some("Moin"));
+        assert_eq!(octree.remove(pos!(0, 0, 0)), Some("Hello"));
+        assert_eq!(octree.leafs.len(), 0);
+
+        // all the Mixed nodes below the root should have been collapsed:
+        assert_eq!(octree.nodes.len(), 1);
+        assert!(matches!(octree.nodes[0], Node::Mixed(ptrs, _) if ptrs == [EMPTY_PTR; 8]));
+    }
+
+    #[test]
+    pub fn remove_from_full_node() {
+        let mut octree = Octree::<&'static str, 16>::new();
+        // fill one 8x8 cube:
+        for x in 8..16 {
+            for y in 0..8 {
+                for z in 8..16 {
+                    octree.insert(PosU8 { x, y, z }, "Hello");
+                }
+            }
+        }
+        assert_eq!(octree.leafs.len(), 0);
+
+        assert_eq!(octree.remove(pos!(13, 5, 9)), Some("Hello"));
+        // 7 leafs at the bottom, the 7 + 7 Full nodes above store their values inline:
+        assert_eq!(octree.leafs.len(), 7);
+        assert_eq!(octree.get(pos!(13, 5, 9)), None);
+        assert_eq!(octree.get(pos!(13, 5, 7)), None);
+        assert_eq!(octree.get(pos!(13, 5, 10)), Some("Hello"));
+        assert_eq!(octree.get(pos!(8, 0, 8)), Some("Hello"));
+
+        // remove the rest of the cube again:
+        for x in 8..16 {
+            for y in 0..8 {
+                for z in 8..16 {
+                    octree.remove(PosU8 { x, y, z });
+                }
+            }
+        }
+        assert_eq!(octree.leafs.len(), 0);
+        assert_eq!(octree.nodes.len(), 1);
+        assert!(matches!(octree.nodes[0], Node::Mixed(ptrs, _) if ptrs == [EMPTY_PTR; 8]));
+    }
+
+    #[test]
+    pub fn fill_if_empty() {
+        let mut octree = Octree::<u32, 4>::new();
+        octree.insert(pos!(1, 2, 3), 5);
+        assert!(!octree.fill_if_empty(pos!(1, 2, 3), 6));
+        assert_eq!(octree.get(pos!(1, 2, 3)), Some(5));
+
+        assert!(octree.fill_if_empty(pos!(3, 2, 1), 6));
+        assert_eq!(octree.get(pos!(3, 2, 1)), Some(6));
+        assert!(!octree.fill_if_empty(pos!(3, 2, 1), 7));
+        assert_eq!(octree.get(pos!(3, 2, 1)), Some(6));
+        assert_eq!(octree.len(), 2);
+
+        // voxels inside of Full nodes are occupied:
+        let mut octree = Octree::<u32, 4>::filled(1);
+        assert!(!octree.fill_if_empty(pos!(4, 4, 4), 2));
+        assert_eq!(octree.node_count(), 1);
+        assert_eq!(octree.get(pos!(4, 4, 4)), Some(1));
+    }
+
+    #[test]
+    pub fn swap() {
+        let mut octree = Octree::<u32, 4>::new();
+        octree.insert(pos!(1, 2, 3), 5);
+        octree.swap(pos!(1, 2, 3), pos!(6, 6, 6));
+        assert_eq!(octree.get(pos!(1, 2, 3)), None);
+        assert_eq!(octree.get(pos!(6, 6, 6)), Some(5));
+        assert_eq!(octree.len(), 1);
+        assert_eq!(octree.leaf_count(), 1);
+
+        octree.insert(pos!(0, 0, 0), 7);
+        octree.swap(pos!(6, 6, 6), pos!(0, 0, 0));
+        assert_eq!(octree.get(pos!(6, 6, 6)), Some(7));
+        assert_eq!(octree.get(pos!(0, 0, 0)), Some(5));
+
+        // both empty or the same position:
+        octree.swap(pos!(2, 2, 2), pos!(3, 3, 3));
+        octree.swap(pos!(0, 0, 0), pos!(0, 0, 0));
+        assert_eq!(octree.len(), 2);
+        assert_eq!(octree.get(pos!(0, 0, 0)), Some(5));
+
+        // swapping a hole into a Full node keeps the tree merged:
+        let mut octree = Octree::<u32, 4>::filled(1);
+        octree.remove(pos!(0, 0, 0));
+        octree.swap(pos!(0, 0, 0), pos!(7, 7, 7));
+        assert_eq!(octree.get(pos!(0, 0, 0)), Some(1));
+        assert_eq!(octree.get(pos!(7, 7, 7)), None);
+        octree.swap(pos!(0, 0, 0), pos!(7, 7, 7));
+        octree.insert(pos!(0, 0, 0), 1);
+        assert_eq!(octree.node_count(), 1);
+    }
+
+    #[test]
+    pub fn get_mut_and_remerge() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.insert(pos!(3, 4, 5), 1);
+        *octree.get_mut(pos!(3, 4, 5)).unwrap() = 2;
+        assert_eq!(octree.get(pos!(3, 4, 5)), Some(2));
+        assert_eq!(octree.get_mut(pos!(3, 4, 6)), None);
+
+        // fill a 2x2x2 cube, with one differing value:
+        for x in 0..2 {
+            for y in 0..2 {
+                for z in 0..2 {
+                    octree.insert(pos!(x, y, z), 7);
+                }
+            }
+        }
+        octree.insert(pos!(1, 1, 1), 8);
+        assert_eq!(octree.leafs.len(), 9);
+
+        *octree.get_mut(pos!(1, 1, 1)).unwrap() = 7;
+        // get_mut does not merge:
+        assert_eq!(octree.leafs.len(), 9);
+        octree.remerge_around(pos!(1, 1, 1));
+        assert_eq!(octree.leafs.len(), 1);
+        assert_eq!(octree.get(pos!(1, 1, 1)), Some(7));
+        assert_eq!(octree.get(pos!(3, 4, 5)), Some(2));
+    }
+
+    #[test]
+    pub fn remerge() {
+        let mut octree = Octree::<u32, 8>::new();
+        // an 8x8x8 cube of different values, which insert can not merge:
+        for x in 0..8 {
+            for y in 0..8 {
+                for z in 0..8 {
+                    octree.insert(pos!(x, y, z), (x + y + z) as u32 % 2);
+                }
+            }
+        }
+        octree.insert(pos!(12, 12, 12), 3);
+        assert_eq!(octree.leaf_count(), 513);
+        let node_count = octree.node_count();
+
+        // make the cube uniform without merging it:
+        for x in 0..8 {
+            for y in 0..8 {
+                for z in 0..8 {
+                    *octree.get_mut(pos!(x, y, z)).unwrap() = 5;
+                }
+            }
+        }
+        assert_eq!(octree.leaf_count(), 513);
+        assert_eq!(octree.node_count(), node_count);
+
+        octree.remerge();
+        let mut expected = Octree::<u32, 8>::new();
+        expected.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), 5);
+        expected.insert(pos!(12, 12, 12), 3);
+        assert_eq!(octree.encode(), expected.encode());
+        assert_eq!(octree.node_count(), expected.node_count());
+        assert_eq!(octree.leaf_count(), 1);
+
+        // merging up to the root:
+        let mut octree = Octree::<u32, 2>::new();
+        for i in 0..64u8 {
+            octree.insert(pos!(i % 4, i / 4 % 4, i / 16), i as u32);
+        }
+        for i in 0..64u8 {
+            *octree.get_mut(pos!(i % 4, i / 4 % 4, i / 16)).unwrap() = 1;
+        }
+        octree.remerge();
+        assert!(matches!(octree.nodes[0], Node::Full(_)));
+        assert_eq!(octree.node_count(), 1);
+        assert_eq!(octree.leaf_count(), 0);
+    }
+
+    #[test]
+    pub fn get_mut_splits_full_node() {
+        let mut octree = Octree::<u32, 16>::new();
+        for x in 0..4 {
+            for y in 0..4 {
+                for z in 0..4 {
+                    octree.insert(pos!(x, y, z), 7);
+                }
+            }
+        }
+        assert_eq!(octree.leafs.len(), 0);
+
+        *octree.get_mut(pos!(2, 1, 3)).unwrap() += 1;
+        assert_eq!(octree.get(pos!(2, 1, 3)), Some(8));
+        assert_eq!(octree.get(pos!(2, 1, 2)), Some(7));
+        assert_eq!(octree.get(pos!(0, 0, 0)), Some(7));
+
+        *octree.get_mut(pos!(2, 1, 3)).unwrap() -= 1;
+        octree.remerge_around(pos!(2, 1, 3));
+        assert_eq!(octree.leafs.len(), 0);
+    }
+
+    #[test]
+    pub fn get_through_shared_reference() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.insert(pos!(1, 2, 3), 4);
+        octree.insert(pos!(30, 2, 3), 5);
+
+        let shared: &Octree<u32, 16> = &octree;
+        let other_shared = &octree;
+        assert_eq!(shared.get(pos!(1, 2, 3)), Some(4));
+        assert_eq!(other_shared.get(pos!(30, 2, 3)), Some(5));
+        assert_eq!(shared.get(pos!(0, 0, 0)), None);
+    }
+
+    #[test]
+    pub fn insert_many_random() {
+        // guards against accidentally slow insertion, e.g. because of debug output in the hot path.
+        let mut octree = Octree::<u8, 128>::new();
+        let mut rng = thread_rng();
+        let start = std::time::Instant::now();
+        for _ in 0..100_000 {
+            let pos = pos!(rng.gen(), rng.gen(), rng.gen());
+            octree.insert(pos, rng.gen_range(0..4));
+        }
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+    }
+
+    #[test]
+    pub fn bounds_check() {
+        let mut octree = Octree::<u32, 16>::new();
+        assert_eq!(octree.try_insert(pos!(31, 31, 31), 1), Ok(()));
+        assert_eq!(octree.try_get(pos!(31, 31, 31)), Ok(Some(1)));
+        let err = OutOfBounds {
+            pos: pos!(32, 0, 0),
+            side: 32,
+        };
+        assert_eq!(octree.try_insert(pos!(32, 0, 0), 1), Err(err));
+        assert_eq!(octree.try_get(pos!(32, 0, 0)), Err(err));
+        assert_eq!(octree.try_get(pos!(0, 250, 0)).unwrap_err().side, 32);
+        assert_eq!(
+            err.to_string(),
+            "pos PosU8 { x: 32, y: 0, z: 0 } out of bounds for octree of side 32"
+        );
+
+        // a tree of half width 128 spans all possible positions:
+        let mut octree = Octree::<u32, 128>::new();
+        octree.insert(pos!(255, 255, 255), 1);
+        assert_eq!(octree.get(pos!(255, 255, 255)), Some(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds for octree of side 32")]
+    pub fn insert_out_of_bounds_panics() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.insert(pos!(0, 0, 32), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds for octree of side 32")]
+    pub fn remove_out_of_bounds_panics() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.remove(pos!(250, 0, 0));
+    }
+
+    #[test]
+    pub fn non_copy_values() {
+        let mut octree = Octree::<String, 16>::new();
+        for x in 0..2 {
+            for y in 0..2 {
+                for z in 0..2 {
+                    octree.insert(pos!(x, y, z), "stone".to_string());
+                }
+            }
+        }
+        assert_eq!(octree.leafs.len(), 0);
+        octree.insert(pos!(1, 0, 1), "chest".to_string());
+        assert_eq!(octree.leafs.len(), 8);
+
+        assert_eq!(octree.get(pos!(1, 0, 1)), Some("chest".to_string()));
+        assert_eq!(octree.get(pos!(0, 0, 0)), Some("stone".to_string()));
+        octree
+            .get_mut(pos!(1, 0, 1))
+            .unwrap()
+            .push_str(" with items");
+        assert_eq!(
+            octree.remove(pos!(1, 0, 1)),
+            Some("chest with items".to_string())
+        );
+        assert_eq!(octree.get(pos!(1, 0, 1)), None);
+        assert_eq!(octree.iter().count(), 7);
+    }
+
+    #[test]
+    pub fn get_ref() {
+        let mut octree = Octree::<Vec<u8>, 16>::new();
+        octree.insert(pos!(4, 5, 6), vec![1, 2, 3]);
+        assert_eq!(octree.get_ref(pos!(4, 5, 6)), Some(&vec![1, 2, 3]));
+        assert_eq!(octree.get_ref(pos!(4, 5, 7)), None);
+
+        octree.insert(pos!(4, 5, 6), vec![4]);
+        assert_eq!(octree.get_ref(pos!(4, 5, 6)), Some(&vec![4]));
+
+        // voxels of Full nodes all reference the same value:
+        for x in 0..2 {
+            for y in 0..2 {
+                for z in 0..2 {
+                    octree.insert(pos!(x, y, z), vec![9; 100]);
+                }
+            }
+        }
+        let a = octree.get_ref(pos!(0, 0, 0)).unwrap();
+        let b = octree.get_ref(pos!(1, 1, 1)).unwrap();
+        assert!(std::ptr::eq(a, b));
+    }
+
+    #[test]
+    pub fn len() {
+        let mut octree = Octree::<u32, 16>::new();
+        assert!(octree.is_empty());
+        assert_eq!(octree.len(), 0);
+
+        octree.insert(pos!(1, 2, 3), 1);
+        octree.insert(pos!(20, 2, 3), 1);
+        assert!(!octree.is_empty());
+        assert_eq!(octree.len(), 2);
+
+        for x in 8..16 {
+            for y in 0..8 {
+                for z in 8..16 {
+                    octree.insert(pos!(x, y, z), 7);
+                }
+            }
+        }
+        assert_eq!(octree.len(), 2 + 512);
+        octree.remove(pos!(9, 1, 9));
+        assert_eq!(octree.len(), 2 + 511);
+
+        octree.remove(pos!(1, 2, 3));
+        assert_eq!(octree.len(), 1 + 511);
+    }
+
+    #[test]
+    pub fn len_of_full_root() {
+        let mut octree = Octree::<u32, 2>::new();
+        for x in 0..4 {
+            for y in 0..4 {
+                for z in 0..4 {
+                    octree.insert(pos!(x, y, z), 7);
+                }
+            }
+        }
+        // the root itself is a Full node:
+        assert!(matches!(octree.nodes[0], Node::Full(_)));
+        assert_eq!(octree.len(), 4 * 4 * 4);
+        assert!(!octree.is_empty());
+    }
+
+    #[test]
+    pub fn introspection() {
+        let mut octree = Octree::<u32, 16>::new();
+        assert_eq!(octree.node_count(), 1);
+        assert_eq!(octree.leaf_count(), 0);
+        assert_eq!(octree.depth(), 0);
+
+        for x in 0..32 {
+            for y in 0..32 {
+                for z in 0..32 {
+                    octree.insert(pos!(x, y, z), 7);
+                }
+            }
+        }
+        // merged into a single Full root:
+        assert_eq!(octree.node_count(), 1);
+        assert_eq!(octree.leaf_count(), 0);
+        assert_eq!(octree.depth(), 0);
+
+        octree.insert(pos!(13, 5, 9), 8);
+        // log2(16) + 1 Mixed nodes down to the leaf:
+        assert_eq!(octree.depth(), 5);
+        assert_eq!(octree.node_count(), 1 + 8 + 8 + 8 + 8);
+        assert_eq!(octree.leaf_count(), 8);
+    }
+
+    /// every presence mask matches the pointers of its node.
+    fn assert_masks_consistent<V>(octree: &Octree<V, 16>) {
+        for (_, node) in octree.nodes.iter() {
+            if let Node::Mixed(ptrs, mask) = node {
+                for (i, ptr) in ptrs.iter().enumerate() {
+                    assert_eq!(mask & (1 << i) != 0, *ptr != EMPTY_PTR);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn presence_masks_stay_consistent() {
+        let mut octree = Octree::<u32, 16>::new();
+        let mut rng = thread_rng();
+        for _ in 0..5000 {
+            let pos = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            if rng.gen_bool(0.3) {
+                octree.remove(pos);
+            } else {
+                octree.insert(pos, rng.gen_range(0..2));
+            }
+        }
+        assert_masks_consistent(&octree);
+        octree.fill_box(pos!(0, 0, 0), pos!(15, 15, 15), 1);
+        for pos in PosU8::iter_box(pos!(0, 0, 0), pos!(7, 7, 7)) {
+            octree.remove(pos);
+        }
+        octree.entry(pos!(20, 20, 20)).or_insert(3);
+        if let crate::Entry::Occupied(entry) = octree.entry(pos!(1, 1, 9)) {
+            entry.remove();
+        }
+        assert_masks_consistent(&octree);
+        assert_masks_consistent(&octree.mirror_x());
+        assert_masks_consistent(&Octree::decode(octree.encode()).unwrap());
+
+        // the mask of the root follows the last voxel out:
+        octree.clear();
+        octree.insert(pos!(30, 1, 1), 1);
+        assert!(matches!(octree.nodes[0], Node::Mixed(_, 0b0001_0000)));
+        octree.remove(pos!(30, 1, 1));
+        assert!(matches!(octree.nodes[0], Node::Mixed(_, 0)));
+    }
+
+    #[test]
+    pub fn clear() {
+        let mut octree = Octree::<u32, 16>::new();
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let pos = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            octree.insert(pos, rng.gen());
+        }
+        let leafs_capacity = octree.leafs.capacity();
+
+        octree.clear();
+        assert_eq!(octree.len(), 0);
+        assert_eq!(octree.node_count(), 1);
+        assert_eq!(octree.leaf_count(), 0);
+        assert!(matches!(octree.nodes[0], Node::Mixed(ptrs, _) if ptrs == [EMPTY_PTR; 8]));
+        assert_eq!(octree.leafs.capacity(), leafs_capacity);
+
+        octree.insert(pos!(3, 2, 1), 5);
+        assert_eq!(octree.get(pos!(3, 2, 1)), Some(5));
+        assert_eq!(octree.len(), 1);
+    }
+
+    #[test]
+    pub fn contains() {
+        let mut octree = Octree::<u32, 16>::new();
+        // empty octants:
+        assert!(!octree.contains(pos!(0, 0, 0)));
+        assert!(!octree.contains(pos!(31, 31, 31)));
+
+        // mixed region:
+        octree.insert(pos!(20, 21, 22), 1);
+        assert!(octree.contains(pos!(20, 21, 22)));
+        assert!(!octree.contains(pos!(20, 21, 23)));
+
+        // full region:
+        for x in 0..8 {
+            for y in 0..8 {
+                for z in 0..8 {
+                    octree.insert(pos!(x, y, z), 7);
+                }
+            }
+        }
+        assert!(octree.contains(pos!(0, 0, 0)));
+        assert!(octree.contains(pos!(7, 3, 5)));
+        assert!(!octree.contains(pos!(8, 3, 5)));
+    }
+
+    #[test]
+    pub fn hashmap_round_trip() {
+        let mut rng = thread_rng();
+        let mut map = HashMap::new();
+        for _ in 0..200 {
+            let pos = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            map.insert(pos, rng.gen_range(0..100u32));
+        }
+        let octree = Octree::<u32, 16>::from(map.clone());
+        assert_eq!(octree.len(), map.len() as u64);
+        assert_eq!(octree.to_hashmap(), map);
+
+        // Full nodes are expanded:
+        let mut octree = Octree::<u32, 16>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(3, 3, 3), 1);
+        let map = octree.to_hashmap();
+        assert_eq!(map.len(), 64);
+        assert_eq!(map.get(&pos!(3, 2, 1)), Some(&1));
+    }
+
+    #[test]
+    pub fn split_deep_in_full_root() {
+        let mut octree = Octree::<u32, 128>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(255, 255, 255), 1);
+        assert_eq!(octree.node_count(), 1);
+
+        octree.insert(pos!(255, 1, 127), 2);
+        octree.insert(pos!(101, 203, 77), 3);
+        assert_eq!(octree.get(pos!(255, 1, 127)), Some(2));
+        assert_eq!(octree.get(pos!(101, 203, 77)), Some(3));
+        assert_eq!(octree.get(pos!(254, 1, 127)), Some(1));
+        assert_eq!(octree.get(pos!(101, 203, 76)), Some(1));
+        assert_eq!(octree.get(pos!(0, 0, 0)), Some(1));
+        assert_eq!(octree.len(), 256 * 256 * 256);
+        assert_eq!(octree.depth(), 8);
+        // first voxel: 8 Mixed nodes with 7 Full siblings each, except on the leaf level.
+        // second voxel: one of these Full nodes becomes Mixed, plus 6 Mixed nodes below with 7 Full siblings each.
+        assert_eq!(octree.node_count(), (8 + 7 * 7) + (6 + 6 * 7));
+
+        assert_eq!(octree.remove(pos!(33, 65, 129)), Some(1));
+        assert_eq!(octree.get(pos!(33, 65, 129)), None);
+        assert_eq!(octree.len(), 256 * 256 * 256 - 1);
+    }
+
+    #[test]
+    pub fn filled() {
+        let octree = Octree::<u32, 4>::filled(3);
+        for x in 0..8 {
+            for y in 0..8 {
+                for z in 0..8 {
+                    assert_eq!(octree.get(pos!(x, y, z)), Some(3));
+                }
+            }
+        }
+        assert_eq!(octree.node_count(), 1);
+        assert_eq!(octree.leaf_count(), 0);
+        assert_eq!(octree.len(), 512);
+
+        let mut octree = Octree::<u32, 128>::filled(5);
+        assert_eq!(octree.get(pos!(255, 0, 17)), Some(5));
+        octree.remove(pos!(1, 2, 3));
+        assert_eq!(octree.len(), 256 * 256 * 256 - 1);
+    }
+
+    #[test]
+    pub fn full_nodes_take_no_leafs() {
+        let mut octree = Octree::<String, 16>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(15, 15, 15), "stone".to_string());
+        octree.fill_box(pos!(16, 0, 0), pos!(19, 3, 3), "dirt".to_string());
+        assert_eq!(octree.leaf_count(), 0);
+        assert_eq!(octree.get(pos!(19, 3, 3)), Some("dirt".to_string()));
+
+        // overwriting Full regions, voxel by voxel or as a whole, needs no leafs either:
+        for pos in PosU8::iter_box(pos!(16, 0, 0), pos!(19, 3, 3)) {
+            octree.insert(pos, "stone".to_string());
+        }
+        octree.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), "dirt".to_string());
+        assert_eq!(octree.leaf_count(), 0);
+
+        // splitting a Full node only takes leafs at the bottom:
+        octree.insert(pos!(9, 9, 9), "chest".to_string());
+        assert_eq!(octree.leaf_count(), 8);
+        octree.insert(pos!(9, 9, 9), "stone".to_string());
+        assert_eq!(octree.leaf_count(), 0);
+    }
+
+    #[test]
+    pub fn carve_full_root() {
+        let mut octree = Octree::<u32, 8>::with_root_full(1);
+        assert_eq!(octree, Octree::filled(1));
+        assert_eq!(octree.node_count(), 1);
+        assert_eq!(octree.remove(pos!(3, 12, 7)), Some(1));
+        assert_eq!(octree.get(pos!(3, 12, 7)), None);
+        assert!(!octree.contains(pos!(3, 12, 7)));
+        // the surrounding voxels keep the value of the root:
+        for (dx, dy, dz) in [(1, 0, 0), (0, 1, 0), (0, 0, 1)] {
+            assert_eq!(octree.get(pos!(3 + dx, 12 + dy, 7 + dz)), Some(1));
+            assert_eq!(octree.get(pos!(3 - dx, 12 - dy, 7 - dz)), Some(1));
+        }
+        assert_eq!(octree.get(pos!(15, 0, 15)), Some(1));
+        assert_eq!(octree.len(), 16 * 16 * 16 - 1);
+        // one split per level into 8 children, 7 of them Full nodes or leafs with the old value:
+        assert_eq!(octree.node_count(), 1 + 3 * 8);
+        assert_eq!(octree.leaf_count(), 7);
+
+        // an insert with a different value splits from the root as well:
+        octree.insert(pos!(8, 8, 8), 2);
+        assert_eq!(octree.get(pos!(8, 8, 8)), Some(2));
+        assert_eq!(octree.get(pos!(9, 8, 8)), Some(1));
+
+        // restoring both voxels merges everything back into a Full root:
+        octree.insert(pos!(3, 12, 7), 1);
+        octree.insert(pos!(8, 8, 8), 1);
+        assert_eq!(octree.node_count(), 1);
+        assert_eq!(octree.encode(), Octree::<u32, 8>::filled(1).encode());
+    }
+
+    #[test]
+    pub fn get_or_insert_with() {
+        let mut octree = Octree::<u32, 4>::new();
+        let mut calls = 0;
+        let val = octree.get_or_insert_with(pos!(1, 2, 3), || {
+            calls += 1;
+            5
+        });
+        assert_eq!((val, calls), (5, 1));
+        let val = octree.get_or_insert_with(pos!(1, 2, 3), || {
+            calls += 1;
+            6
+        });
+        assert_eq!((val, calls), (5, 1));
+        assert_eq!(octree.get(pos!(1, 2, 3)), Some(5));
+
+        // inside of a Full node, the position is already set:
+        octree.fill_box(pos!(4, 4, 4), pos!(7, 7, 7), 9);
+        let val = octree.get_or_insert_with(pos!(5, 6, 7), || {
+            calls += 1;
+            1
+        });
+        assert_eq!((val, calls), (9, 1));
+
+        // completing a node merges it like insert does:
+        let mut expected = Octree::<u32, 4>::new();
+        expected.fill_box(pos!(0, 0, 0), pos!(1, 1, 1), 2);
+        let mut octree = Octree::<u32, 4>::new();
+        for (pos, _) in expected.iter().skip(1) {
+            octree.insert(pos, 2);
+        }
+        assert_eq!(octree.get_or_insert_with(PosU8::ZERO, || 2), 2);
+        assert_eq!(octree.encode(), expected.encode());
+    }
+
+    #[test]
+    pub fn default_and_from_iter() {
+        let octree = Octree::<u32, 16>::default();
+        assert!(octree.is_empty());
+        assert_eq!(octree.node_count(), 1);
+
+        let voxels = vec![
+            (pos!(1, 2, 3), 4),
+            (pos!(31, 0, 7), 5),
+            (pos!(1, 2, 3), 6),
+            (pos!(0, 0, 0), 7),
+        ];
+        let octree: Octree<u32, 16> = voxels.into_iter().collect();
+        assert_eq!(octree.len(), 3);
+        assert_eq!(octree.get(pos!(1, 2, 3)), Some(6));
+        assert_eq!(octree.get(pos!(31, 0, 7)), Some(5));
+        assert_eq!(octree.get(pos!(0, 0, 0)), Some(7));
+    }
+
+    #[test]
+    pub fn extend() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.insert(pos!(1, 2, 3), 1);
+        octree.insert(pos!(4, 5, 6), 2);
+
+        octree.extend(vec![(pos!(4, 5, 6), 3), (pos!(7, 8, 9), 4)]);
+        assert_eq!(octree.len(), 3);
+        assert_eq!(octree.get(pos!(1, 2, 3)), Some(1));
+        assert_eq!(octree.get(pos!(4, 5, 6)), Some(3));
+        assert_eq!(octree.get(pos!(7, 8, 9)), Some(4));
+    }
+
+    #[test]
+    pub fn to_string_depth_first() {
+        let mut octree = Octree::<u32, 2>::new();
+        octree.insert(pos!(3, 3, 3), 2);
+        octree.insert(pos!(0, 0, 1), 1);
+        octree.fill_box(pos!(2, 0, 0), pos!(3, 1, 1), 3);
+        let expected = "\
+Node 0 (2):
+   0: Node 2 (1):
+      1: Leaf: 1
+      0, 2, 3, 4, 5, 6, 7: Empty
+   4: Node 3 (1):
+      All: 3
+   7: Node 1 (1):
+      7: Leaf: 2
+      0, 1, 2, 3, 4, 5, 6: Empty
+   1, 2, 3, 5, 6: Empty";
+        assert_eq!(octree.to_string(), expected);
+    }
+
+    #[test]
+    pub fn display_and_debug() {
+        let mut octree = Octree::<u32, 2>::new();
+        assert_eq!(
+            format!("{octree}"),
+            "Node 0 (2):\n   0, 1, 2, 3, 4, 5, 6, 7: Empty"
+        );
+        octree.fill_box(pos!(2, 0, 0), pos!(3, 1, 1), 3);
+        assert_eq!(format!("{octree}"), octree.to_string());
+        assert_eq!(format!("{octree:?}"), "Octree<2> { voxels: 8, nodes: 2 }");
+
+        let filled = Octree::<u32, 8>::filled(1);
+        assert_eq!(format!("{filled}"), "Node 0 (8):\n   All: 1");
+        assert_eq!(
+            format!("{filled:?}"),
+            "Octree<8> { voxels: 4096, nodes: 1 }"
+        );
+    }
+
+    #[test]
+    pub fn get_path() {
+        let mut octree = Octree::<u32, 16>::new();
+        let pos = pos!(21, 6, 31);
+        octree.insert(pos, 1);
+        let path = octree.get_path(pos);
+        assert_eq!(path.len(), 5);
+        assert_eq!(path[0], 4 | 1);
+
+        let mut reconstructed = PosU8::ZERO;
+        let mut half_width = 16;
+        for idx in path.iter() {
+            reconstructed += oct_offset(*idx, half_width);
+            half_width /= 2;
+        }
+        assert_eq!(reconstructed, pos);
+
+        // following the path through the structure reaches the voxel:
+        let leaf = path
+            .iter()
+            .try_fold(octree.root(), |node, idx| node.child(*idx))
+            .unwrap();
+        assert_eq!(leaf.origin(), pos);
+        assert_eq!(leaf.is_full(), Some(&1));
+
+        // the path does not depend on the structure:
+        let filled = Octree::<u32, 16>::filled(1);
+        assert_eq!(filled.get_path(pos), path);
+    }
 }
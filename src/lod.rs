@@ -0,0 +1,319 @@
+use crate::{oct_offset, Node, Octree, PosU8, EMPTY_PTR};
+
+/// How the value of a coarse cell is chosen, see [`Octree::sample_lod`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LodMode {
+    /// the value covering the most voxels of the cell. Empty voxels are ignored, ties are won by the value
+    /// that comes first in octant order.
+    #[default]
+    Majority,
+    /// the first set voxel of the cell in octant order, cheaper than counting.
+    FirstFound,
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// reads the tree at a lower resolution: returns the value of the cube of side `2^level` that contains `pos`,
+    /// chosen according to `mode`, or `None` if the whole cube is empty.
+    ///
+    /// Level 0 is the same as [`Octree::get`], levels above the root level are treated like the root level.
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn sample_lod(&self, mut pos: PosU8, level: u8, mode: LodMode) -> Option<V> {
+        Self::assert_in_bounds(pos);
+        let width = 1u16 << (level as u32).min(Self::SIDE.trailing_zeros());
+        let mut node_ptr: usize = 0;
+        let mut half_width: u8 = HALF_WIDTH;
+        loop {
+            match self.nodes[node_ptr] {
+                Node::Full(ref val) => return Some(val.clone()),
+                Node::Mixed(..) if half_width as u16 * 2 == width => {
+                    return self.aggregate_node(node_ptr, half_width, mode);
+                }
+                Node::Mixed(ptrs, _) => {
+                    let ptr = ptrs[Self::oct_index(&mut pos, half_width)];
+                    if ptr == EMPTY_PTR {
+                        return None;
+                    } else if half_width == 1 {
+                        // points to leaf
+                        return Some(self.leafs[ptr].clone());
+                    }
+                    half_width /= 2;
+                    node_ptr = ptr;
+                }
+            }
+        }
+    }
+
+    /// the fraction of the cubes of side `2^level` that contain at least one set voxel, e.g. to decide whether a
+    /// chunk is worth meshing in detail. Level 0 is the fraction of set voxels, levels above the root level are
+    /// treated like the root level.
+    pub fn occupancy_at_level(&self, level: u8) -> f32 {
+        let cell_width = 1u16 << (level as u32).min(Self::SIDE.trailing_zeros());
+        let cells_per_side = (Self::SIDE / cell_width) as u64;
+        let occupied = self.occupied_cells(0, HALF_WIDTH, cell_width);
+        (occupied as f64 / cells_per_side.pow(3) as f64) as f32
+    }
+
+    /// number of cubes of side `cell_width` inside of the node at `node_ptr` that contain at least one set voxel.
+    fn occupied_cells(&self, node_ptr: usize, half_width: u8, cell_width: u16) -> u64 {
+        match self.nodes[node_ptr] {
+            Node::Full(_) => (half_width as u64 * 2 / cell_width as u64).pow(3),
+            Node::Mixed(_, mask) if half_width as u16 * 2 == cell_width => (mask != 0) as u64,
+            Node::Mixed(ptrs, _) => ptrs
+                .into_iter()
+                .filter(|ptr| *ptr != EMPTY_PTR)
+                .map(|ptr| {
+                    if half_width == 1 {
+                        // points to leaf, which is a cell of its own on level 0.
+                        1
+                    } else {
+                        self.occupied_cells(ptr, half_width / 2, cell_width)
+                    }
+                })
+                .sum(),
+        }
+    }
+
+    /// builds a tree of half the resolution, where every 2x2x2 block of this tree becomes one voxel with the
+    /// block's [`LodMode::Majority`] value. Calling it repeatedly yields an LOD pyramid.
+    ///
+    /// `SMALL` has to be `HALF_WIDTH / 2`, this is checked at runtime, because stable Rust can not express it as a
+    /// bound yet. So the call looks like `octree.downsample::<8>()` for a `Octree<V, 16>`.
+    pub fn downsample<const SMALL: u8>(&self) -> Octree<V, SMALL> {
+        assert!(
+            SMALL >= 1 && SMALL as u16 * 2 == HALF_WIDTH as u16,
+            "downsample needs SMALL == HALF_WIDTH / 2"
+        );
+        let mut small = Octree::new();
+        self.downsample_node(0, PosU8::ZERO, HALF_WIDTH, &mut small);
+        small
+    }
+
+    fn downsample_node<const SMALL: u8>(
+        &self,
+        node_ptr: usize,
+        origin: PosU8,
+        half_width: u8,
+        small: &mut Octree<V, SMALL>,
+    ) {
+        match self.nodes[node_ptr] {
+            Node::Full(ref val) => {
+                let min = origin / 2;
+                let max = min + PosU8::from([half_width - 1; 3]);
+                small.fill_box(min, max, val.clone());
+            }
+            Node::Mixed(..) if half_width == 1 => {
+                if let Some(val) = self.aggregate_node(node_ptr, half_width, LodMode::Majority) {
+                    small.insert(origin / 2, val);
+                }
+            }
+            Node::Mixed(ptrs, _) => {
+                for (i, ptr) in ptrs.into_iter().enumerate() {
+                    if ptr != EMPTY_PTR {
+                        let child_origin = origin + oct_offset(i, half_width);
+                        self.downsample_node(ptr, child_origin, half_width / 2, small);
+                    }
+                }
+            }
+        }
+    }
+
+    /// the value representing all voxels of the node at `node_ptr`, `None` if it is empty.
+    pub(crate) fn aggregate_node(
+        &self,
+        node_ptr: usize,
+        half_width: u8,
+        mode: LodMode,
+    ) -> Option<V> {
+        match mode {
+            LodMode::FirstFound => {
+                let mut first: Option<V> = None;
+                self.for_each_in_node(node_ptr, PosU8::ZERO, half_width, &mut |_, _, val| {
+                    if first.is_none() {
+                        first = Some(val.clone());
+                    }
+                });
+                first
+            }
+            LodMode::Majority => {
+                // (value, number of voxels) in the order the values are first seen.
+                let mut counts: Vec<(V, u32)> = vec![];
+                self.for_each_in_node(node_ptr, PosU8::ZERO, half_width, &mut |_, width, val| {
+                    let voxels = width as u32 * width as u32 * width as u32;
+                    match counts.iter_mut().find(|(v, _)| v == val) {
+                        Some((_, count)) => *count += voxels,
+                        None => counts.push((val.clone(), voxels)),
+                    }
+                });
+                let mut best: Option<(V, u32)> = None;
+                for (val, count) in counts {
+                    // strictly greater, so ties keep the earlier value
+                    if best
+                        .as_ref()
+                        .is_none_or(|(_, best_count)| count > *best_count)
+                    {
+                        best = Some((val, count));
+                    }
+                }
+                best.map(|(val, _)| val)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use super::LodMode;
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn sample_lod_level_0_is_get() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 8>::new();
+        for _ in 0..300 {
+            let pos = pos!(
+                rng.gen_range(0..16),
+                rng.gen_range(0..16),
+                rng.gen_range(0..16)
+            );
+            octree.insert(pos, rng.gen_range(0..3));
+        }
+        octree.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 7);
+        for _ in 0..300 {
+            let pos = pos!(
+                rng.gen_range(0..16),
+                rng.gen_range(0..16),
+                rng.gen_range(0..16)
+            );
+            assert_eq!(
+                octree.sample_lod(pos, 0, LodMode::Majority),
+                octree.get(pos)
+            );
+            assert_eq!(
+                octree.sample_lod(pos, 0, LodMode::FirstFound),
+                octree.get(pos)
+            );
+        }
+    }
+
+    #[test]
+    pub fn sample_lod_coarse_cell() {
+        let mut octree = Octree::<u32, 8>::new();
+        // the cell (4..8, 4..8, 4..8) at level 2: 3 voxels of 1, 5 voxels of 2, the rest empty.
+        for z in 0..3 {
+            octree.insert(pos!(4, 4, 4 + z), 1);
+        }
+        for x in 0..4 {
+            octree.insert(pos!(4 + x, 7, 7), 2);
+        }
+        octree.insert(pos!(7, 6, 7), 2);
+
+        assert_eq!(
+            octree.sample_lod(pos!(6, 5, 4), 2, LodMode::Majority),
+            Some(2)
+        );
+        assert_eq!(
+            octree.sample_lod(pos!(6, 5, 4), 2, LodMode::FirstFound),
+            Some(1)
+        );
+        // the neighboring cell is empty:
+        assert_eq!(octree.sample_lod(pos!(0, 0, 0), 2, LodMode::Majority), None);
+        // the whole tree:
+        assert_eq!(
+            octree.sample_lod(pos!(0, 0, 0), 4, LodMode::Majority),
+            Some(2)
+        );
+        assert_eq!(
+            octree.sample_lod(pos!(0, 0, 0), 200, LodMode::Majority),
+            Some(2)
+        );
+
+        // a tie is won by the value first in octant order:
+        let mut octree = Octree::<u32, 8>::new();
+        octree.insert(pos!(1, 1, 1), 5);
+        octree.insert(pos!(0, 0, 0), 6);
+        assert_eq!(
+            octree.sample_lod(pos!(1, 0, 0), 1, LodMode::Majority),
+            Some(6)
+        );
+
+        // a Full node covering the cell:
+        let mut octree = Octree::<u32, 8>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), 3);
+        assert_eq!(
+            octree.sample_lod(pos!(1, 2, 3), 2, LodMode::Majority),
+            Some(3)
+        );
+    }
+
+    #[test]
+    pub fn occupancy_at_level() {
+        let mut octree = Octree::<u32, 8>::new();
+        assert_eq!(octree.occupancy_at_level(0), 0.0);
+        assert_eq!(octree.occupancy_at_level(4), 0.0);
+
+        // a checkerboard fills half of the voxels, but every coarser cell:
+        for pos in PosU8::iter_box(pos!(0, 0, 0), pos!(15, 15, 15)) {
+            if (pos.x + pos.y + pos.z) % 2 == 0 {
+                octree.insert(pos, 1);
+            }
+        }
+        assert!((octree.occupancy_at_level(0) - 0.5).abs() < 1e-6);
+        assert_eq!(octree.occupancy_at_level(1), 1.0);
+        assert_eq!(octree.occupancy_at_level(3), 1.0);
+
+        // a single voxel occupies one cell per level:
+        let mut octree = Octree::<u32, 8>::new();
+        octree.insert(pos!(5, 9, 13), 1);
+        assert_eq!(octree.occupancy_at_level(0), 1.0 / 4096.0);
+        assert_eq!(octree.occupancy_at_level(1), 1.0 / 512.0);
+        assert_eq!(octree.occupancy_at_level(2), 1.0 / 64.0);
+        assert_eq!(octree.occupancy_at_level(3), 1.0 / 8.0);
+        assert_eq!(octree.occupancy_at_level(4), 1.0);
+        assert_eq!(octree.occupancy_at_level(200), 1.0);
+
+        // Full nodes count all of their cells:
+        octree.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), 2);
+        assert_eq!(octree.occupancy_at_level(0), (512.0 + 1.0) / 4096.0);
+        assert_eq!(octree.occupancy_at_level(2), (8.0 + 1.0) / 64.0);
+        assert_eq!(octree.occupancy_at_level(3), 2.0 / 8.0);
+    }
+
+    #[test]
+    pub fn downsample() {
+        let mut octree = Octree::<u32, 8>::new();
+        // the block at (2..4, 2..4, 2..4): 5 voxels of 1, 3 voxels of 2.
+        octree.fill_box(pos!(2, 2, 2), pos!(3, 3, 3), 1);
+        octree.insert(pos!(3, 3, 3), 2);
+        octree.insert(pos!(2, 3, 3), 2);
+        octree.insert(pos!(3, 2, 3), 2);
+        // a tie in the block at (0..2, 0..2, 0..2):
+        octree.insert(pos!(1, 1, 1), 5);
+        octree.insert(pos!(0, 0, 0), 6);
+        // a Full node is kept as a whole:
+        octree.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 7);
+
+        let small: Octree<u32, 4> = octree.downsample();
+        assert_eq!(small.get(pos!(1, 1, 1)), Some(1));
+        assert_eq!(small.get(pos!(0, 0, 0)), Some(6));
+        assert_eq!(small.get(pos!(4, 4, 4)), Some(7));
+        assert_eq!(small.get(pos!(7, 7, 7)), Some(7));
+        assert_eq!(small.len(), 2 + 64);
+
+        let tiny: Octree<u32, 2> = small.downsample();
+        assert_eq!(tiny.get(pos!(0, 0, 0)), Some(6));
+        assert_eq!(tiny.get(pos!(3, 3, 3)), Some(7));
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn downsample_wrong_half_width_panics() {
+        let octree = Octree::<u32, 8>::new();
+        let _: Octree<u32, 8> = octree.downsample();
+    }
+}
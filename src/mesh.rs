@@ -0,0 +1,625 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+};
+
+use crate::{Node, Octree, PosU8, EMPTY_PTR};
+
+/// one of the 6 faces of a voxel, named after the direction its normal points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Face {
+    PosX,
+    NegX,
+    PosY,
+    NegY,
+    PosZ,
+    NegZ,
+}
+
+impl Face {
+    /// all faces in the order +x, -x, +y, -y, +z, -z, same as [`PosU8::neighbors_6`].
+    pub const ALL: [Face; 6] = [
+        Face::PosX,
+        Face::NegX,
+        Face::PosY,
+        Face::NegY,
+        Face::PosZ,
+        Face::NegZ,
+    ];
+
+    /// 0 for x, 1 for y, 2 for z.
+    pub fn axis(self) -> usize {
+        match self {
+            Face::PosX | Face::NegX => 0,
+            Face::PosY | Face::NegY => 1,
+            Face::PosZ | Face::NegZ => 2,
+        }
+    }
+
+    /// true if the normal points in positive direction.
+    pub fn is_positive(self) -> bool {
+        matches!(self, Face::PosX | Face::PosY | Face::PosZ)
+    }
+
+    /// the two axes perpendicular to [`Face::axis`], in x, y, z order.
+    pub fn plane_axes(self) -> (usize, usize) {
+        match self.axis() {
+            0 => (1, 2),
+            1 => (0, 2),
+            _ => (0, 1),
+        }
+    }
+
+    pub fn normal(self) -> [i8; 3] {
+        let mut normal = [0; 3];
+        normal[self.axis()] = if self.is_positive() { 1 } else { -1 };
+        normal
+    }
+}
+
+/// a rectangle of coplanar voxel faces with the same value, see [`Octree::greedy_mesh`].
+///
+/// The rectangle spans `width` voxels along the first and `height` voxels along the second of the
+/// two axes perpendicular to `face.axis()`, in x, y, z order. E.g. for [`Face::PosY`] `width` is
+/// along x and `height` along z.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quad<V> {
+    /// the voxel with the smallest coordinates covered by the quad.
+    pub origin: PosU8,
+    pub width: u16,
+    pub height: u16,
+    pub face: Face,
+    pub val: V,
+}
+
+/// in-plane coordinates and value of a face in one slice of [`Octree::greedy_mesh`].
+type SliceCell<V> = (u8, u8, V);
+
+/// how much of a cube is covered by set voxels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Occupancy {
+    Empty,
+    Full,
+    Mixed,
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// all voxel faces whose neighbor in face direction is empty or outside of the octree,
+    /// together with the value of the voxel they belong to.
+    ///
+    /// Faces inside of uniform regions are never looked at, and the neighbors of a region side are
+    /// first checked as a whole, so only sides next to Mixed regions need per voxel lookups.
+    pub fn exposed_faces(&self) -> Vec<(PosU8, Face, V)> {
+        let mut faces = vec![];
+        for (origin, width, val) in self.iter_regions() {
+            for face in Face::ALL {
+                let Some(neighbor_origin) = Self::neighbor_cube(origin, width, face) else {
+                    // side of the octree
+                    for pos in side_voxels(origin, width, face) {
+                        faces.push((pos, face, val.clone()));
+                    }
+                    continue;
+                };
+                match self.cube_occupancy(neighbor_origin, width) {
+                    Occupancy::Full => {}
+                    Occupancy::Empty => {
+                        for pos in side_voxels(origin, width, face) {
+                            faces.push((pos, face, val.clone()));
+                        }
+                    }
+                    Occupancy::Mixed => {
+                        for pos in side_voxels(origin, width, face) {
+                            let neighbor = step(pos, face);
+                            if !self.contains(neighbor) {
+                                faces.push((pos, face, val.clone()));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        faces
+    }
+
+    /// merges the [`Octree::exposed_faces`] into as few rectangles as possible: coplanar faces
+    /// pointing in the same direction with equal values are combined greedily, first along the
+    /// width, then along the height of the quad.
+    pub fn greedy_mesh(&self) -> Vec<Quad<V>> {
+        // faces grouped by direction and layer, with their in-plane coordinates:
+        let mut slices: BTreeMap<(Face, u8), Vec<SliceCell<V>>> = BTreeMap::new();
+        for (pos, face, val) in self.exposed_faces() {
+            let c = [pos.x, pos.y, pos.z];
+            let (u_axis, v_axis) = face.plane_axes();
+            slices
+                .entry((face, c[face.axis()]))
+                .or_default()
+                .push((c[u_axis], c[v_axis], val));
+        }
+
+        let mut quads = vec![];
+        for ((face, layer), cells) in slices {
+            let u_min = cells.iter().map(|c| c.0).min().unwrap_or(0) as usize;
+            let v_min = cells.iter().map(|c| c.1).min().unwrap_or(0) as usize;
+            let u_len = cells.iter().map(|c| c.0).max().unwrap_or(0) as usize + 1 - u_min;
+            let v_len = cells.iter().map(|c| c.1).max().unwrap_or(0) as usize + 1 - v_min;
+            let mut grid: Vec<Option<V>> = vec![None; u_len * v_len];
+            for (u, v, val) in cells {
+                grid[(v as usize - v_min) * u_len + (u as usize - u_min)] = Some(val);
+            }
+
+            for v in 0..v_len {
+                for u in 0..u_len {
+                    let Some(val) = grid[v * u_len + u].take() else {
+                        continue;
+                    };
+                    let mut width = 1;
+                    while u + width < u_len && grid[v * u_len + u + width].as_ref() == Some(&val) {
+                        width += 1;
+                    }
+                    let mut height = 1;
+                    while v + height < v_len
+                        && (u..u + width)
+                            .all(|i| grid[(v + height) * u_len + i].as_ref() == Some(&val))
+                    {
+                        height += 1;
+                    }
+                    for row in v..v + height {
+                        for cell in &mut grid[row * u_len + u..row * u_len + u + width] {
+                            *cell = None;
+                        }
+                    }
+
+                    let (u_axis, v_axis) = face.plane_axes();
+                    let mut origin = [0u8; 3];
+                    origin[face.axis()] = layer;
+                    origin[u_axis] = (u_min + u) as u8;
+                    origin[v_axis] = (v_min + v) as u8;
+                    quads.push(Quad {
+                        origin: origin.into(),
+                        width: width as u16,
+                        height: height as u16,
+                        face,
+                        val,
+                    });
+                }
+            }
+        }
+        quads
+    }
+
+    /// ambient occlusion of the 4 corners of the `face` of the voxel at `pos`, from 0 (fully occluded) to 3 (open).
+    ///
+    /// Each corner is occluded by the two voxels next to it and the voxel diagonal to it in the layer in front of the face,
+    /// two solid side voxels fully occlude the corner. The corners are in the order `(0, 0)`, `(1, 0)`, `(1, 1)`, `(0, 1)`
+    /// along the [`Face::plane_axes`]. Voxels outside of the tree count as empty.
+    pub fn face_ao(&self, pos: PosU8, face: Face) -> [u8; 4] {
+        let (u_axis, v_axis) = face.plane_axes();
+        let mut layer = [pos.x as i16, pos.y as i16, pos.z as i16];
+        layer[face.axis()] += face.normal()[face.axis()] as i16;
+        let solid = |du: i16, dv: i16| {
+            let mut p = layer;
+            p[u_axis] += du;
+            p[v_axis] += dv;
+            p.iter().all(|c| (0..Self::SIDE as i16).contains(c))
+                && self.contains(PosU8::new(p[0] as u8, p[1] as u8, p[2] as u8))
+        };
+        [(-1, -1), (1, -1), (1, 1), (-1, 1)].map(|(du, dv)| {
+            let side_u = solid(du, 0);
+            let side_v = solid(0, dv);
+            if side_u && side_v {
+                0
+            } else {
+                3 - (side_u as u8 + side_v as u8 + solid(du, dv) as u8)
+            }
+        })
+    }
+
+    /// the occupancy at the point `p`, for smooth meshing: set voxels are 1 and empty voxels 0 at their centers,
+    /// in between the 8 surrounding voxels are interpolated trilinearly. Voxels outside of the tree count as empty.
+    pub fn sample_occupancy(&self, p: [f32; 3]) -> f32 {
+        let (corners, t) = self.surrounding_occupancy(p);
+        (0..8)
+            .map(|i| {
+                let weight: f32 = (0..3).map(|axis| corner_weight(i, axis, t)).product();
+                weight * corners[i]
+            })
+            .sum()
+    }
+
+    /// the gradient of [`Octree::sample_occupancy`] at `p`. It points from empty to set voxels,
+    /// so the normalized negative gradient is the outward surface normal.
+    pub fn gradient(&self, p: [f32; 3]) -> [f32; 3] {
+        let (corners, t) = self.surrounding_occupancy(p);
+        std::array::from_fn(|axis| {
+            (0..8)
+                .map(|i| {
+                    // the derivative of the weight along `axis` is +1 or -1.
+                    let sign = if i & (4 >> axis) != 0 { 1.0 } else { -1.0 };
+                    let weight: f32 = (0..3)
+                        .filter(|other| *other != axis)
+                        .map(|other| corner_weight(i, other, t))
+                        .product();
+                    sign * weight * corners[i]
+                })
+                .sum()
+        })
+    }
+
+    /// the occupancy of the 8 voxels whose centers surround `p` in octant order (x = 4, y = 2, z = 1),
+    /// and the position of `p` between the centers, from 0 to 1 on each axis.
+    fn surrounding_occupancy(&self, p: [f32; 3]) -> ([f32; 8], [f32; 3]) {
+        let base = p.map(|c| (c - 0.5).floor());
+        let t = std::array::from_fn(|axis| p[axis] - 0.5 - base[axis]);
+        let corners = std::array::from_fn(|i| {
+            let c = [0, 1, 2].map(|axis| base[axis] as i32 + (i & (4 >> axis) != 0) as i32);
+            let inside = c.iter().all(|c| (0..Self::SIDE as i32).contains(c));
+            let solid = inside && self.contains(PosU8::new(c[0] as u8, c[1] as u8, c[2] as u8));
+            solid as u8 as f32
+        });
+        (corners, t)
+    }
+
+    /// a Wavefront OBJ of all [`Octree::exposed_faces`] as quads, for looking at the tree in a 3D viewer.
+    /// Vertices shared between faces are only written once.
+    pub fn to_obj(&self) -> String {
+        let mut vertices: HashMap<[u16; 3], usize> = HashMap::new();
+        let mut obj = String::new();
+        let mut faces = String::new();
+        for (pos, face, _) in self.exposed_faces() {
+            let (u_axis, v_axis) = face.plane_axes();
+            let mut corner = [pos.x as u16, pos.y as u16, pos.z as u16];
+            if face.is_positive() {
+                corner[face.axis()] += 1;
+            }
+            let mut corners: Vec<[u16; 3]> = [(0, 0), (1, 0), (1, 1), (0, 1)]
+                .into_iter()
+                .map(|(du, dv)| {
+                    let mut c = corner;
+                    c[u_axis] += du;
+                    c[v_axis] += dv;
+                    c
+                })
+                .collect();
+            // counter clockwise seen from outside, the plane axes of y faces span a left handed basis:
+            if face.is_positive() == (face.axis() == 1) {
+                corners.reverse();
+            }
+            faces.push('f');
+            for c in corners {
+                let next_index = vertices.len() + 1;
+                let index = *vertices.entry(c).or_insert_with(|| {
+                    writeln!(obj, "v {} {} {}", c[0], c[1], c[2]).unwrap();
+                    next_index
+                });
+                write!(faces, " {index}").unwrap();
+            }
+            faces.push('\n');
+        }
+        obj.push_str(&faces);
+        obj
+    }
+
+    /// the center of every set voxel, as a point cloud for debugging.
+    pub fn to_points(&self) -> Vec<[f32; 3]> {
+        let mut points = Vec::with_capacity(self.len() as usize);
+        self.for_each_voxel(|pos, _| {
+            points.push([pos.x as f32 + 0.5, pos.y as f32 + 0.5, pos.z as f32 + 0.5])
+        });
+        points
+    }
+
+    /// origin of the cube with the same `width` next to the cube at `origin` in direction `face`,
+    /// `None` if it would be outside of the octree.
+    fn neighbor_cube(origin: PosU8, width: u16, face: Face) -> Option<PosU8> {
+        let mut c: [u16; 3] = [origin.x as u16, origin.y as u16, origin.z as u16];
+        let axis = face.axis();
+        if face.is_positive() {
+            c[axis] += width;
+            if c[axis] >= Self::SIDE {
+                return None;
+            }
+        } else {
+            c[axis] = c[axis].checked_sub(width)?;
+        }
+        Some(PosU8::new(c[0] as u8, c[1] as u8, c[2] as u8))
+    }
+
+    /// occupancy of the cube at `origin` with side `width`, which must be aligned to the tree,
+    /// i.e. `width` a power of two and `origin` a multiple of it.
+    pub(crate) fn cube_occupancy(&self, origin: PosU8, width: u16) -> Occupancy {
+        let mut pos = origin;
+        let mut node_ptr = 0;
+        let mut half_width = HALF_WIDTH;
+        loop {
+            match self.nodes[node_ptr] {
+                Node::Full(_) => return Occupancy::Full,
+                Node::Mixed(ptrs, _) => {
+                    if half_width as u16 * 2 == width {
+                        return if node_ptr == 0 && self.nodes[0].is_empty() {
+                            Occupancy::Empty
+                        } else {
+                            Occupancy::Mixed
+                        };
+                    }
+                    let ptr = ptrs[Self::oct_index(&mut pos, half_width)];
+                    if ptr == EMPTY_PTR {
+                        return Occupancy::Empty;
+                    } else if half_width == 1 {
+                        // points to leaf
+                        return Occupancy::Full;
+                    }
+                    node_ptr = ptr;
+                    half_width /= 2;
+                }
+            }
+        }
+    }
+}
+
+/// the voxel next to `pos` in direction `face`, must not leave the `u8` range.
+fn step(pos: PosU8, face: Face) -> PosU8 {
+    match face {
+        Face::PosX => pos + PosU8::X,
+        Face::NegX => pos - PosU8::X,
+        Face::PosY => pos + PosU8::Y,
+        Face::NegY => pos - PosU8::Y,
+        Face::PosZ => pos + PosU8::Z,
+        Face::NegZ => pos - PosU8::Z,
+    }
+}
+
+/// the voxels of the cube at `origin` with side `width` that touch its `face` side.
+fn side_voxels(origin: PosU8, width: u16, face: Face) -> impl Iterator<Item = PosU8> {
+    let axis = face.axis();
+    let layer = if face.is_positive() {
+        width as u32 - 1
+    } else {
+        0
+    };
+    // u32, because a side of the whole tree has 256 * 256 voxels.
+    let width = width as u32;
+    (0..width * width).map(move |i| {
+        let (u, v) = (i % width, i / width);
+        let offset = match axis {
+            0 => [layer, u, v],
+            1 => [u, layer, v],
+            _ => [u, v, layer],
+        };
+        let o: [u8; 3] = offset.map(|c| c as u8);
+        origin + PosU8::from(o)
+    })
+}
+
+/// the trilinear weight of the corner in octant `i` along `axis`, for the position `t` between the corners.
+fn corner_weight(i: usize, axis: usize, t: [f32; 3]) -> f32 {
+    if i & (4 >> axis) != 0 {
+        t[axis]
+    } else {
+        1.0 - t[axis]
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::collections::HashSet;
+
+    use rand::{thread_rng, Rng};
+
+    use super::{Face, Quad};
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn exposed_faces_of_cube() {
+        let mut octree = Octree::<u8, 16>::new();
+        octree.fill_box(pos!(3, 4, 5), pos!(12, 13, 14), 1);
+        let faces = octree.exposed_faces();
+        assert_eq!(faces.len(), 6 * 10 * 10);
+
+        let unique: HashSet<(PosU8, Face)> = faces.iter().map(|(p, f, _)| (*p, *f)).collect();
+        assert_eq!(unique.len(), faces.len());
+        for (pos, face, val) in faces {
+            assert_eq!(val, 1);
+            let on_side = match face {
+                Face::PosX => pos.x == 12,
+                Face::NegX => pos.x == 3,
+                Face::PosY => pos.y == 13,
+                Face::NegY => pos.y == 4,
+                Face::PosZ => pos.z == 14,
+                Face::NegZ => pos.z == 5,
+            };
+            assert!(on_side, "interior face {face:?} at {pos:?}");
+        }
+    }
+
+    #[test]
+    pub fn exposed_faces_at_octree_border() {
+        let mut octree = Octree::<u8, 2>::new();
+        octree.fill_box(PosU8::ZERO, pos!(3, 3, 3), 1);
+        assert_eq!(octree.exposed_faces().len(), 6 * 4 * 4);
+    }
+
+    #[test]
+    pub fn exposed_faces_of_full_root_of_largest_tree() {
+        // a side of 256 * 256 voxels does not fit into a u16:
+        let octree = Octree::<u8, 128>::filled(1);
+        assert_eq!(octree.exposed_faces().len(), 6 * 256 * 256);
+        assert_eq!(octree.greedy_mesh().len(), 6);
+    }
+
+    #[test]
+    pub fn exposed_faces_match_brute_force() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u8, 8>::new();
+        for _ in 0..600 {
+            let pos = pos!(
+                rng.gen_range(0..16),
+                rng.gen_range(0..16),
+                rng.gen_range(0..16)
+            );
+            octree.insert(pos, 1);
+        }
+        octree.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), 2);
+
+        let mut expected = HashSet::new();
+        for (pos, _) in octree.iter() {
+            for face in Face::ALL {
+                let n = face.normal();
+                let neighbor = [
+                    pos.x as i16 + n[0] as i16,
+                    pos.y as i16 + n[1] as i16,
+                    pos.z as i16 + n[2] as i16,
+                ];
+                let outside = neighbor.iter().any(|c| !(0..16).contains(c));
+                if outside
+                    || !octree.contains(pos!(
+                        neighbor[0] as u8,
+                        neighbor[1] as u8,
+                        neighbor[2] as u8
+                    ))
+                {
+                    expected.insert((pos, face));
+                }
+            }
+        }
+        let faces = octree.exposed_faces();
+        let found: HashSet<(PosU8, Face)> = faces.iter().map(|(p, f, _)| (*p, *f)).collect();
+        assert_eq!(found.len(), faces.len());
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    pub fn greedy_mesh_flat_slab() {
+        let mut octree = Octree::<u8, 16>::new();
+        octree.fill_box(pos!(0, 5, 0), pos!(15, 5, 15), 1);
+        let quads = octree.greedy_mesh();
+        assert_eq!(quads.len(), 6);
+        assert!(quads.contains(&Quad {
+            origin: pos!(0, 5, 0),
+            width: 16,
+            height: 16,
+            face: Face::PosY,
+            val: 1
+        }));
+        assert!(quads.contains(&Quad {
+            origin: pos!(15, 5, 0),
+            width: 1,
+            height: 16,
+            face: Face::PosX,
+            val: 1
+        }));
+        let area: u32 = quads.iter().map(|q| q.width as u32 * q.height as u32).sum();
+        assert_eq!(area as usize, octree.exposed_faces().len());
+    }
+
+    #[test]
+    pub fn greedy_mesh_keeps_values_apart() {
+        let mut octree = Octree::<u8, 4>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(3, 0, 7), 1);
+        octree.fill_box(pos!(4, 0, 0), pos!(7, 0, 7), 2);
+        let quads = octree.greedy_mesh();
+        let top: Vec<_> = quads.iter().filter(|q| q.face == Face::PosY).collect();
+        assert_eq!(top.len(), 2);
+        assert!(top.iter().all(|q| q.width == 4 && q.height == 8));
+
+        let mut covered = HashSet::new();
+        for q in quads.iter() {
+            let (u_axis, v_axis) = q.face.plane_axes();
+            for du in 0..q.width as u8 {
+                for dv in 0..q.height as u8 {
+                    let mut c = [q.origin.x, q.origin.y, q.origin.z];
+                    c[u_axis] += du;
+                    c[v_axis] += dv;
+                    let pos = PosU8::from(c);
+                    assert_eq!(octree.get(pos), Some(q.val));
+                    assert!(covered.insert((pos, q.face)));
+                }
+            }
+        }
+        assert_eq!(covered.len(), octree.exposed_faces().len());
+    }
+
+    #[test]
+    pub fn to_obj_single_voxel() {
+        let mut octree = Octree::<u8, 4>::new();
+        octree.insert(pos!(1, 2, 3), 1);
+        let obj = octree.to_obj();
+        let vertices: Vec<&str> = obj.lines().filter(|l| l.starts_with("v ")).collect();
+        let faces: Vec<&str> = obj.lines().filter(|l| l.starts_with("f ")).collect();
+        assert_eq!(vertices.len(), 8);
+        assert_eq!(faces.len(), 6);
+        assert!(vertices.contains(&"v 1 2 3"));
+        assert!(vertices.contains(&"v 2 3 4"));
+        for face in faces {
+            assert_eq!(face.split(' ').count(), 5);
+        }
+    }
+
+    #[test]
+    pub fn to_points() {
+        let mut octree = Octree::<u8, 4>::new();
+        octree.insert(pos!(1, 2, 3), 1);
+        assert_eq!(octree.to_points(), vec![[1.5, 2.5, 3.5]]);
+        octree.fill_box(pos!(4, 4, 4), pos!(7, 7, 7), 2);
+        assert_eq!(octree.to_points().len(), 65);
+    }
+
+    #[test]
+    pub fn face_ao() {
+        let mut octree = Octree::<u8, 4>::new();
+        octree.insert(pos!(4, 4, 4), 1);
+        assert_eq!(octree.face_ao(pos!(4, 4, 4), Face::PosY), [3, 3, 3, 3]);
+
+        // an edge neighbor on the +x side above the voxel:
+        octree.insert(pos!(5, 5, 4), 1);
+        assert_eq!(octree.face_ao(pos!(4, 4, 4), Face::PosY), [3, 2, 2, 3]);
+        // plus the diagonal neighbor of the (1, 1) corner:
+        octree.insert(pos!(5, 5, 5), 1);
+        assert_eq!(octree.face_ao(pos!(4, 4, 4), Face::PosY), [3, 2, 1, 3]);
+        // plus the other side of the (1, 1) corner, which is then fully occluded:
+        octree.insert(pos!(4, 5, 5), 1);
+        assert_eq!(octree.face_ao(pos!(4, 4, 4), Face::PosY), [3, 2, 0, 2]);
+        // the face below is not affected:
+        assert_eq!(octree.face_ao(pos!(4, 4, 4), Face::NegY), [3, 3, 3, 3]);
+
+        // neighbors outside of the tree are empty:
+        octree.insert(pos!(0, 0, 0), 1);
+        assert_eq!(octree.face_ao(pos!(0, 0, 0), Face::NegX), [3, 3, 3, 3]);
+    }
+
+    #[test]
+    pub fn occupancy_and_gradient_at_boundary() {
+        // solid for x < 8, empty for x >= 8:
+        let mut octree = Octree::<u32, 8>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(7, 15, 15), 1);
+
+        assert_eq!(octree.sample_occupancy([3.5, 4.5, 5.5]), 1.0);
+        assert_eq!(octree.sample_occupancy([12.5, 4.5, 5.5]), 0.0);
+        // halfway between the centers of the last solid and first empty voxel:
+        assert_eq!(octree.sample_occupancy([8.0, 4.5, 5.5]), 0.5);
+        assert_eq!(octree.sample_occupancy([7.75, 8.0, 8.0]), 0.75);
+        // voxels outside of the tree count as empty:
+        assert_eq!(octree.sample_occupancy([0.0, 4.5, 5.5]), 0.5);
+
+        // the gradient points across the interface, from the empty to the solid side:
+        let [gx, gy, gz] = octree.gradient([8.0, 8.0, 8.0]);
+        assert_eq!(gx, -1.0);
+        assert_eq!(gy, 0.0);
+        assert_eq!(gz, 0.0);
+        assert_eq!(octree.gradient([3.0, 3.0, 3.0]), [0.0, 0.0, 0.0]);
+
+        // a diagonal interface:
+        let mut octree = Octree::<u32, 8>::new();
+        for pos in PosU8::iter_box(pos!(0, 0, 0), pos!(15, 15, 15)) {
+            if pos.x + pos.y < 16 {
+                octree.insert(pos, 1);
+            }
+        }
+        let [gx, gy, gz] = octree.gradient([8.0, 8.0, 8.0]);
+        assert!(gx < 0.0 && gy < 0.0);
+        assert!((gx - gy).abs() < 1e-6);
+        assert_eq!(gz, 0.0);
+    }
+}
@@ -0,0 +1,164 @@
+use slab::Slab;
+
+use crate::{oct_offset, Node, Octree, PosU8, Ptr, EMPTY_PTR};
+
+/// Read-only view of a node of an [`Octree`] for custom traversals, see [`Octree::root`].
+///
+/// A `NodeRef` is either a Full node, a leaf (a single voxel) or a Mixed node with up to 8 children.
+/// Empty children do not exist as `NodeRef`s.
+pub struct NodeRef<'a, V> {
+    nodes: &'a Slab<Node<V>>,
+    leafs: &'a Slab<V>,
+    kind: NodeRefKind,
+    origin: PosU8,
+    /// half of the width, 0 for a leaf.
+    half_width: u8,
+}
+
+#[derive(Clone, Copy)]
+enum NodeRefKind {
+    Node(Ptr),
+    Leaf(Ptr),
+}
+
+impl<V> Clone for NodeRef<'_, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for NodeRef<'_, V> {}
+
+impl<'a, V> NodeRef<'a, V> {
+    /// the value of every voxel in the node for Full nodes and leafs, `None` for Mixed nodes.
+    pub fn is_full(&self) -> Option<&'a V> {
+        match self.kind {
+            NodeRefKind::Leaf(leaf_ptr) => Some(&self.leafs[leaf_ptr]),
+            NodeRefKind::Node(node_ptr) => match self.nodes[node_ptr] {
+                Node::Full(ref val) => Some(val),
+                Node::Mixed(..) => None,
+            },
+        }
+    }
+
+    /// the child in octant `i` (x = 4, y = 2, z = 1) of a Mixed node, `None` if it is empty
+    /// or if this node is a Full node or a leaf.
+    ///
+    /// panics if `i >= 8`.
+    pub fn child(&self, i: usize) -> Option<NodeRef<'a, V>> {
+        assert!(i < 8, "octant {i} is not in 0..8");
+        let NodeRefKind::Node(node_ptr) = self.kind else {
+            return None;
+        };
+        let Node::Mixed(ptrs, _) = self.nodes[node_ptr] else {
+            return None;
+        };
+        let ptr = ptrs[i];
+        if ptr == EMPTY_PTR {
+            return None;
+        }
+        let kind = if self.half_width == 1 {
+            NodeRefKind::Leaf(ptr)
+        } else {
+            NodeRefKind::Node(ptr)
+        };
+        Some(NodeRef {
+            kind,
+            origin: self.origin + oct_offset(i, self.half_width),
+            half_width: self.half_width / 2,
+            ..*self
+        })
+    }
+
+    /// side width of the cube covered by the node, 1 for a leaf.
+    pub fn width(&self) -> u16 {
+        if self.half_width == 0 {
+            1
+        } else {
+            self.half_width as u16 * 2
+        }
+    }
+
+    /// the corner of the cube covered by the node with the smallest coordinates.
+    pub fn origin(&self) -> PosU8 {
+        self.origin
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH> {
+    /// the root node, for walking the tree structure without going through its slabs.
+    /// An empty tree has a Mixed root without children.
+    pub fn root(&self) -> NodeRef<'_, V> {
+        NodeRef {
+            nodes: &self.nodes,
+            leafs: &self.leafs,
+            kind: NodeRefKind::Node(0),
+            origin: PosU8::ZERO,
+            half_width: HALF_WIDTH,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use super::NodeRef;
+    use crate::{pos, Octree, PosU8};
+
+    fn collect(node: NodeRef<'_, u32>, voxels: &mut Vec<(PosU8, u32)>) {
+        if let Some(val) = node.is_full() {
+            let width = node.width();
+            for x in 0..width {
+                for y in 0..width {
+                    for z in 0..width {
+                        let offset = pos!(x as u8, y as u8, z as u8);
+                        voxels.push((node.origin() + offset, *val));
+                    }
+                }
+            }
+            return;
+        }
+        for i in 0..8 {
+            if let Some(child) = node.child(i) {
+                assert_eq!(child.width() * 2, node.width());
+                collect(child, voxels);
+            }
+        }
+    }
+
+    #[test]
+    pub fn node_ref_reconstructs_voxels() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 8>::new();
+        for _ in 0..300 {
+            let pos = pos!(
+                rng.gen_range(0..16),
+                rng.gen_range(0..16),
+                rng.gen_range(0..16)
+            );
+            octree.insert(pos, rng.gen_range(0..3));
+        }
+        octree.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 7);
+
+        let mut voxels = vec![];
+        collect(octree.root(), &mut voxels);
+        voxels.sort_by_key(|(pos, _)| *pos);
+        let mut expected: Vec<(PosU8, u32)> = octree.iter().collect();
+        expected.sort_by_key(|(pos, _)| *pos);
+        assert_eq!(voxels, expected);
+    }
+
+    #[test]
+    pub fn node_ref_root() {
+        let octree = Octree::<u32, 8>::new();
+        let root = octree.root();
+        assert_eq!(root.width(), 16);
+        assert_eq!(root.is_full(), None);
+        assert!((0..8).all(|i| root.child(i).is_none()));
+
+        let filled = Octree::<u32, 8>::filled(3);
+        assert_eq!(filled.root().is_full(), Some(&3));
+        assert!(filled.root().child(0).is_none());
+    }
+}
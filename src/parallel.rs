@@ -0,0 +1,150 @@
+use crate::{Node, Octree, PosU8, Ptr, EMPTY_PTR};
+
+/// inputs smaller than this are built sequentially by [`Octree::build_parallel`],
+/// spawning the threads costs more than they save.
+const MIN_PARALLEL_VOXELS: usize = 10_000;
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq + Send + Sync,
+{
+    /// builds a tree from `voxels` on 8 threads, one per octant of the root node.
+    ///
+    /// Every thread builds its octant in a tree of its own, afterwards the octants are moved into the slabs
+    /// of a fresh tree. The result is the same as collecting `voxels` sequentially, so later voxels overwrite
+    /// earlier ones at the same position.
+    ///
+    /// Small inputs and machines with a single core are built sequentially, where the threads would only
+    /// add overhead.
+    ///
+    /// panics if a position is out of bounds.
+    pub fn build_parallel(voxels: &[(PosU8, V)]) -> Self {
+        let single_core = std::thread::available_parallelism().map_or(true, |n| n.get() == 1);
+        if HALF_WIDTH == 1 || voxels.len() < MIN_PARALLEL_VOXELS || single_core {
+            // for a HALF_WIDTH of 1 the root's children are leafs, nothing worth splitting up.
+            return voxels.iter().cloned().collect();
+        }
+        Self::build_octants_parallel(voxels)
+    }
+
+    /// the threaded part of [`Octree::build_parallel`], `HALF_WIDTH` must be at least 2.
+    fn build_octants_parallel(voxels: &[(PosU8, V)]) -> Self {
+        let mut octants: [Vec<(PosU8, V)>; 8] = Default::default();
+        for (pos, val) in voxels {
+            Self::assert_in_bounds(*pos);
+            let idx = Self::oct_index(&mut pos.clone(), HALF_WIDTH);
+            octants[idx].push((*pos, val.clone()));
+        }
+
+        let subtrees: Vec<Self> = std::thread::scope(|scope| {
+            let handles: Vec<_> = octants
+                .into_iter()
+                .map(|octant| scope.spawn(move || octant.into_iter().collect::<Self>()))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("octant build panicked"))
+                .collect()
+        });
+
+        let mut octree = Self::new();
+        let mut root_ptrs = [EMPTY_PTR; 8];
+        for (i, mut subtree) in subtrees.into_iter().enumerate() {
+            let Node::Mixed(ptrs, _) = subtree.nodes[0] else {
+                unreachable!("only one octant of the subtree is set, so its root can not be Full");
+            };
+            if ptrs[i] != EMPTY_PTR {
+                root_ptrs[i] = octree.adopt_subtree(&mut subtree, ptrs[i], HALF_WIDTH / 2);
+            }
+        }
+        octree.nodes[0] = Node::from_ptrs(root_ptrs);
+        // all octants might be Full with the same value:
+        octree.merge_path(vec![(0, HALF_WIDTH)]);
+        octree
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// moves the node at `node_ptr` with `half_width` and everything below it out of `other` into this tree,
+    /// returns the pointer of the node in this tree.
+    fn adopt_subtree(&mut self, other: &mut Self, node_ptr: Ptr, half_width: u8) -> Ptr {
+        let node = match other.nodes.remove(node_ptr) {
+            Node::Full(val) => Node::Full(val),
+            Node::Mixed(ptrs, _) => Node::from_ptrs(ptrs.map(|ptr| {
+                if ptr == EMPTY_PTR {
+                    EMPTY_PTR
+                } else if half_width == 1 {
+                    // points to leaf
+                    self.leafs.insert(other.leafs.remove(ptr))
+                } else {
+                    self.adopt_subtree(other, ptr, half_width / 2)
+                }
+            })),
+        };
+        self.nodes.insert(node)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn build_parallel_matches_sequential() {
+        let mut rng = thread_rng();
+        let mut voxels: Vec<(PosU8, u32)> = (0..20_000)
+            .map(|_| {
+                let pos = pos!(
+                    rng.gen_range(0..64),
+                    rng.gen_range(0..64),
+                    rng.gen_range(0..64)
+                );
+                (pos, rng.gen_range(0..3))
+            })
+            .collect();
+        // a uniform region and overwritten voxels:
+        for x in 0..32 {
+            for y in 0..32 {
+                for z in 0..32 {
+                    voxels.push((pos!(x, y, z), 9));
+                }
+            }
+        }
+        voxels.push((pos!(1, 2, 3), 10));
+
+        let sequential: Octree<u32, 32> = voxels.iter().cloned().collect();
+        // the threaded path directly, build_parallel falls back to a sequential build on a single core:
+        let parallel = Octree::<u32, 32>::build_octants_parallel(&voxels);
+        assert!(parallel == sequential);
+        assert!(Octree::<u32, 32>::build_parallel(&voxels) == sequential);
+        assert_eq!(parallel.encode(), sequential.encode());
+        assert_eq!(parallel.node_count(), sequential.node_count());
+        assert_eq!(parallel.leaf_count(), sequential.leaf_count());
+    }
+
+    #[test]
+    pub fn build_parallel_full_and_empty() {
+        let mut voxels = vec![];
+        for x in 0..8 {
+            for y in 0..8 {
+                for z in 0..8 {
+                    voxels.push((pos!(x, y, z), 1u32));
+                }
+            }
+        }
+        let octree = Octree::<u32, 4>::build_octants_parallel(&voxels);
+        assert_eq!(octree.node_count(), 1);
+        assert_eq!(octree.leaf_count(), 0);
+        assert_eq!(octree.len(), 512);
+
+        assert!(Octree::<u32, 4>::build_parallel(&[]).is_empty());
+        assert!(Octree::<u32, 4>::build_octants_parallel(&[]).is_empty());
+        let tiny = Octree::<u32, 1>::build_parallel(&[(pos!(1, 0, 1), 3)]);
+        assert_eq!(tiny.get(pos!(1, 0, 1)), Some(3));
+    }
+}
@@ -21,11 +21,167 @@ impl PosU8 {
     pub const Y: Self = Self { x: 0, y: 1, z: 0 };
     pub const Z: Self = Self { x: 0, y: 0, z: 1 };
     pub const ZERO: Self = Self { x: 0, y: 0, z: 0 };
+    pub const ONE: Self = Self { x: 1, y: 1, z: 1 };
+    pub const MAX: Self = Self {
+        x: u8::MAX,
+        y: u8::MAX,
+        z: u8::MAX,
+    };
 
     pub fn new(x: u8, y: u8, z: u8) -> Self {
         PosU8 { x, y, z }
     }
 
+    /// a position with all components set to `n`.
+    pub fn splat(n: u8) -> Self {
+        PosU8 { x: n, y: n, z: n }
+    }
+
+    pub fn as_array(self) -> [u8; 3] {
+        [self.x, self.y, self.z]
+    }
+
+    pub fn as_tuple(self) -> (u8, u8, u8) {
+        (self.x, self.y, self.z)
+    }
+
+    /// component-wise addition, `None` if any component overflows.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        Some(PosU8 {
+            x: self.x.checked_add(rhs.x)?,
+            y: self.y.checked_add(rhs.y)?,
+            z: self.z.checked_add(rhs.z)?,
+        })
+    }
+
+    /// component-wise subtraction, `None` if any component underflows.
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        Some(PosU8 {
+            x: self.x.checked_sub(rhs.x)?,
+            y: self.y.checked_sub(rhs.y)?,
+            z: self.z.checked_sub(rhs.z)?,
+        })
+    }
+
+    /// component-wise addition, clamping at 255.
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        PosU8 {
+            x: self.x.saturating_add(rhs.x),
+            y: self.y.saturating_add(rhs.y),
+            z: self.z.saturating_add(rhs.z),
+        }
+    }
+
+    /// component-wise subtraction, clamping at 0.
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        PosU8 {
+            x: self.x.saturating_sub(rhs.x),
+            y: self.y.saturating_sub(rhs.y),
+            z: self.z.saturating_sub(rhs.z),
+        }
+    }
+
+    /// component-wise addition, wrapping around at the boundary of `u8`.
+    pub fn wrapping_add(self, rhs: Self) -> Self {
+        PosU8 {
+            x: self.x.wrapping_add(rhs.x),
+            y: self.y.wrapping_add(rhs.y),
+            z: self.z.wrapping_add(rhs.z),
+        }
+    }
+
+    /// component-wise subtraction, wrapping around at the boundary of `u8`.
+    pub fn wrapping_sub(self, rhs: Self) -> Self {
+        PosU8 {
+            x: self.x.wrapping_sub(rhs.x),
+            y: self.y.wrapping_sub(rhs.y),
+            z: self.z.wrapping_sub(rhs.z),
+        }
+    }
+
+    /// component-wise minimum. Note that this shadows the lexicographic [`Ord::min`].
+    pub fn min(self, other: Self) -> Self {
+        PosU8 {
+            x: self.x.min(other.x),
+            y: self.y.min(other.y),
+            z: self.z.min(other.z),
+        }
+    }
+
+    /// component-wise maximum. Note that this shadows the lexicographic [`Ord::max`].
+    pub fn max(self, other: Self) -> Self {
+        PosU8 {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y),
+            z: self.z.max(other.z),
+        }
+    }
+
+    /// sum of the absolute component differences.
+    pub fn manhattan(self, other: Self) -> u32 {
+        self.x.abs_diff(other.x) as u32
+            + self.y.abs_diff(other.y) as u32
+            + self.z.abs_diff(other.z) as u32
+    }
+
+    /// squared euclidean distance, computed in `u32` because it exceeds `u16` for far apart positions.
+    pub fn dist_squared(self, other: Self) -> u32 {
+        let dx = self.x.abs_diff(other.x) as u32;
+        let dy = self.y.abs_diff(other.y) as u32;
+        let dz = self.z.abs_diff(other.z) as u32;
+        dx * dx + dy * dy + dz * dz
+    }
+
+    /// the face neighbors in the order +x, -x, +y, -y, +z, -z.
+    /// Neighbors that would over- or underflow a `u8` are skipped.
+    pub fn neighbors_6(self) -> impl Iterator<Item = PosU8> {
+        [
+            self.checked_add(PosU8::X),
+            self.checked_sub(PosU8::X),
+            self.checked_add(PosU8::Y),
+            self.checked_sub(PosU8::Y),
+            self.checked_add(PosU8::Z),
+            self.checked_sub(PosU8::Z),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// like [`PosU8::neighbors_6`], but also skips neighbors outside of a tree with the given `half_width`.
+    pub fn neighbors_6_bounded(self, half_width: u8) -> impl Iterator<Item = PosU8> {
+        let side = half_width as u16 * 2;
+        self.neighbors_6()
+            .filter(move |n| (n.x as u16) < side && (n.y as u16) < side && (n.z as u16) < side)
+    }
+
+    /// all positions in the box `min..=max`, x changes fastest, then y, then z.
+    /// Yields nothing if `min` is larger than `max` on any axis.
+    pub fn iter_box(min: PosU8, max: PosU8) -> impl Iterator<Item = PosU8> {
+        (min.z..=max.z).flat_map(move |z| {
+            (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| PosU8 { x, y, z }))
+        })
+    }
+
+    /// Morton (Z-order) code of the position, interleaving the bits of the components.
+    ///
+    /// Bit `i` of x, y and z ends up at bit `3i + 2`, `3i + 1` and `3i` of the code, so every
+    /// group of 3 bits is an octant index as used by the [`crate::Octree`] (x: 4, y: 2, z: 1),
+    /// with the most significant group being the octant of the root node in a tree of `HALF_WIDTH` 128.
+    pub fn to_morton(self) -> u32 {
+        spread_bits(self.x) << 2 | spread_bits(self.y) << 1 | spread_bits(self.z)
+    }
+
+    /// inverse of [`PosU8::to_morton`], bits above the lowest 24 are ignored.
+    pub fn from_morton(code: u32) -> Self {
+        PosU8 {
+            x: compact_bits(code >> 2),
+            y: compact_bits(code >> 1),
+            z: compact_bits(code),
+        }
+    }
+
+    // The `plus_*`/`minus_*` helpers and the operator impls below overflow like plain `u8` arithmetic
+    // (panic in debug builds). Use the checked variants near the boundary.
     pub fn plus_x(&self) -> Self {
         PosU8 {
             x: self.x + 1,
@@ -131,12 +287,102 @@ impl PosU8 {
     }
 }
 
+/// moves bit `i` of `v` to bit `3i`.
+fn spread_bits(v: u8) -> u32 {
+    let mut v = v as u32;
+    v = (v | v << 8) & 0x0000_f00f;
+    v = (v | v << 4) & 0x000c_30c3;
+    v = (v | v << 2) & 0x0024_9249;
+    v
+}
+
+/// inverse of [`spread_bits`], moves bit `3i` of `v` to bit `i`.
+fn compact_bits(v: u32) -> u8 {
+    let mut v = v & 0x0024_9249;
+    v = (v | v >> 2) & 0x000c_30c3;
+    v = (v | v >> 4) & 0x0000_f00f;
+    v = (v | v >> 8) & 0x0000_00ff;
+    v as u8
+}
+
 impl From<PosU8> for [f32; 3] {
     fn from(pos: PosU8) -> Self {
         [pos.x as f32, pos.y as f32, pos.z as f32]
     }
 }
 
+impl From<[u8; 3]> for PosU8 {
+    fn from([x, y, z]: [u8; 3]) -> Self {
+        PosU8 { x, y, z }
+    }
+}
+
+impl From<(u8, u8, u8)> for PosU8 {
+    fn from((x, y, z): (u8, u8, u8)) -> Self {
+        PosU8 { x, y, z }
+    }
+}
+
+/// Error for conversions into a [`PosU8`] where a component does not fit into a `u8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PosOutOfRange;
+
+impl std::fmt::Display for PosOutOfRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "position component out of range for u8")
+    }
+}
+
+impl std::error::Error for PosOutOfRange {}
+
+impl TryFrom<[i32; 3]> for PosU8 {
+    type Error = PosOutOfRange;
+
+    fn try_from([x, y, z]: [i32; 3]) -> Result<Self, Self::Error> {
+        let c = |c: i32| u8::try_from(c).map_err(|_| PosOutOfRange);
+        Ok(PosU8 {
+            x: c(x)?,
+            y: c(y)?,
+            z: c(z)?,
+        })
+    }
+}
+
+impl TryFrom<(u32, u32, u32)> for PosU8 {
+    type Error = PosOutOfRange;
+
+    fn try_from((x, y, z): (u32, u32, u32)) -> Result<Self, Self::Error> {
+        let c = |c: u32| u8::try_from(c).map_err(|_| PosOutOfRange);
+        Ok(PosU8 {
+            x: c(x)?,
+            y: c(y)?,
+            z: c(z)?,
+        })
+    }
+}
+
+/// rounds each component to the nearest integer.
+impl TryFrom<[f32; 3]> for PosU8 {
+    type Error = PosOutOfRange;
+
+    fn try_from([x, y, z]: [f32; 3]) -> Result<Self, Self::Error> {
+        let c = |c: f32| {
+            let c = c.round();
+            // also rejects NaN:
+            if (0.0..=255.0).contains(&c) {
+                Ok(c as u8)
+            } else {
+                Err(PosOutOfRange)
+            }
+        };
+        Ok(PosU8 {
+            x: c(x)?,
+            y: c(y)?,
+            z: c(z)?,
+        })
+    }
+}
+
 impl std::ops::Add for PosU8 {
     type Output = Self;
 
@@ -177,6 +423,30 @@ impl std::ops::SubAssign for PosU8 {
     }
 }
 
+impl std::ops::Mul<u8> for PosU8 {
+    type Output = Self;
+
+    fn mul(self, rhs: u8) -> Self::Output {
+        PosU8 {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+
+impl std::ops::Div<u8> for PosU8 {
+    type Output = Self;
+
+    fn div(self, rhs: u8) -> Self::Output {
+        PosU8 {
+            x: self.x / rhs,
+            y: self.y / rhs,
+            z: self.z / rhs,
+        }
+    }
+}
+
 // #[macro_export]
 // macro_rules! pos {
 //     ($x:expr, $y:expr, $z:expr ) => {
@@ -187,3 +457,199 @@ impl std::ops::SubAssign for PosU8 {
 //         }
 //     };
 // }
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use super::{PosOutOfRange, PosU8};
+
+    #[test]
+    pub fn overflow_safe_arithmetic() {
+        let max = PosU8::new(255, 255, 255);
+        assert_eq!(max.checked_add(PosU8::X), None);
+        assert_eq!(max.checked_add(PosU8::ZERO), Some(max));
+        assert_eq!(
+            PosU8::new(254, 0, 0).checked_add(PosU8::X),
+            Some(PosU8::new(255, 0, 0))
+        );
+        assert_eq!(PosU8::ZERO.checked_sub(PosU8::Z), None);
+        assert_eq!(PosU8::Z.checked_sub(PosU8::Z), Some(PosU8::ZERO));
+
+        assert_eq!(max.saturating_add(PosU8::Y), max);
+        assert_eq!(PosU8::ZERO.saturating_sub(PosU8::Y), PosU8::ZERO);
+        assert_eq!(
+            PosU8::new(3, 0, 255).saturating_sub(PosU8::new(1, 1, 1)),
+            PosU8::new(2, 0, 254)
+        );
+
+        assert_eq!(max.wrapping_add(PosU8::X), PosU8::new(0, 255, 255));
+        assert_eq!(PosU8::ZERO.wrapping_sub(PosU8::Z), PosU8::new(0, 0, 255));
+    }
+
+    #[test]
+    pub fn scalar_ops_and_min_max() {
+        assert_eq!(PosU8::new(1, 2, 3) * 4, PosU8::new(4, 8, 12));
+        assert_eq!(PosU8::new(9, 2, 255) / 2, PosU8::new(4, 1, 127));
+
+        let a = PosU8::new(5, 1, 9);
+        let b = PosU8::new(2, 8, 3);
+        assert_eq!(a.min(b), PosU8::new(2, 1, 3));
+        assert_eq!(a.max(b), PosU8::new(5, 8, 9));
+        assert_eq!(b.min(a), PosU8::new(2, 1, 3));
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn div_by_zero_panics() {
+        let _ = PosU8::new(1, 2, 3) / 0;
+    }
+
+    #[test]
+    pub fn distances() {
+        let a = PosU8::new(1, 5, 3);
+        let b = PosU8::new(4, 1, 3);
+        assert_eq!(a.manhattan(b), 7);
+        assert_eq!(b.manhattan(a), 7);
+        assert_eq!(a.dist_squared(b), 25);
+        assert_eq!(a.dist_squared(a), 0);
+
+        // opposite corners of a 256x256x256 tree:
+        let max = PosU8::new(255, 255, 255);
+        assert_eq!(PosU8::ZERO.manhattan(max), 3 * 255);
+        assert_eq!(PosU8::ZERO.dist_squared(max), 3 * 255 * 255);
+        assert_eq!(max.dist_squared(PosU8::ZERO), 195075);
+    }
+
+    #[test]
+    pub fn neighbors() {
+        let neighbors: Vec<PosU8> = PosU8::new(5, 5, 5).neighbors_6().collect();
+        assert_eq!(
+            neighbors,
+            vec![
+                PosU8::new(6, 5, 5),
+                PosU8::new(4, 5, 5),
+                PosU8::new(5, 6, 5),
+                PosU8::new(5, 4, 5),
+                PosU8::new(5, 5, 6),
+                PosU8::new(5, 5, 4),
+            ]
+        );
+        assert_eq!(PosU8::ZERO.neighbors_6().count(), 3);
+        assert_eq!(PosU8::new(255, 255, 255).neighbors_6().count(), 3);
+        assert_eq!(PosU8::new(0, 255, 7).neighbors_6().count(), 4);
+
+        // corners of a 32x32x32 tree:
+        assert_eq!(PosU8::ZERO.neighbors_6_bounded(16).count(), 3);
+        let corner = PosU8::new(31, 31, 31);
+        let neighbors: Vec<PosU8> = corner.neighbors_6_bounded(16).collect();
+        assert_eq!(
+            neighbors,
+            vec![
+                PosU8::new(30, 31, 31),
+                PosU8::new(31, 30, 31),
+                PosU8::new(31, 31, 30)
+            ]
+        );
+        assert_eq!(PosU8::new(5, 31, 5).neighbors_6_bounded(16).count(), 5);
+        assert_eq!(PosU8::new(255, 0, 5).neighbors_6_bounded(128).count(), 4);
+    }
+
+    #[test]
+    pub fn constants_and_conversions() {
+        assert_eq!(PosU8::MAX, PosU8::new(255, 255, 255));
+        assert_eq!(PosU8::ONE, PosU8::X + PosU8::Y + PosU8::Z);
+        assert_eq!(PosU8::splat(7), PosU8::new(7, 7, 7));
+        assert_eq!(PosU8::splat(0), PosU8::ZERO);
+        assert_eq!(PosU8::MAX.checked_add(PosU8::ONE), None);
+
+        let pos = PosU8::new(1, 2, 3);
+        assert_eq!(pos.as_array(), [1, 2, 3]);
+        assert_eq!(pos.as_tuple(), (1, 2, 3));
+        assert_eq!(PosU8::from(pos.as_array()), pos);
+        assert_eq!(PosU8::from(pos.as_tuple()), pos);
+    }
+
+    #[test]
+    pub fn iter_box() {
+        let min = PosU8::new(1, 2, 3);
+        let max = PosU8::new(4, 4, 9);
+        let positions: Vec<PosU8> = PosU8::iter_box(min, max).collect();
+        assert_eq!(positions.len(), 4 * 3 * 7);
+        assert_eq!(positions[0], min);
+        assert_eq!(positions[1], PosU8::new(2, 2, 3));
+        assert_eq!(positions[4], PosU8::new(1, 3, 3));
+        assert_eq!(*positions.last().unwrap(), max);
+
+        assert_eq!(PosU8::iter_box(min, min).count(), 1);
+        // up to the last position of a u8:
+        let max = PosU8::new(255, 255, 255);
+        assert_eq!(
+            PosU8::iter_box(PosU8::new(250, 254, 0), max).count(),
+            6 * 2 * 256
+        );
+        // min larger than max on one axis:
+        assert_eq!(
+            PosU8::iter_box(PosU8::new(5, 0, 0), PosU8::new(4, 9, 9)).count(),
+            0
+        );
+        assert_eq!(
+            PosU8::iter_box(PosU8::new(0, 0, 9), PosU8::new(4, 9, 8)).count(),
+            0
+        );
+    }
+
+    #[test]
+    pub fn morton() {
+        assert_eq!(PosU8::ZERO.to_morton(), 0);
+        assert_eq!(PosU8::Z.to_morton(), 0b001);
+        assert_eq!(PosU8::Y.to_morton(), 0b010);
+        assert_eq!(PosU8::X.to_morton(), 0b100);
+        assert_eq!(PosU8::new(2, 0, 3).to_morton(), 0b101_001);
+        assert_eq!(PosU8::new(255, 255, 255).to_morton(), (1 << 24) - 1);
+
+        // every position of a 32x32x32 tree round trips and has a unique code below 32^3:
+        let mut seen = vec![false; 32 * 32 * 32];
+        for x in 0..32 {
+            for y in 0..32 {
+                for z in 0..32 {
+                    let pos = PosU8::new(x, y, z);
+                    let code = pos.to_morton();
+                    assert_eq!(PosU8::from_morton(code), pos);
+                    assert!(!seen[code as usize]);
+                    seen[code as usize] = true;
+                }
+            }
+        }
+
+        let mut rng = thread_rng();
+        for _ in 0..10000 {
+            let pos = PosU8::new(rng.gen(), rng.gen(), rng.gen());
+            assert_eq!(PosU8::from_morton(pos.to_morton()), pos);
+        }
+    }
+
+    #[test]
+    pub fn conversions() {
+        assert_eq!(PosU8::from([1, 2, 3]), PosU8::new(1, 2, 3));
+        assert_eq!(PosU8::from((1, 2, 3)), PosU8::new(1, 2, 3));
+
+        assert_eq!(PosU8::try_from([0, 255, 3]), Ok(PosU8::new(0, 255, 3)));
+        assert_eq!(PosU8::try_from([-1, 2, 3]), Err(PosOutOfRange));
+        assert_eq!(PosU8::try_from([1, 256, 3]), Err(PosOutOfRange));
+
+        assert_eq!(PosU8::try_from((7u32, 8, 255)), Ok(PosU8::new(7, 8, 255)));
+        assert_eq!(PosU8::try_from((7u32, 8, 256)), Err(PosOutOfRange));
+
+        assert_eq!(
+            PosU8::try_from([0.4f32, 1.5, 254.6]),
+            Ok(PosU8::new(0, 2, 255))
+        );
+        assert_eq!(PosU8::try_from([-0.6f32, 1.0, 1.0]), Err(PosOutOfRange));
+        assert_eq!(PosU8::try_from([1.0f32, 255.5, 1.0]), Err(PosOutOfRange));
+        assert_eq!(PosU8::try_from([1.0f32, f32::NAN, 1.0]), Err(PosOutOfRange));
+
+        let back: [f32; 3] = PosU8::new(4, 5, 6).into();
+        assert_eq!(PosU8::try_from(back), Ok(PosU8::new(4, 5, 6)));
+    }
+}
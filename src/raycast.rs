@@ -0,0 +1,489 @@
+use crate::{geom::ray_box, oct_offset, Node, Octree, PosU8, Ptr, EMPTY_PTR};
+
+/// a ray with normalized direction.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Ray {
+    pub origin: [f32; 3],
+    pub dir: [f32; 3],
+}
+
+impl Ray {
+    /// `None` if `dir` has no length.
+    pub(crate) fn new(origin: [f32; 3], dir: [f32; 3]) -> Option<Self> {
+        let len = (dir[0] * dir[0] + dir[1] * dir[1] + dir[2] * dir[2]).sqrt();
+        if len == 0.0 || !len.is_finite() {
+            return None;
+        }
+        Some(Ray {
+            origin,
+            dir: dir.map(|d| d / len),
+        })
+    }
+
+    /// `(t_enter, t_exit, enter_axis)` of the ray with the cube at `origin` with side `width`.
+    pub(crate) fn hit_cube(&self, origin: PosU8, width: u16) -> Option<(f32, f32, usize)> {
+        let min: [f32; 3] = origin.into();
+        let max = min.map(|c| c + width as f32);
+        ray_box(self.origin, self.dir, min, max)
+    }
+
+    /// `(t_enter, child index)` of all non empty children `ptrs` of the node at `origin` with
+    /// `half_width` the ray passes through within `max_dist`, sorted front to back.
+    pub(crate) fn hit_children(
+        &self,
+        max_dist: f32,
+        ptrs: &[Ptr; 8],
+        origin: PosU8,
+        half_width: u8,
+    ) -> Vec<(f32, usize)> {
+        let mut hit_children: Vec<(f32, usize)> = Vec::with_capacity(8);
+        for (i, ptr) in ptrs.iter().enumerate() {
+            if *ptr == EMPTY_PTR {
+                continue;
+            }
+            let child_origin = origin + oct_offset(i, half_width);
+            if let Some((t_enter, t_exit, _)) = self.hit_cube(child_origin, half_width as u16) {
+                if t_exit >= 0.0 && t_enter <= max_dist {
+                    hit_children.push((t_enter, i));
+                }
+            }
+        }
+        hit_children.sort_by(|a, b| a.0.total_cmp(&b.0));
+        hit_children
+    }
+
+    /// the voxel inside of the cube at `origin` with side `width` where the ray is at `t`,
+    /// `enter_axis` is used to resolve positions on the entry face.
+    pub(crate) fn voxel_at(
+        &self,
+        t: f32,
+        enter_axis: Option<usize>,
+        origin: PosU8,
+        width: u16,
+    ) -> PosU8 {
+        let cube_min: [f32; 3] = origin.into();
+        let mut voxel = [0u8; 3];
+        for axis in 0..3 {
+            let last = cube_min[axis] + width as f32 - 1.0;
+            let c = if enter_axis == Some(axis) {
+                if self.dir[axis] > 0.0 {
+                    cube_min[axis]
+                } else {
+                    last
+                }
+            } else {
+                let p = self.origin[axis] + self.dir[axis] * t;
+                p.floor().clamp(cube_min[axis], last)
+            };
+            voxel[axis] = c as u8;
+        }
+        voxel.into()
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// casts a ray from `origin` in direction `dir` and returns the first set voxel it hits
+    /// within `max_dist`, together with the normal of the face it entered through.
+    ///
+    /// `dir` does not need to be normalized, `max_dist` is measured in voxels.
+    /// If `origin` lies inside of a set voxel, that voxel is returned with a normal of `[0.0; 3]`.
+    ///
+    /// Empty octants of the tree are skipped as a whole, instead of stepping through every voxel.
+    pub fn raycast(
+        &self,
+        origin: [f32; 3],
+        dir: [f32; 3],
+        max_dist: f32,
+    ) -> Option<(PosU8, V, [f32; 3])> {
+        let ray = Ray::new(origin, dir)?;
+        let (pos, val, t, enter_axis) = match self.nodes[0] {
+            Node::Full(ref val) => self.hit_value(&ray, max_dist, PosU8::ZERO, Self::SIDE, val),
+            Node::Mixed(ptrs, _) => {
+                self.raycast_children(&ray, max_dist, &ptrs, PosU8::ZERO, HALF_WIDTH)
+            }
+        }?;
+        let mut normal = [0.0; 3];
+        if t > 0.0 {
+            normal[enter_axis] = -ray.dir[enter_axis].signum();
+        }
+        Some((pos, val.clone(), normal))
+    }
+
+    /// casts a ray from `origin` in direction `dir` and lazily yields every set voxel it passes
+    /// through within `max_dist`, front to back, together with the distance `t` at which the ray
+    /// enters the voxel (`0.0` for a voxel containing `origin`).
+    ///
+    /// Like [`Octree::raycast`], empty octants are skipped as a whole.
+    pub fn raycast_all(
+        &self,
+        origin: [f32; 3],
+        dir: [f32; 3],
+        max_dist: f32,
+    ) -> impl Iterator<Item = (PosU8, V, f32)> + '_ {
+        let mut stack = vec![];
+        let mut walk = None;
+        if let Some(ray) = Ray::new(origin, dir) {
+            stack.push(Pending::Node {
+                node_ptr: 0,
+                origin: PosU8::ZERO,
+                half_width: HALF_WIDTH,
+            });
+            walk = Some(Walk {
+                ray,
+                voxel: [0; 3],
+                t: 0.0,
+                region_min: [0; 3],
+                region_max: [0; 3],
+                val: None,
+                done: true,
+            });
+        }
+        RaycastAllIter {
+            octree: self,
+            max_dist,
+            stack,
+            walk,
+        }
+    }
+
+    /// hit with the uniform cube at `origin` with side `width`: `(voxel, value, t, enter_axis)`.
+    fn hit_value<'a>(
+        &self,
+        ray: &Ray,
+        max_dist: f32,
+        origin: PosU8,
+        width: u16,
+        val: &'a V,
+    ) -> Option<(PosU8, &'a V, f32, usize)> {
+        let (t_enter, t_exit, enter_axis) = ray.hit_cube(origin, width)?;
+        if t_exit < 0.0 || t_enter > max_dist {
+            return None;
+        }
+        let t = t_enter.max(0.0);
+        let enter_axis_if_outside = (t_enter > 0.0).then_some(enter_axis);
+        let voxel = ray.voxel_at(t, enter_axis_if_outside, origin, width);
+        Some((voxel, val, t, enter_axis))
+    }
+
+    /// visits the children `ptrs` of a node at `origin` with `half_width` front to back.
+    fn raycast_children(
+        &self,
+        ray: &Ray,
+        max_dist: f32,
+        ptrs: &[Ptr; 8],
+        origin: PosU8,
+        half_width: u8,
+    ) -> Option<(PosU8, &V, f32, usize)> {
+        for (_, i) in ray.hit_children(max_dist, ptrs, origin, half_width) {
+            let ptr = ptrs[i];
+            let child_origin = origin + oct_offset(i, half_width);
+            let hit = if half_width == 1 {
+                self.hit_value(ray, max_dist, child_origin, 1, &self.leafs[ptr])
+            } else {
+                match self.nodes[ptr] {
+                    Node::Full(ref val) => {
+                        self.hit_value(ray, max_dist, child_origin, half_width as u16, val)
+                    }
+                    Node::Mixed(child_ptrs, _) => self.raycast_children(
+                        ray,
+                        max_dist,
+                        &child_ptrs,
+                        child_origin,
+                        half_width / 2,
+                    ),
+                }
+            };
+            if hit.is_some() {
+                return hit;
+            }
+        }
+        None
+    }
+}
+
+/// a part of the tree the ray passes through that still has to be visited.
+enum Pending<'a, V> {
+    Node {
+        node_ptr: Ptr,
+        origin: PosU8,
+        half_width: u8,
+    },
+    Uniform {
+        val: &'a V,
+        origin: PosU8,
+        width: u16,
+    },
+}
+
+/// voxel by voxel traversal of the ray through a uniform region.
+struct Walk<'a, V> {
+    ray: Ray,
+    voxel: [i32; 3],
+    /// distance at which the ray enters `voxel`.
+    t: f32,
+    region_min: [i32; 3],
+    /// exclusive.
+    region_max: [i32; 3],
+    /// value of the region, `None` until the first region is entered.
+    val: Option<&'a V>,
+    done: bool,
+}
+
+impl<V> Walk<'_, V> {
+    /// moves on to the next voxel the ray enters, ends the walk if it leaves the region.
+    fn step(&mut self, max_dist: f32) {
+        let mut next_t = f32::INFINITY;
+        let mut next_axis = 0;
+        for axis in 0..3 {
+            let d = self.ray.dir[axis];
+            if d == 0.0 {
+                continue;
+            }
+            let boundary = self.voxel[axis] + (d > 0.0) as i32;
+            let t = (boundary as f32 - self.ray.origin[axis]) / d;
+            if t < next_t {
+                next_t = t;
+                next_axis = axis;
+            }
+        }
+        self.voxel[next_axis] += self.ray.dir[next_axis].signum() as i32;
+        self.t = next_t;
+        let in_region = (0..3)
+            .all(|a| self.voxel[a] >= self.region_min[a] && self.voxel[a] < self.region_max[a]);
+        self.done = !in_region || next_t > max_dist;
+    }
+}
+
+struct RaycastAllIter<'a, V, const HALF_WIDTH: u8> {
+    octree: &'a Octree<V, HALF_WIDTH>,
+    max_dist: f32,
+    /// front most part on top.
+    stack: Vec<Pending<'a, V>>,
+    /// `None` if the ray has no direction.
+    walk: Option<Walk<'a, V>>,
+}
+
+impl<'a, V: Clone, const HALF_WIDTH: u8> Iterator for RaycastAllIter<'a, V, HALF_WIDTH> {
+    type Item = (PosU8, V, f32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let walk = self.walk.as_mut()?;
+        loop {
+            if !walk.done {
+                let voxel = PosU8 {
+                    x: walk.voxel[0] as u8,
+                    y: walk.voxel[1] as u8,
+                    z: walk.voxel[2] as u8,
+                };
+                let val = walk
+                    .val
+                    .expect("a region was entered before the walk started");
+                let item = (voxel, val.clone(), walk.t);
+                walk.step(self.max_dist);
+                return Some(item);
+            }
+
+            match self.stack.pop()? {
+                Pending::Node {
+                    node_ptr,
+                    origin,
+                    half_width,
+                } => match self.octree.nodes[node_ptr] {
+                    Node::Full(ref val) => self.stack.push(Pending::Uniform {
+                        val,
+                        origin,
+                        width: half_width as u16 * 2,
+                    }),
+                    Node::Mixed(ptrs, _) => {
+                        let hit_children =
+                            walk.ray
+                                .hit_children(self.max_dist, &ptrs, origin, half_width);
+                        for (_, i) in hit_children.into_iter().rev() {
+                            let child_origin = origin + oct_offset(i, half_width);
+                            self.stack.push(if half_width == 1 {
+                                Pending::Uniform {
+                                    val: &self.octree.leafs[ptrs[i]],
+                                    origin: child_origin,
+                                    width: 1,
+                                }
+                            } else {
+                                Pending::Node {
+                                    node_ptr: ptrs[i],
+                                    origin: child_origin,
+                                    half_width: half_width / 2,
+                                }
+                            });
+                        }
+                    }
+                },
+                Pending::Uniform { val, origin, width } => {
+                    let Some((t_enter, t_exit, enter_axis)) = walk.ray.hit_cube(origin, width)
+                    else {
+                        continue;
+                    };
+                    if t_exit < 0.0 || t_enter > self.max_dist {
+                        continue;
+                    }
+                    let t = t_enter.max(0.0);
+                    let enter_axis = (t_enter > 0.0).then_some(enter_axis);
+                    let voxel = walk.ray.voxel_at(t, enter_axis, origin, width);
+                    let region_min = [origin.x as i32, origin.y as i32, origin.z as i32];
+                    walk.voxel = [voxel.x as i32, voxel.y as i32, voxel.z as i32];
+                    walk.t = t;
+                    walk.region_min = region_min;
+                    walk.region_max = region_min.map(|c| c + width as i32);
+                    walk.val = Some(val);
+                    walk.done = false;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn raycast_axis_aligned() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.insert(pos!(10, 5, 5), 1);
+        octree.insert(pos!(20, 5, 5), 2);
+
+        // along +x, hits the nearer voxel:
+        let hit = octree.raycast([0.5, 5.5, 5.5], [1.0, 0.0, 0.0], 100.0);
+        assert_eq!(hit, Some((pos!(10, 5, 5), 1, [-1.0, 0.0, 0.0])));
+
+        // along -x from the other side:
+        let hit = octree.raycast([31.5, 5.5, 5.5], [-3.0, 0.0, 0.0], 100.0);
+        assert_eq!(hit, Some((pos!(20, 5, 5), 2, [1.0, 0.0, 0.0])));
+
+        // along +y and -z:
+        let hit = octree.raycast([10.5, 0.2, 5.5], [0.0, 1.0, 0.0], 100.0);
+        assert_eq!(hit, Some((pos!(10, 5, 5), 1, [0.0, -1.0, 0.0])));
+        let hit = octree.raycast([20.5, 5.5, 30.0], [0.0, 0.0, -1.0], 100.0);
+        assert_eq!(hit, Some((pos!(20, 5, 5), 2, [0.0, 0.0, 1.0])));
+
+        // too short:
+        assert_eq!(octree.raycast([0.5, 5.5, 5.5], [1.0, 0.0, 0.0], 9.0), None);
+        // parallel, but next to the voxels:
+        assert_eq!(
+            octree.raycast([0.5, 6.5, 5.5], [1.0, 0.0, 0.0], 100.0),
+            None
+        );
+        // zero direction:
+        assert_eq!(
+            octree.raycast([0.5, 5.5, 5.5], [0.0, 0.0, 0.0], 100.0),
+            None
+        );
+    }
+
+    #[test]
+    pub fn raycast_edge_cases() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 7);
+
+        // starting inside of a solid region:
+        let hit = octree.raycast([10.5, 10.5, 10.5], [1.0, 1.0, 0.0], 100.0);
+        assert_eq!(hit, Some((pos!(10, 10, 10), 7, [0.0, 0.0, 0.0])));
+
+        // hitting a Full node resolves the exact voxel:
+        let hit = octree.raycast([12.5, 13.25, -5.0], [0.0, 0.0, 1.0], 100.0);
+        assert_eq!(hit, Some((pos!(12, 13, 8), 7, [0.0, 0.0, -1.0])));
+
+        // diagonal ray from outside of the tree:
+        let hit = octree.raycast([-1.0, -1.0, -1.0], [1.0, 1.0, 1.0], 100.0);
+        let (pos, val, normal) = hit.unwrap();
+        assert_eq!((pos, val), (pos!(8, 8, 8), 7));
+        assert_eq!(normal.iter().filter(|c| **c == -1.0).count(), 1);
+
+        // missing the tree entirely:
+        assert_eq!(
+            octree.raycast([-1.0, 40.0, 0.0], [1.0, 0.0, 0.0], 100.0),
+            None
+        );
+        // pointing away from the tree:
+        assert_eq!(
+            octree.raycast([-1.0, 10.0, 10.0], [-1.0, 0.0, 0.0], 100.0),
+            None
+        );
+        // a ray through empty space only:
+        assert_eq!(
+            octree.raycast([0.5, 0.5, 0.5], [1.0, 0.0, 0.0], 100.0),
+            None
+        );
+
+        let empty = Octree::<u32, 16>::new();
+        assert_eq!(empty.raycast([0.5, 0.5, 0.5], [1.0, 1.0, 1.0], 100.0), None);
+    }
+
+    #[test]
+    pub fn raycast_full_root() {
+        let mut octree = Octree::<u32, 4>::new();
+        octree.fill_box(PosU8::ZERO, pos!(7, 7, 7), 3);
+        let hit = octree.raycast([-2.0, 3.5, 4.5], [1.0, 0.0, 0.0], 10.0);
+        assert_eq!(hit, Some((pos!(0, 3, 4), 3, [-1.0, 0.0, 0.0])));
+    }
+
+    #[test]
+    pub fn raycast_all_line() {
+        let mut octree = Octree::<u32, 16>::new();
+        for x in [3u8, 4, 9, 20, 27] {
+            octree.insert(pos!(x, 2, 2), x as u32);
+        }
+        octree.insert(pos!(9, 3, 2), 100);
+
+        let hits: Vec<_> = octree
+            .raycast_all([0.5, 2.5, 2.5], [1.0, 0.0, 0.0], 100.0)
+            .collect();
+        let values: Vec<u32> = hits.iter().map(|(_, v, _)| *v).collect();
+        assert_eq!(values, vec![3, 4, 9, 20, 27]);
+        for (pos, v, t) in hits.iter() {
+            assert_eq!(*pos, pos!(*v as u8, 2, 2));
+            assert_eq!(*t, pos.x as f32 - 0.5);
+        }
+        assert!(hits.windows(2).all(|w| w[0].2 < w[1].2));
+
+        // limited by max_dist, backwards:
+        let hits: Vec<_> = octree
+            .raycast_all([25.5, 2.5, 2.5], [-1.0, 0.0, 0.0], 17.0)
+            .map(|(_, v, _)| v)
+            .collect();
+        assert_eq!(hits, vec![20, 9]);
+    }
+
+    #[test]
+    pub fn raycast_all_through_full_region() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 1);
+        octree.insert(pos!(16, 9, 9), 2);
+
+        let hits: Vec<_> = octree
+            .raycast_all([10.5, 9.5, 9.5], [1.0, 0.0, 0.0], 100.0)
+            .collect();
+        let positions: Vec<u8> = hits.iter().map(|(pos, _, _)| pos.x).collect();
+        assert_eq!(positions, vec![10, 11, 12, 13, 14, 15, 16]);
+        assert_eq!(hits[0].2, 0.0);
+        assert_eq!(hits.last().unwrap().1, 2);
+
+        // a diagonal ray visits face-connected voxels in order:
+        let hits: Vec<_> = octree
+            .raycast_all([7.2, 7.6, 8.5], [1.0, 1.0, 0.0], 100.0)
+            .collect();
+        assert!(hits
+            .windows(2)
+            .all(|w| w[0].2 <= w[1].2 && w[0].0.manhattan(w[1].0) == 1));
+        assert_eq!(
+            hits[0].0,
+            octree
+                .raycast([7.2, 7.6, 8.5], [1.0, 1.0, 0.0], 100.0)
+                .unwrap()
+                .0
+        );
+
+        assert_eq!(octree.raycast_all([0.0; 3], [0.0; 3], 100.0).count(), 0);
+    }
+}
@@ -0,0 +1,592 @@
+use std::collections::VecDeque;
+
+use crate::{oct_offset, Node, Octree, PosU8, EMPTY_PTR};
+
+/// How much of a cube is covered by a region that is written to the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Coverage {
+    Outside,
+    Inside,
+    Partial,
+}
+
+/// coverage of the cube at `origin` with side `width` by the box `min..=max`.
+pub(crate) fn box_coverage(min: PosU8, max: PosU8, origin: PosU8, width: u16) -> Coverage {
+    let cube_min = [origin.x as u16, origin.y as u16, origin.z as u16];
+    let cube_max = cube_min.map(|c| c + width - 1);
+    let min = [min.x as u16, min.y as u16, min.z as u16];
+    let max = [max.x as u16, max.y as u16, max.z as u16];
+    if (0..3).any(|i| cube_max[i] < min[i] || cube_min[i] > max[i]) {
+        Coverage::Outside
+    } else if (0..3).all(|i| min[i] <= cube_min[i] && cube_max[i] <= max[i]) {
+        Coverage::Inside
+    } else {
+        Coverage::Partial
+    }
+}
+
+/// coverage of the cube at `origin` with side `width` by the voxels whose distance to `center` is at most `radius`.
+pub(crate) fn sphere_coverage(center: PosU8, radius: u8, origin: PosU8, width: u16) -> Coverage {
+    let center = [center.x as i32, center.y as i32, center.z as i32];
+    let cube_min = [origin.x as i32, origin.y as i32, origin.z as i32];
+    let cube_max = cube_min.map(|c| c + width as i32 - 1);
+    let mut nearest_dist_squared = 0;
+    let mut farthest_dist_squared = 0;
+    for i in 0..3 {
+        let nearest = center[i].clamp(cube_min[i], cube_max[i]) - center[i];
+        let farthest = (cube_min[i] - center[i])
+            .abs()
+            .max((cube_max[i] - center[i]).abs());
+        nearest_dist_squared += nearest * nearest;
+        farthest_dist_squared += farthest * farthest;
+    }
+    let radius_squared = radius as i32 * radius as i32;
+    if nearest_dist_squared > radius_squared {
+        Coverage::Outside
+    } else if farthest_dist_squared <= radius_squared {
+        Coverage::Inside
+    } else {
+        Coverage::Partial
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// sets all voxels in the box `min..=max` to `val`.
+    ///
+    /// Octree cubes that are completely inside of the box become a single Full node, only the
+    /// boundary of the box is split up further.
+    ///
+    /// panics if `min` or `max` is out of bounds.
+    pub fn fill_box(&mut self, min: PosU8, max: PosU8, val: V) {
+        Self::assert_in_bounds(min);
+        Self::assert_in_bounds(max);
+        self.write_region(
+            &|origin, width| box_coverage(min, max, origin, width),
+            Some(&val),
+        );
+    }
+
+    /// removes all voxels in the box `min..=max`.
+    ///
+    /// Octree cubes that are completely inside of the box are freed as a whole.
+    ///
+    /// panics if `min` or `max` is out of bounds.
+    pub fn clear_box(&mut self, min: PosU8, max: PosU8) {
+        Self::assert_in_bounds(min);
+        Self::assert_in_bounds(max);
+        self.write_region(&|origin, width| box_coverage(min, max, origin, width), None);
+    }
+
+    /// splits all Full nodes overlapping the box `min..=max` down to the leaf level, such that every set voxel
+    /// of the box has a leaf of its own, e.g. to mutate the voxels one by one via [`Octree::get_mut`]
+    /// without splitting the tree again for every voxel.
+    ///
+    /// This leaves the tree unmerged on purpose, [`Octree::remerge`] merges it again.
+    ///
+    /// panics if `min` or `max` is out of bounds.
+    pub fn split_region(&mut self, min: PosU8, max: PosU8) {
+        Self::assert_in_bounds(min);
+        Self::assert_in_bounds(max);
+        if !matches!(
+            box_coverage(min, max, PosU8::ZERO, Self::SIDE),
+            Coverage::Outside
+        ) {
+            self.split_region_in_node(0, PosU8::ZERO, HALF_WIDTH, min, max);
+        }
+    }
+
+    fn split_region_in_node(
+        &mut self,
+        node_ptr: usize,
+        origin: PosU8,
+        half_width: u8,
+        min: PosU8,
+        max: PosU8,
+    ) {
+        let ptrs = match self.nodes[node_ptr] {
+            Node::Full(_) => {
+                let full_val = self.take_full_value(node_ptr);
+                let ptrs = std::array::from_fn(|_| {
+                    self.insert_uniform_child(full_val.clone(), half_width / 2)
+                });
+                self.nodes[node_ptr] = Node::from_ptrs(ptrs);
+                ptrs
+            }
+            Node::Mixed(ptrs, _) => ptrs,
+        };
+        if half_width == 1 {
+            // the children are leafs already.
+            return;
+        }
+        for (i, ptr) in ptrs.into_iter().enumerate() {
+            let child_origin = origin + oct_offset(i, half_width);
+            if ptr != EMPTY_PTR
+                && !matches!(
+                    box_coverage(min, max, child_origin, half_width as u16),
+                    Coverage::Outside
+                )
+            {
+                self.split_region_in_node(ptr, child_origin, half_width / 2, min, max);
+            }
+        }
+    }
+
+    /// sets all voxels whose distance to `center` is at most `radius` to `val`.
+    /// Parts of the sphere outside of the tree are ignored.
+    ///
+    /// Octree cubes completely inside of the sphere become a single Full node,
+    /// only the surface of the sphere is split up further.
+    ///
+    /// panics if `center` is out of bounds.
+    pub fn fill_sphere(&mut self, center: PosU8, radius: u8, val: V) {
+        Self::assert_in_bounds(center);
+        self.write_region(
+            &|origin, width| sphere_coverage(center, radius, origin, width),
+            Some(&val),
+        );
+    }
+
+    /// replaces the value of all voxels that are 6-connected to `seed` through voxels with the same value as `seed`
+    /// by `new`, and returns the number of changed voxels. Empty voxels are never traversed.
+    ///
+    /// Nothing happens if `seed` is empty or already has the value `new`.
+    ///
+    /// panics if `seed` is out of bounds.
+    pub fn flood_fill(&mut self, seed: PosU8, new: V) -> usize {
+        let Some(old) = self.get(seed) else {
+            return 0;
+        };
+        if old == new {
+            return 0;
+        }
+        let mut changed = 0;
+        let mut queue = VecDeque::from([seed]);
+        self.insert(seed, new.clone());
+        // every queued voxel is already set to `new`, so it is not visited a second time.
+        while let Some(pos) = queue.pop_front() {
+            changed += 1;
+            for neighbor in pos.neighbors_6_bounded(HALF_WIDTH) {
+                if self.get_ref(neighbor) == Some(&old) {
+                    self.insert(neighbor, new.clone());
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        changed
+    }
+
+    /// sets every voxel on the 3D Bresenham line from `a` to `b` (both included) to `val`.
+    /// Voxels of the line that lie outside of the tree are skipped.
+    pub fn draw_line(&mut self, a: PosU8, b: PosU8, val: V) {
+        let from = [a.x, a.y, a.z];
+        let to = [b.x, b.y, b.z];
+        let units = [PosU8::X, PosU8::Y, PosU8::Z];
+        let delta: [i32; 3] = std::array::from_fn(|i| from[i].abs_diff(to[i]) as i32);
+        // the axis along which the line advances by one voxel in every step:
+        let main = (0..3).max_by_key(|i| (delta[*i], 2 - i)).unwrap_or(0);
+        let mut errors: [i32; 3] = std::array::from_fn(|i| 2 * delta[i] - delta[main]);
+
+        let mut pos = a;
+        let step = |pos: PosU8, axis: usize| {
+            // never saturates, the line stays between a and b.
+            if to[axis] > from[axis] {
+                pos.saturating_add(units[axis])
+            } else {
+                pos.saturating_sub(units[axis])
+            }
+        };
+        if Self::in_bounds(pos) {
+            self.insert(pos, val.clone());
+        }
+        for _ in 0..delta[main] {
+            pos = step(pos, main);
+            for axis in (0..3).filter(|i| *i != main) {
+                if errors[axis] >= 0 {
+                    pos = step(pos, axis);
+                    errors[axis] -= 2 * delta[main];
+                }
+                errors[axis] += 2 * delta[axis];
+            }
+            if Self::in_bounds(pos) {
+                self.insert(pos, val.clone());
+            }
+        }
+    }
+
+    /// writes `val` to every voxel of the region described by `coverage`, `None` clears the voxels.
+    ///
+    /// `coverage` is called with the origin and width of octree cubes and must return
+    /// `Inside` or `Outside` for single voxels (width 1).
+    pub(crate) fn write_region(
+        &mut self,
+        coverage: &impl Fn(PosU8, u16) -> Coverage,
+        val: Option<&V>,
+    ) {
+        match coverage(PosU8::ZERO, Self::SIDE) {
+            Coverage::Outside => {}
+            Coverage::Inside => {
+                // replace the whole tree:
+                self.mark_dirty_cube(PosU8::ZERO, Self::SIDE);
+                if let Node::Mixed(ptrs, _) = self.nodes[0] {
+                    self.delete_mixed_child_nodes(&ptrs, HALF_WIDTH);
+                }
+                self.nodes[0] = match val {
+                    Some(val) => Node::Full(val.clone()),
+                    None => Node::empty(),
+                };
+            }
+            Coverage::Partial => {
+                self.write_region_in_node(0, PosU8::ZERO, HALF_WIDTH, coverage, val)
+            }
+        }
+    }
+
+    /// writes the region into the node at `node_ptr`, which is partially covered by it.
+    fn write_region_in_node(
+        &mut self,
+        node_ptr: usize,
+        origin: PosU8,
+        half_width: u8,
+        coverage: &impl Fn(PosU8, u16) -> Coverage,
+        val: Option<&V>,
+    ) {
+        let mut ptrs = match self.nodes[node_ptr] {
+            Node::Full(ref full_val) => {
+                if Some(full_val) == val {
+                    // already has the value everywhere.
+                    return;
+                }
+                // split into 8 children with the value of the Full node:
+                let full_val = self.take_full_value(node_ptr);
+                std::array::from_fn(|_| self.insert_uniform_child(full_val.clone(), half_width / 2))
+            }
+            Node::Mixed(ptrs, _) => ptrs,
+        };
+
+        for (i, ptr) in ptrs.iter_mut().enumerate() {
+            let child_origin = origin + oct_offset(i, half_width);
+            match coverage(child_origin, half_width as u16) {
+                Coverage::Outside => {}
+                Coverage::Inside => {
+                    if *ptr != EMPTY_PTR || val.is_some() {
+                        self.mark_dirty_cube(child_origin, half_width as u16);
+                    }
+                    if *ptr != EMPTY_PTR {
+                        self.delete_child(*ptr, half_width);
+                    }
+                    *ptr = match val {
+                        None => EMPTY_PTR,
+                        Some(val) if half_width == 1 => self.leafs.insert(val.clone()),
+                        Some(val) => self.nodes.insert(Node::Full(val.clone())),
+                    };
+                }
+                Coverage::Partial => {
+                    debug_assert!(half_width > 1, "single voxels are never partially covered");
+                    if *ptr == EMPTY_PTR {
+                        if val.is_none() {
+                            continue;
+                        }
+                        *ptr = self.nodes.insert(Node::empty());
+                    }
+                    self.write_region_in_node(*ptr, child_origin, half_width / 2, coverage, val);
+                    if self.nodes[*ptr].is_empty() {
+                        self.nodes.remove(*ptr);
+                        *ptr = EMPTY_PTR;
+                    }
+                }
+            }
+        }
+
+        // the written region can make this node uniform:
+        if let Some(uniform_val) = self.uniform_child_value(&ptrs, half_width).cloned() {
+            self.delete_mixed_child_nodes(&ptrs, half_width);
+            self.nodes[node_ptr] = Node::Full(uniform_val);
+        } else {
+            self.nodes[node_ptr] = Node::from_ptrs(ptrs);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use crate::{pos, Node, Octree, PosU8};
+
+    fn fill_box_naive<const HALF_WIDTH: u8>(
+        octree: &mut Octree<u32, HALF_WIDTH>,
+        min: PosU8,
+        max: PosU8,
+        val: u32,
+    ) {
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    octree.insert(pos!(x, y, z), val);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn fill_whole_tree() {
+        let mut octree = Octree::<u32, 128>::new();
+        octree.insert(pos!(3, 4, 5), 1);
+        octree.fill_box(PosU8::ZERO, pos!(255, 255, 255), 7);
+        assert!(matches!(octree.nodes[0], Node::Full(_)));
+        assert_eq!(octree.node_count(), 1);
+        assert_eq!(octree.leaf_count(), 0);
+        assert_eq!(octree.get(pos!(3, 4, 5)), Some(7));
+    }
+
+    #[test]
+    pub fn fill_box_matches_naive_insert() {
+        let boxes = [
+            // aligned:
+            (pos!(0, 0, 0), pos!(15, 15, 15)),
+            (pos!(8, 16, 24), pos!(15, 23, 31)),
+            (pos!(4, 4, 4), pos!(5, 5, 5)),
+            // unaligned:
+            (pos!(3, 1, 7), pos!(20, 9, 30)),
+            (pos!(0, 0, 0), pos!(31, 31, 30)),
+            (pos!(5, 5, 5), pos!(5, 5, 5)),
+            // empty:
+            (pos!(5, 5, 5), pos!(4, 5, 5)),
+        ];
+        for (min, max) in boxes {
+            let mut octree = Octree::<u32, 16>::new();
+            let mut naive = Octree::<u32, 16>::new();
+            octree.fill_box(min, max, 3);
+            fill_box_naive(&mut naive, min, max, 3);
+            assert_eq!(octree.encode(), naive.encode(), "box {min:?} {max:?}");
+            assert_eq!(octree.node_count(), naive.node_count());
+            assert_eq!(octree.leaf_count(), naive.leaf_count());
+        }
+    }
+
+    #[test]
+    pub fn fill_overlapping_boxes() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 16>::new();
+        let mut naive = Octree::<u32, 16>::new();
+        for _ in 0..20 {
+            let a = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            let b = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            let min = pos!(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+            let max = pos!(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+            let val = rng.gen_range(0..3);
+            octree.fill_box(min, max, val);
+            fill_box_naive(&mut naive, min, max, val);
+            assert_eq!(octree.encode(), naive.encode());
+        }
+    }
+
+    #[test]
+    pub fn clear_whole_tree() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.fill_box(pos!(3, 1, 7), pos!(20, 9, 30), 1);
+        octree.insert(pos!(31, 31, 31), 2);
+        octree.clear_box(PosU8::ZERO, pos!(31, 31, 31));
+        assert_eq!(octree.encode(), Octree::<u32, 16>::new().encode());
+        assert_eq!(octree.node_count(), 1);
+        assert_eq!(octree.leaf_count(), 0);
+
+        // clearing a Full root:
+        octree.fill_box(PosU8::ZERO, pos!(31, 31, 31), 1);
+        octree.clear_box(PosU8::ZERO, pos!(31, 31, 31));
+        assert!(octree.is_empty());
+        assert_eq!(octree.leaf_count(), 0);
+    }
+
+    #[test]
+    pub fn clear_box_partial() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.fill_box(PosU8::ZERO, pos!(31, 31, 31), 1);
+        octree.clear_box(pos!(3, 1, 7), pos!(20, 9, 30));
+        assert_eq!(octree.len(), 32 * 32 * 32 - 18 * 9 * 24);
+        assert_eq!(octree.get(pos!(3, 1, 7)), None);
+        assert_eq!(octree.get(pos!(20, 9, 30)), None);
+        assert_eq!(octree.get(pos!(2, 1, 7)), Some(1));
+        assert_eq!(octree.get(pos!(20, 10, 30)), Some(1));
+        assert_eq!(octree.get(pos!(20, 9, 31)), Some(1));
+
+        // same result as removing voxel by voxel:
+        let mut naive = Octree::<u32, 16>::new();
+        naive.fill_box(PosU8::ZERO, pos!(31, 31, 31), 1);
+        for x in 3..=20 {
+            for y in 1..=9 {
+                for z in 7..=30 {
+                    naive.remove(pos!(x, y, z));
+                }
+            }
+        }
+        assert_eq!(octree.encode(), naive.encode());
+    }
+
+    #[test]
+    pub fn clear_box_collapses_empty_nodes() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.insert(pos!(1, 1, 1), 1);
+        octree.insert(pos!(2, 3, 1), 1);
+        octree.insert(pos!(30, 30, 30), 2);
+        octree.clear_box(pos!(0, 0, 0), pos!(3, 3, 3));
+        assert_eq!(octree.len(), 1);
+        let mut expected = Octree::<u32, 16>::new();
+        expected.insert(pos!(30, 30, 30), 2);
+        assert_eq!(octree.node_count(), expected.node_count());
+        assert_eq!(octree.encode(), expected.encode());
+    }
+
+    #[test]
+    pub fn split_region() {
+        let mut octree = Octree::<u32, 8>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(15, 15, 7), 1);
+        octree.insert(pos!(3, 3, 12), 2);
+        let before = octree.clone();
+
+        octree.split_region(pos!(0, 0, 0), pos!(7, 7, 7));
+        // the 8x8x8 cube is split into leafs, the other Full nodes stay:
+        assert_eq!(octree.leaf_count(), 512 + 1);
+        for (pos, val) in before.iter() {
+            assert_eq!(octree.get(pos), Some(val));
+        }
+        assert_eq!(octree.len(), before.len());
+
+        // get_mut does not need to split anymore:
+        let node_count = octree.node_count();
+        *octree.get_mut(pos!(1, 2, 3)).unwrap() = 5;
+        assert_eq!(octree.node_count(), node_count);
+        assert_eq!(octree.get(pos!(1, 2, 3)), Some(5));
+
+        // an unaligned box only splits the Full nodes it overlaps:
+        let mut octree = before.clone();
+        octree.split_region(pos!(7, 7, 7), pos!(8, 8, 8));
+        assert_eq!(octree.get(pos!(8, 8, 7)), Some(1));
+        assert_eq!(octree.get(pos!(8, 8, 8)), None);
+        for pos in [pos!(7, 7, 7), pos!(8, 7, 7), pos!(8, 8, 7)] {
+            let node = octree
+                .get_path(pos)
+                .into_iter()
+                .try_fold(octree.root(), |node, idx| node.child(idx));
+            assert_eq!(node.map(|node| node.width()), Some(1));
+        }
+        octree.remerge();
+        assert_eq!(octree.encode(), before.encode());
+        assert_eq!(octree.node_count(), before.node_count());
+    }
+
+    #[test]
+    pub fn fill_sphere() {
+        for (center, radius) in [
+            (pos!(16, 16, 16), 10),
+            (pos!(3, 30, 16), 7),
+            (pos!(0, 0, 0), 40),
+            (pos!(5, 6, 7), 0),
+        ] {
+            let mut octree = Octree::<u32, 16>::new();
+            octree.fill_sphere(center, radius, 1);
+
+            // brute force reference:
+            let mut naive = Octree::<u32, 16>::new();
+            let mut count = 0;
+            for x in 0..32u8 {
+                for y in 0..32u8 {
+                    for z in 0..32u8 {
+                        let d = [x, y, z]
+                            .iter()
+                            .zip([center.x, center.y, center.z])
+                            .map(|(a, b)| (*a as i32 - b as i32).pow(2))
+                            .sum::<i32>();
+                        if d <= radius as i32 * radius as i32 {
+                            naive.insert(pos!(x, y, z), 1);
+                            count += 1;
+                        }
+                    }
+                }
+            }
+            assert_eq!(octree.len(), count);
+            assert_eq!(octree.encode(), naive.encode());
+        }
+    }
+
+    #[test]
+    pub fn flood_fill_l_shape() {
+        let mut octree = Octree::<u32, 8>::new();
+        // an L made of two bars:
+        octree.fill_box(pos!(0, 0, 0), pos!(9, 0, 0), 1);
+        octree.fill_box(pos!(0, 1, 0), pos!(0, 6, 0), 1);
+        // same value, but not connected:
+        octree.insert(pos!(2, 2, 0), 1);
+        octree.fill_box(pos!(12, 0, 0), pos!(15, 0, 0), 1);
+        // connected, but a different value:
+        octree.insert(pos!(10, 0, 0), 2);
+
+        assert_eq!(octree.flood_fill(pos!(0, 3, 0), 5), 16);
+        for x in 0..10 {
+            assert_eq!(octree.get(pos!(x, 0, 0)), Some(5));
+        }
+        for y in 1..7 {
+            assert_eq!(octree.get(pos!(0, y, 0)), Some(5));
+        }
+        assert_eq!(octree.get(pos!(2, 2, 0)), Some(1));
+        assert_eq!(octree.get(pos!(10, 0, 0)), Some(2));
+        assert_eq!(octree.get(pos!(12, 0, 0)), Some(1));
+        assert_eq!(octree.len(), 16 + 1 + 4 + 1);
+
+        // empty seed and unchanged value:
+        assert_eq!(octree.flood_fill(pos!(5, 5, 5), 3), 0);
+        assert_eq!(octree.flood_fill(pos!(0, 0, 0), 5), 0);
+    }
+
+    #[test]
+    pub fn flood_fill_full_node() {
+        let mut octree = Octree::<u32, 8>::new();
+        octree.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 1);
+        assert_eq!(octree.flood_fill(pos!(15, 15, 15), 2), 512);
+        assert_eq!(octree.leaf_count(), 0);
+    }
+
+    #[test]
+    pub fn draw_line() {
+        let mut octree = Octree::<u32, 8>::new();
+        octree.draw_line(pos!(0, 0, 0), pos!(10, 10, 10), 1);
+        assert_eq!(octree.len(), 11);
+        assert_eq!(octree.get(pos!(0, 0, 0)), Some(1));
+        assert_eq!(octree.get(pos!(5, 5, 5)), Some(1));
+        assert_eq!(octree.get(pos!(10, 10, 10)), Some(1));
+
+        let mut octree = Octree::<u32, 8>::new();
+        octree.draw_line(pos!(2, 3, 12), pos!(2, 3, 4), 1);
+        assert_eq!(octree.len(), 9);
+        for z in 4..=12 {
+            assert_eq!(octree.get(pos!(2, 3, z)), Some(1));
+        }
+
+        // every step moves along the main axis and each voxel is connected to the previous one:
+        let mut octree = Octree::<u32, 8>::new();
+        octree.draw_line(pos!(15, 1, 0), pos!(0, 7, 12), 1);
+        assert_eq!(octree.len(), 16);
+        assert_eq!(octree.get(pos!(15, 1, 0)), Some(1));
+        assert_eq!(octree.get(pos!(0, 7, 12)), Some(1));
+        for x in 0..16 {
+            let in_layer = octree.iter().filter(|(pos, _)| pos.x == x).count();
+            assert_eq!(in_layer, 1);
+        }
+
+        // clamped to the bounds of the tree:
+        let mut octree = Octree::<u32, 4>::new();
+        octree.draw_line(pos!(0, 2, 2), pos!(200, 2, 2), 1);
+        assert_eq!(octree.len(), 8);
+    }
+}
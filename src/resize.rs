@@ -0,0 +1,224 @@
+use crate::{
+    region::{box_coverage, Coverage},
+    Node, Octree, PosU8, EMPTY_PTR,
+};
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// copies all voxels of this tree into `larger`, shifted by `offset`. Voxels already in `larger` are overwritten
+    /// where this tree has a voxel set and kept where it does not.
+    ///
+    /// Uniform regions are written as boxes, so a Full node is copied without visiting its voxels.
+    ///
+    /// panics if this tree shifted by `offset` does not fit into `larger`.
+    pub fn embed_into<const BIG: u8>(&self, offset: PosU8, larger: &mut Octree<V, BIG>) {
+        let fits = [offset.x, offset.y, offset.z]
+            .iter()
+            .all(|c| *c as u16 + Self::SIDE <= Octree::<V, BIG>::SIDE);
+        assert!(
+            fits,
+            "octree of side {} at offset {offset:?} does not fit into octree of side {}",
+            Self::SIDE,
+            Octree::<V, BIG>::SIDE
+        );
+        for (origin, width, val) in self.iter_regions() {
+            let min = origin + offset;
+            let max = min + PosU8::from([(width - 1) as u8; 3]);
+            larger.fill_box(min, max, val);
+        }
+    }
+
+    /// returns a copy that only contains the voxels inside of the box `min..=max`, at their original positions.
+    ///
+    /// panics if `min` or `max` is out of bounds.
+    pub fn crop(&self, min: PosU8, max: PosU8) -> Octree<V, HALF_WIDTH> {
+        Self::assert_in_bounds(min);
+        Self::assert_in_bounds(max);
+        let mut cropped = self.clone();
+        // clears everything outside of the box:
+        cropped.write_region(
+            &|origin, width| match box_coverage(min, max, origin, width) {
+                Coverage::Inside => Coverage::Outside,
+                Coverage::Outside => Coverage::Inside,
+                Coverage::Partial => Coverage::Partial,
+            },
+            None,
+        );
+        cropped
+    }
+
+    /// extracts the cube of side `2 * SMALL` starting at `min` into a smaller tree, re-based such that `min`
+    /// becomes the origin. Parts of the cube outside of this tree stay empty.
+    ///
+    /// panics if `min` is out of bounds.
+    pub fn crop_into<const SMALL: u8>(&self, min: PosU8) -> Octree<V, SMALL> {
+        Self::assert_in_bounds(min);
+        let small_side = Octree::<V, SMALL>::SIDE;
+        let min = [min.x as u16, min.y as u16, min.z as u16];
+        let max = min.map(|c| c + small_side - 1);
+        let mut cropped = Octree::new();
+        for (origin, width, val) in self.iter_regions() {
+            let region_min = [origin.x as u16, origin.y as u16, origin.z as u16];
+            let region_max = region_min.map(|c| c + width - 1);
+            if (0..3).any(|i| region_max[i] < min[i] || region_min[i] > max[i]) {
+                continue;
+            }
+            let from: [u8; 3] = std::array::from_fn(|i| (region_min[i].max(min[i]) - min[i]) as u8);
+            let to: [u8; 3] = std::array::from_fn(|i| (region_max[i].min(max[i]) - min[i]) as u8);
+            cropped.fill_box(from.into(), to.into(), val);
+        }
+        cropped
+    }
+
+    /// turns this tree into one with twice the side length, containing this tree as the octant at the origin.
+    ///
+    /// `BIG` has to be `2 * HALF_WIDTH`, this is checked at runtime, because stable Rust can not express it as a
+    /// bound yet. So the call looks like `octree.grow::<32>()` for a `Octree<V, 16>`.
+    ///
+    /// No node or value is copied, the old root simply becomes a child of the new one. For placing the old tree
+    /// somewhere else or growing by more than one level, see [`Octree::embed_into`].
+    pub fn grow<const BIG: u8>(self) -> Octree<V, BIG> {
+        assert_eq!(
+            BIG as u16,
+            HALF_WIDTH as u16 * 2,
+            "grow needs BIG == 2 * HALF_WIDTH"
+        );
+        // the old voxels keep their positions, so the dirty cubes stay dirty.
+        let Octree {
+            mut nodes,
+            leafs,
+            dirty,
+        } = self;
+        let dirty = dirty.map(|dirty| Box::new(dirty.grow::<BIG>()));
+        if nodes[0].is_empty() {
+            let mut grown = Octree::new();
+            grown.dirty = dirty;
+            return grown;
+        }
+        let old_root = std::mem::replace(&mut nodes[0], Node::empty());
+        let old_root_ptr = nodes.insert(old_root);
+        let mut ptrs = [EMPTY_PTR; 8];
+        ptrs[0] = old_root_ptr;
+        nodes[0] = Node::from_ptrs(ptrs);
+        Octree {
+            nodes,
+            leafs,
+            dirty,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn embed_into() {
+        let mut rng = thread_rng();
+        let mut small = Octree::<u32, 8>::new();
+        for _ in 0..300 {
+            let pos = pos!(
+                rng.gen_range(0..16),
+                rng.gen_range(0..16),
+                rng.gen_range(0..16)
+            );
+            small.insert(pos, rng.gen_range(0..3));
+        }
+        small.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 7);
+
+        let mut large = Octree::<u32, 32>::new();
+        large.insert(pos!(0, 0, 0), 100);
+        let offset = pos!(20, 33, 48);
+        small.embed_into(offset, &mut large);
+
+        assert_eq!(large.len(), small.len() + 1);
+        for (pos, val) in small.iter() {
+            assert_eq!(large.get(pos + offset), Some(val));
+        }
+        assert_eq!(large.get(pos!(0, 0, 0)), Some(100));
+        assert_eq!(large.bounding_box().unwrap().1, offset + pos!(15, 15, 15));
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn embed_into_out_of_bounds_panics() {
+        let small = Octree::<u32, 8>::new();
+        let mut large = Octree::<u32, 32>::new();
+        small.embed_into(pos!(49, 0, 0), &mut large);
+    }
+
+    #[test]
+    pub fn grow() {
+        let mut octree = Octree::<u32, 4>::new();
+        octree.insert(pos!(1, 2, 3), 1);
+        octree.fill_box(pos!(4, 4, 4), pos!(7, 7, 7), 2);
+        let grown: Octree<u32, 8> = octree.clone().grow();
+
+        let mut expected = Octree::<u32, 8>::new();
+        octree.embed_into(PosU8::ZERO, &mut expected);
+        assert_eq!(grown, expected);
+        assert_eq!(grown.encode(), expected.encode());
+        assert_eq!(grown.get(pos!(7, 7, 7)), Some(2));
+        assert_eq!(grown.get(pos!(8, 7, 7)), None);
+
+        let mut full = Octree::<u32, 4>::new();
+        full.fill_box(PosU8::ZERO, pos!(7, 7, 7), 3);
+        let grown = full.grow::<8>();
+        assert_eq!(grown.len(), 512);
+        assert_eq!(grown.depth(), 1);
+
+        assert!(Octree::<u32, 4>::new().grow::<8>().is_empty());
+    }
+
+    #[test]
+    pub fn crop() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 8>::new();
+        for _ in 0..400 {
+            let pos = pos!(
+                rng.gen_range(0..16),
+                rng.gen_range(0..16),
+                rng.gen_range(0..16)
+            );
+            octree.insert(pos, rng.gen_range(0..3));
+        }
+        octree.fill_box(pos!(4, 4, 4), pos!(11, 11, 11), 5);
+
+        let (min, max) = (pos!(2, 5, 3), pos!(9, 13, 6));
+        let cropped = octree.crop(min, max);
+        for x in 0..16 {
+            for y in 0..16 {
+                for z in 0..16 {
+                    let pos = pos!(x, y, z);
+                    let inside = pos.min(min) == min && pos.max(max) == max;
+                    let expected = if inside { octree.get(pos) } else { None };
+                    assert_eq!(cropped.get(pos), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn crop_into_smaller_tree() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.fill_box(pos!(4, 4, 4), pos!(11, 11, 11), 5);
+        octree.insert(pos!(12, 12, 12), 6);
+        octree.insert(pos!(30, 30, 30), 7);
+
+        let cropped: Octree<u32, 4> = octree.crop_into(pos!(8, 8, 8));
+        assert_eq!(cropped.len(), 4 * 4 * 4 + 1);
+        assert_eq!(cropped.get(pos!(0, 0, 0)), Some(5));
+        assert_eq!(cropped.get(pos!(3, 3, 3)), Some(5));
+        assert_eq!(cropped.get(pos!(4, 4, 4)), Some(6));
+        assert_eq!(cropped.get(pos!(7, 7, 7)), None);
+
+        // a cube reaching over the border of the tree:
+        let cropped: Octree<u32, 4> = octree.crop_into(pos!(28, 28, 28));
+        assert_eq!(cropped.len(), 1);
+        assert_eq!(cropped.get(pos!(2, 2, 2)), Some(7));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{DenseError, Node, Octree, Voxel, EMPTY_PTR};
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Voxel,
+{
+    /// run length encoding of all voxels in Morton order (see [`crate::PosU8::to_morton`]),
+    /// empty voxels are encoded as runs of [`Voxel::EMPTY`]. Neighboring runs always have different values.
+    ///
+    /// Full nodes and empty children are emitted as a single run, so this never visits individual voxels of uniform cubes.
+    ///
+    /// Voxels holding [`Voxel::EMPTY`] are indistinguishable from empty ones, so [`Octree::from_rle`] drops them.
+    /// An [`crate::OctreeSentinel`] never stores that value and round-trips losslessly.
+    pub fn to_rle(&self) -> Vec<(V, u32)> {
+        let mut runs: Vec<(V, u32)> = vec![];
+        self.rle_node(&mut runs, 0, HALF_WIDTH);
+        runs
+    }
+
+    fn rle_node(&self, runs: &mut Vec<(V, u32)>, node_ptr: usize, half_width: u8) {
+        match self.nodes[node_ptr] {
+            Node::Full(ref val) => {
+                let len = (half_width as u32 * 2).pow(3);
+                push_run(runs, val, len);
+            }
+            Node::Mixed(ptrs, _) => {
+                let child_len = (half_width as u32).pow(3);
+                for ptr in ptrs {
+                    if ptr == EMPTY_PTR {
+                        push_run(runs, &V::EMPTY, child_len);
+                    } else if half_width == 1 {
+                        // points to leaf
+                        push_run(runs, &self.leafs[ptr], 1);
+                    } else {
+                        self.rle_node(runs, ptr, half_width / 2);
+                    }
+                }
+            }
+        }
+    }
+
+    /// inverse of [`Octree::to_rle`], runs of [`Voxel::EMPTY`] are left empty.
+    /// The runs have to cover exactly `(2 * HALF_WIDTH)³` voxels, runs of length 0 are allowed.
+    ///
+    /// Voxels that held [`Voxel::EMPTY`] when encoded are indistinguishable from empty ones and stay empty.
+    pub fn from_rle(runs: &[(V, u32)]) -> Result<Self, DenseError> {
+        let expected = (Self::SIDE as usize).pow(3);
+        let found: usize = runs.iter().map(|(_, len)| *len as usize).sum();
+        if found != expected {
+            return Err(DenseError::WrongLength { expected, found });
+        }
+        // from_fn visits the positions in Morton order.
+        let mut runs = runs.iter().filter(|(_, len)| *len > 0);
+        let mut current: Option<(&V, u32)> = None;
+        Ok(Self::from_fn(|_| {
+            let (val, remaining) = match current {
+                Some((val, remaining)) if remaining > 0 => (val, remaining),
+                _ => {
+                    let (val, len) = runs.next().expect("run lengths were checked");
+                    (val, *len)
+                }
+            };
+            current = Some((val, remaining - 1));
+            (!val.is_empty()).then(|| val.clone())
+        }))
+    }
+}
+
+/// appends `len` voxels of `val`, extending the last run if it has the same value.
+fn push_run<V: PartialEq + Clone>(runs: &mut Vec<(V, u32)>, val: &V, len: u32) {
+    match runs.last_mut() {
+        Some((last, last_len)) if last == val => *last_len += len,
+        _ => runs.push((val.clone(), len)),
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use crate::{pos, DenseError, Octree, PosU8};
+
+    #[test]
+    pub fn rle_round_trip() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 8>::new();
+        for _ in 0..300 {
+            let pos = pos!(
+                rng.gen_range(0..16),
+                rng.gen_range(0..16),
+                rng.gen_range(0..16)
+            );
+            octree.insert(pos, rng.gen_range(1..4));
+        }
+        octree.fill_box(pos!(0, 8, 0), pos!(7, 15, 7), 5);
+
+        let runs = octree.to_rle();
+        assert_eq!(runs.iter().map(|(_, len)| *len).sum::<u32>(), 16 * 16 * 16);
+        assert!(runs.windows(2).all(|w| w[0].0 != w[1].0));
+        let decoded = Octree::<u32, 8>::from_rle(&runs).unwrap();
+        assert_eq!(decoded.encode(), octree.encode());
+
+        // runs follow the Morton order:
+        let mut morton = 0;
+        for (val, len) in runs {
+            for code in morton..morton + len {
+                let expected = octree.get(PosU8::from_morton(code)).unwrap_or(0);
+                assert_eq!(val, expected);
+            }
+            morton += len;
+        }
+    }
+
+    #[test]
+    pub fn rle_compresses_uniform_regions() {
+        let mut octree = Octree::<u8, 16>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(31, 15, 31), 1);
+        octree.insert(pos!(3, 20, 7), 2);
+        let runs = octree.to_rle();
+        let voxels = 32 * 32 * 32;
+        assert!(runs.len() * 100 < voxels);
+        // the two lower octants with x < 16 are a single run:
+        assert_eq!(runs[0], (1, 2 * 16 * 16 * 16));
+
+        let empty = Octree::<u8, 16>::new();
+        assert_eq!(empty.to_rle(), vec![(0, voxels as u32)]);
+        assert!(Octree::<u8, 16>::from_rle(&[(0, voxels as u32)])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    pub fn rle_drops_stored_empty_values() {
+        let mut octree = Octree::<u8, 4>::new();
+        octree.insert(pos!(1, 1, 1), 0);
+        octree.insert(pos!(2, 2, 2), 1);
+        let runs = octree.to_rle();
+        assert_eq!(runs.iter().filter(|(val, _)| *val == 0).count(), 2);
+        let decoded = Octree::<u8, 4>::from_rle(&runs).unwrap();
+        assert_eq!(decoded.get(pos!(1, 1, 1)), None);
+        assert_eq!(decoded.get(pos!(2, 2, 2)), Some(1));
+        assert_eq!(decoded.len(), 1);
+    }
+
+    #[test]
+    pub fn rle_wrong_length() {
+        assert_eq!(
+            Octree::<u8, 4>::from_rle(&[(1, 500)]),
+            Err(DenseError::WrongLength {
+                expected: 512,
+                found: 500
+            })
+        );
+    }
+}
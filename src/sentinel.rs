@@ -0,0 +1,164 @@
+use crate::{Octree, PosU8};
+
+/// Voxel values with a dedicated value that means "empty", like air in a block game.
+pub trait Voxel: Clone + PartialEq {
+    const EMPTY: Self;
+
+    fn is_empty(&self) -> bool {
+        *self == Self::EMPTY
+    }
+}
+
+macro_rules! impl_voxel {
+    ($($t:ty),*) => {
+        $(
+            impl Voxel for $t {
+                const EMPTY: Self = 0;
+            }
+        )*
+    };
+}
+
+impl_voxel!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl Voxel for bool {
+    const EMPTY: Self = false;
+}
+
+/// An [`Octree`] where writing [`Voxel::EMPTY`] clears the voxel instead of storing it, and reading an empty
+/// voxel returns [`Voxel::EMPTY`]. "Set to air" and "remove" are the same operation, so the tree never holds
+/// leafs or Full nodes with the empty value.
+#[derive(Debug, Clone)]
+pub struct OctreeSentinel<V, const HALF_WIDTH: u8> {
+    octree: Octree<V, HALF_WIDTH>,
+}
+
+impl<V, const HALF_WIDTH: u8> OctreeSentinel<V, HALF_WIDTH>
+where
+    V: Voxel,
+{
+    pub fn new() -> Self {
+        OctreeSentinel {
+            octree: Octree::new(),
+        }
+    }
+
+    /// returns [`Voxel::EMPTY`] for empty voxels.
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn get(&self, pos: PosU8) -> V {
+        self.octree.get(pos).unwrap_or(V::EMPTY)
+    }
+
+    /// sets the voxel at `pos` to `val`, inserting [`Voxel::EMPTY`] removes the voxel.
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn insert(&mut self, pos: PosU8, val: V) {
+        if val.is_empty() {
+            self.octree.remove(pos);
+        } else {
+            self.octree.insert(pos, val);
+        }
+    }
+
+    /// sets all voxels in the box `min..=max` to `val`, filling with [`Voxel::EMPTY`] clears the box.
+    ///
+    /// panics if `min` or `max` is out of bounds.
+    pub fn fill_box(&mut self, min: PosU8, max: PosU8, val: V) {
+        if val.is_empty() {
+            self.octree.clear_box(min, max);
+        } else {
+            self.octree.fill_box(min, max, val);
+        }
+    }
+
+    /// the underlying tree, which only contains non empty values.
+    pub fn octree(&self) -> &Octree<V, HALF_WIDTH> {
+        &self.octree
+    }
+
+    pub fn into_octree(self) -> Octree<V, HALF_WIDTH> {
+        self.octree
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> Default for OctreeSentinel<V, HALF_WIDTH>
+where
+    V: Voxel,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// empty values are removed while converting.
+impl<V, const HALF_WIDTH: u8> From<Octree<V, HALF_WIDTH>> for OctreeSentinel<V, HALF_WIDTH>
+where
+    V: Voxel,
+{
+    fn from(octree: Octree<V, HALF_WIDTH>) -> Self {
+        let mut sentinel = Self::new();
+        for (origin, width, val) in octree.iter_regions() {
+            let max = origin + PosU8::from([(width - 1) as u8; 3]);
+            sentinel.fill_box(origin, max, val);
+        }
+        sentinel
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use super::{OctreeSentinel, Voxel};
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn inserting_sentinel_removes() {
+        let mut sentinel = OctreeSentinel::<u8, 8>::new();
+        let mut octree = Octree::<u8, 8>::new();
+        let mut rng = thread_rng();
+        for _ in 0..500 {
+            let pos = pos!(
+                rng.gen_range(0..16),
+                rng.gen_range(0..16),
+                rng.gen_range(0..16)
+            );
+            let val = rng.gen_range(0..3);
+            sentinel.insert(pos, val);
+            if val == 0 {
+                octree.remove(pos);
+            } else {
+                octree.insert(pos, val);
+            }
+        }
+        assert_eq!(sentinel.octree().encode(), octree.encode());
+        assert_eq!(sentinel.octree().node_count(), octree.node_count());
+        assert_eq!(sentinel.octree().leaf_count(), octree.leaf_count());
+        assert!(sentinel.octree().iter().all(|(_, val)| !val.is_empty()));
+    }
+
+    #[test]
+    pub fn sentinel_collapses_regions() {
+        let mut sentinel = OctreeSentinel::<u32, 8>::new();
+        sentinel.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), 3);
+        sentinel.insert(pos!(9, 9, 9), 4);
+        assert_eq!(sentinel.get(pos!(1, 1, 1)), 3);
+        assert_eq!(sentinel.get(pos!(15, 15, 15)), 0);
+
+        sentinel.insert(pos!(9, 9, 9), 0);
+        sentinel.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), 0);
+        assert!(sentinel.octree().is_empty());
+        assert_eq!(sentinel.octree().node_count(), 1);
+        assert_eq!(sentinel.octree().leaf_count(), 0);
+
+        // converting drops stored empty values:
+        let mut octree = Octree::<u32, 8>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(15, 15, 15), 0);
+        octree.insert(pos!(2, 2, 2), 1);
+        let sentinel = OctreeSentinel::from(octree);
+        assert_eq!(sentinel.octree().len(), 1);
+        assert_eq!(sentinel.get(pos!(2, 2, 2)), 1);
+        assert_eq!(sentinel.get(pos!(3, 2, 2)), u32::EMPTY);
+    }
+}
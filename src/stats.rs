@@ -0,0 +1,318 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{
+    geom::aabbs_intersect,
+    oct_offset,
+    region::{box_coverage, Coverage},
+    Node, Octree, PosU8, EMPTY_PTR,
+};
+
+/// How the nodes of an [`Octree`] are distributed over its levels, see [`Octree::stats`].
+///
+/// Level 0 is the root, the last level holds the nodes of width 1, which are the leafs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OctreeStats {
+    /// number of nodes on each level, Mixed nodes, Full nodes and leafs.
+    pub nodes_per_level: Vec<usize>,
+    /// number of nodes without children on each level, Full nodes and leafs.
+    pub leafs_per_level: Vec<usize>,
+    /// number of Full nodes on all levels.
+    pub full_node_count: usize,
+    /// number of set voxels, like [`Octree::len`].
+    pub len: u64,
+}
+
+impl OctreeStats {
+    /// set voxels per stored node or leaf. Far above 1 if large uniform regions are merged into Full nodes,
+    /// below 1 if most voxels need a leaf of their own, where a dense array would be the better choice.
+    pub fn compress_ratio(&self) -> f64 {
+        self.len as f64 / self.nodes_per_level.iter().sum::<usize>() as f64
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// counts the nodes per level, see [`OctreeStats`].
+    pub fn stats(&self) -> OctreeStats {
+        let levels = Self::SIDE.trailing_zeros() as usize + 1;
+        let mut stats = OctreeStats {
+            nodes_per_level: vec![0; levels],
+            leafs_per_level: vec![0; levels],
+            full_node_count: 0,
+            len: 0,
+        };
+        // node_ptr, half_width, level of the node
+        let mut stack: Vec<(usize, u8, usize)> = vec![(0, HALF_WIDTH, 0)];
+        while let Some((node_ptr, half_width, level)) = stack.pop() {
+            stats.nodes_per_level[level] += 1;
+            let ptrs = match self.nodes[node_ptr] {
+                Node::Full(_) => {
+                    stats.leafs_per_level[level] += 1;
+                    stats.full_node_count += 1;
+                    stats.len += (half_width as u64 * 2).pow(3);
+                    continue;
+                }
+                Node::Mixed(ptrs, _) => ptrs,
+            };
+            for ptr in ptrs {
+                if ptr == EMPTY_PTR {
+                    continue;
+                } else if half_width == 1 {
+                    // points to leaf
+                    stats.nodes_per_level[level + 1] += 1;
+                    stats.leafs_per_level[level + 1] += 1;
+                    stats.len += 1;
+                } else {
+                    stack.push((ptr, half_width / 2, level + 1));
+                }
+            }
+        }
+        stats
+    }
+
+    /// smallest box `(min, max)` (both inclusive) containing all set voxels, `None` for an empty tree.
+    pub fn bounding_box(&self) -> Option<(PosU8, PosU8)> {
+        let mut bounds: Option<(PosU8, PosU8)> = None;
+        self.for_each(|origin, width, _| {
+            let last = (width - 1) as u8;
+            let region_max = origin + PosU8::new(last, last, last);
+            bounds = Some(match bounds {
+                Some((min, max)) => (min.min(origin), max.max(region_max)),
+                None => (origin, region_max),
+            });
+        });
+        bounds
+    }
+
+    /// number of set voxels in the box `min..=max`.
+    ///
+    /// Octants outside of the box are skipped and Full nodes add the volume of their intersection with the box
+    /// without descending, so this is much faster than `iter_box(min, max).count()` for large uniform regions.
+    ///
+    /// panics if `min` or `max` is out of bounds.
+    pub fn count_in_box(&self, min: PosU8, max: PosU8) -> u64 {
+        Self::assert_in_bounds(min);
+        Self::assert_in_bounds(max);
+        self.count_in_box_in_node(0, PosU8::ZERO, HALF_WIDTH as u16 * 2, min, max)
+    }
+
+    fn count_in_box_in_node(
+        &self,
+        node_ptr: usize,
+        origin: PosU8,
+        width: u16,
+        min: PosU8,
+        max: PosU8,
+    ) -> u64 {
+        match self.nodes[node_ptr] {
+            Node::Full(_) => box_intersection_volume(min, max, origin, width),
+            Node::Mixed(ptrs, _) => {
+                let half_width = (width / 2) as u8;
+                let mut count = 0;
+                for (i, ptr) in ptrs.into_iter().enumerate() {
+                    if ptr == EMPTY_PTR {
+                        continue;
+                    }
+                    let child_origin = origin + oct_offset(i, half_width);
+                    match box_coverage(min, max, child_origin, width / 2) {
+                        Coverage::Outside => {}
+                        // points to leaf
+                        _ if half_width == 1 => count += 1,
+                        _ => {
+                            count +=
+                                self.count_in_box_in_node(ptr, child_origin, width / 2, min, max)
+                        }
+                    }
+                }
+                count
+            }
+        }
+    }
+
+    /// true if the [`Octree::bounding_box`] of the set voxels intersects the box `min..max`, in voxel units where
+    /// the voxel at `pos` spans `pos..pos + 1`. Always false for an empty tree.
+    pub fn intersects_aabb(&self, min: [f32; 3], max: [f32; 3]) -> bool {
+        let Some((bounds_min, bounds_max)) = self.bounding_box() else {
+            return false;
+        };
+        let bounds_min = [bounds_min.x, bounds_min.y, bounds_min.z].map(|c| c as f32);
+        let bounds_max = [bounds_max.x, bounds_max.y, bounds_max.z].map(|c| c as f32 + 1.0);
+        aabbs_intersect(bounds_min, bounds_max, min, max)
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + Eq + Hash,
+{
+    /// number of voxels holding each value. This counts voxels, not nodes:
+    /// a Full node of width `w` adds `w³` to the count of its value.
+    pub fn count_values(&self) -> HashMap<V, u64> {
+        let mut counts: HashMap<V, u64> = HashMap::new();
+        self.for_each(|_, width, val| {
+            let volume = (width as u64).pow(3);
+            match counts.get_mut(val) {
+                Some(count) => *count += volume,
+                None => {
+                    counts.insert(val.clone(), volume);
+                }
+            }
+        });
+        counts
+    }
+}
+
+/// number of voxels of the cube at `origin` with side `width` that are inside of the box `min..=max`.
+fn box_intersection_volume(min: PosU8, max: PosU8, origin: PosU8, width: u16) -> u64 {
+    let cube_min = [origin.x as i64, origin.y as i64, origin.z as i64];
+    let min = [min.x as i64, min.y as i64, min.z as i64];
+    let max = [max.x as i64, max.y as i64, max.z as i64];
+    (0..3)
+        .map(|i| {
+            let cube_max = cube_min[i] + width as i64 - 1;
+            (cube_max.min(max[i]) - cube_min[i].max(min[i]) + 1).max(0) as u64
+        })
+        .product()
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::collections::HashMap;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn stats_uniform_and_checkerboard() {
+        let uniform = Octree::<u32, 8>::filled(1);
+        let stats = uniform.stats();
+        assert_eq!(stats.nodes_per_level, vec![1, 0, 0, 0, 0]);
+        assert_eq!(stats.leafs_per_level, vec![1, 0, 0, 0, 0]);
+        assert_eq!(stats.full_node_count, 1);
+        assert_eq!(stats.compress_ratio(), 4096.0);
+
+        // every other voxel set, so nothing can be merged:
+        let mut checkerboard = Octree::<u32, 8>::new();
+        for pos in PosU8::iter_box(pos!(0, 0, 0), pos!(15, 15, 15)) {
+            if (pos.x + pos.y + pos.z) % 2 == 0 {
+                checkerboard.insert(pos, 1);
+            }
+        }
+        let stats = checkerboard.stats();
+        assert_eq!(stats.nodes_per_level, vec![1, 8, 64, 512, 2048]);
+        assert_eq!(stats.leafs_per_level, vec![0, 0, 0, 0, 2048]);
+        assert_eq!(stats.full_node_count, 0);
+        assert_eq!(stats.len, checkerboard.len());
+        assert!(stats.compress_ratio() > 0.5 && stats.compress_ratio() < 1.0);
+
+        // a Full node next to single voxels:
+        let mut octree = Octree::<u32, 8>::new();
+        octree.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 2);
+        octree.insert(pos!(1, 1, 1), 3);
+        let stats = octree.stats();
+        assert_eq!(stats.nodes_per_level, vec![1, 2, 1, 1, 1]);
+        assert_eq!(stats.leafs_per_level, vec![0, 1, 0, 0, 1]);
+        assert_eq!(stats.len, 513);
+        assert_eq!(
+            stats.nodes_per_level.iter().sum::<usize>(),
+            octree.node_count() + octree.leaf_count()
+        );
+    }
+
+    #[test]
+    pub fn count_values() {
+        let mut octree = Octree::<char, 16>::new();
+        assert_eq!(octree.count_values(), HashMap::new());
+
+        octree.fill_box(pos!(0, 0, 0), pos!(15, 15, 15), 'a');
+        octree.fill_box(pos!(8, 8, 8), pos!(19, 9, 9), 'b');
+        octree.insert(pos!(31, 31, 31), 'c');
+
+        let counts = octree.count_values();
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts[&'a'], 16 * 16 * 16 - 8 * 2 * 2);
+        assert_eq!(counts[&'b'], 12 * 2 * 2);
+        assert_eq!(counts[&'c'], 1);
+        assert_eq!(counts.values().sum::<u64>(), octree.len());
+    }
+
+    #[test]
+    pub fn bounding_box() {
+        let mut octree = Octree::<u32, 16>::new();
+        assert_eq!(octree.bounding_box(), None);
+
+        octree.insert(pos!(3, 20, 5), 1);
+        assert_eq!(
+            octree.bounding_box(),
+            Some((pos!(3, 20, 5), pos!(3, 20, 5)))
+        );
+
+        octree.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 2);
+        octree.insert(pos!(1, 9, 30), 1);
+        assert_eq!(
+            octree.bounding_box(),
+            Some((pos!(1, 8, 5), pos!(15, 20, 30)))
+        );
+
+        octree.fill_box(PosU8::ZERO, pos!(31, 31, 31), 1);
+        assert_eq!(octree.bounding_box(), Some((PosU8::ZERO, pos!(31, 31, 31))));
+
+        let mut octree = Octree::<u32, 128>::new();
+        octree.fill_box(PosU8::ZERO, pos!(255, 255, 255), 1);
+        assert_eq!(
+            octree.bounding_box(),
+            Some((PosU8::ZERO, pos!(255, 255, 255)))
+        );
+    }
+
+    #[test]
+    pub fn intersects_aabb() {
+        let mut octree = Octree::<u32, 16>::new();
+        assert!(!octree.intersects_aabb([0.0; 3], [32.0; 3]));
+        octree.fill_box(pos!(4, 4, 4), pos!(7, 7, 7), 1);
+        assert!(octree.intersects_aabb([0.0; 3], [5.0; 3]));
+        assert!(octree.intersects_aabb([7.5; 3], [10.0; 3]));
+        assert!(!octree.intersects_aabb([8.5; 3], [10.0; 3]));
+        assert!(!octree.intersects_aabb([0.0, 0.0, 0.0], [3.0, 32.0, 32.0]));
+    }
+
+    #[test]
+    pub fn count_in_box_matches_brute_force() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 16>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(15, 15, 15), 1);
+        octree.fill_box(pos!(16, 0, 16), pos!(31, 7, 31), 2);
+        for _ in 0..500 {
+            let pos = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            octree.insert(pos, 3);
+        }
+
+        let boxes = [
+            // aligned:
+            (pos!(0, 0, 0), pos!(31, 31, 31)),
+            (pos!(0, 0, 0), pos!(15, 15, 15)),
+            (pos!(16, 0, 16), pos!(23, 7, 23)),
+            // unaligned:
+            (pos!(3, 1, 7), pos!(20, 9, 30)),
+            (pos!(5, 5, 5), pos!(5, 5, 5)),
+            (pos!(10, 2, 13), pos!(29, 30, 17)),
+            // empty:
+            (pos!(5, 5, 5), pos!(4, 9, 9)),
+        ];
+        for (min, max) in boxes {
+            let brute_force = octree.iter_box(min, max).count() as u64;
+            assert_eq!(octree.count_in_box(min, max), brute_force);
+        }
+        assert_eq!(
+            octree.count_in_box(pos!(0, 0, 0), pos!(31, 31, 31)),
+            octree.len()
+        );
+    }
+}
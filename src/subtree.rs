@@ -0,0 +1,380 @@
+use crate::{Node, Octree, PosU8, Ptr, EMPTY_PTR};
+
+/// The content of an octree aligned cube, see [`Octree::get_region_value`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegionValue<V> {
+    /// every voxel of the cube has this value.
+    Uniform(V),
+    /// no voxel of the cube is set.
+    Empty,
+    /// the cube holds different values, or set and empty voxels.
+    Mixed,
+    /// the cube is not aligned to the nodes of the tree.
+    Unaligned,
+}
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// whether the cube of side `width` starting at `origin` has one value everywhere, e.g. to draw it as a single
+    /// billboard. The cube has to be octree aligned, `width` a power of two no larger than the tree and `origin` a
+    /// multiple of it, otherwise [`RegionValue::Unaligned`] is returned.
+    ///
+    /// Only descends to the node covering the cube. Mixed nodes left unmerged by [`Octree::get_mut`] are reported as
+    /// [`RegionValue::Mixed`] until [`Octree::remerge_around`] is called.
+    ///
+    /// panics if `origin` is out of bounds.
+    pub fn get_region_value(&self, origin: PosU8, width: u16) -> RegionValue<V> {
+        Self::assert_in_bounds(origin);
+        if !width.is_power_of_two()
+            || width > Self::SIDE
+            || (origin.x | origin.y | origin.z) as u16 & (width - 1) != 0
+        {
+            return RegionValue::Unaligned;
+        }
+        let mut pos = origin;
+        let mut node_ptr: usize = 0;
+        let mut half_width: u8 = HALF_WIDTH;
+        loop {
+            match self.nodes[node_ptr] {
+                Node::Full(ref val) => return RegionValue::Uniform(val.clone()),
+                Node::Mixed(_, mask) if half_width as u16 * 2 == width => {
+                    return if mask == 0 {
+                        RegionValue::Empty
+                    } else {
+                        RegionValue::Mixed
+                    };
+                }
+                Node::Mixed(ptrs, _) => {
+                    let ptr = ptrs[Self::oct_index(&mut pos, half_width)];
+                    if ptr == EMPTY_PTR {
+                        return RegionValue::Empty;
+                    } else if half_width == 1 {
+                        // points to leaf
+                        return RegionValue::Uniform(self.leafs[ptr].clone());
+                    }
+                    node_ptr = ptr;
+                    half_width /= 2;
+                }
+            }
+        }
+    }
+
+    /// copies the octree aligned cube of side `2 * SMALL` starting at `origin` into a tree of its own, re-based
+    /// such that `origin` becomes the origin. Returns `None` if `origin` is not a multiple of the cube side or
+    /// the cube is larger than this tree.
+    ///
+    /// Unlike [`Octree::crop_into`], the nodes of the cube are copied one by one, without rebuilding them from regions.
+    ///
+    /// panics if `origin` is out of bounds.
+    pub fn subtree_at<const SMALL: u8>(&self, origin: PosU8) -> Option<Octree<V, SMALL>> {
+        Self::assert_in_bounds(origin);
+        let mask = (Octree::<V, SMALL>::SIDE - 1) as u8;
+        if SMALL > HALF_WIDTH || (origin.x | origin.y | origin.z) & mask != 0 {
+            return None;
+        }
+        let mut node_ptr: usize = 0;
+        let mut half_width: u8 = HALF_WIDTH;
+        while half_width > SMALL {
+            let ptrs = match self.nodes[node_ptr] {
+                Node::Full(ref val) => {
+                    return Some(Octree::filled(val.clone()));
+                }
+                Node::Mixed(ptrs, _) => ptrs,
+            };
+            let idx = Self::absolute_oct_index(origin, half_width);
+            if ptrs[idx] == EMPTY_PTR {
+                return Some(Octree::new());
+            }
+            node_ptr = ptrs[idx];
+            half_width /= 2;
+        }
+        let mut subtree = Octree::<V, SMALL>::new();
+        let copy_ptr = self.copy_node_into(node_ptr, SMALL, &mut subtree);
+        // the copy becomes the root, which has to be at ptr 0.
+        let root = subtree.nodes.remove(copy_ptr);
+        subtree.nodes[0] = root;
+        Some(subtree)
+    }
+
+    /// replaces the octree aligned cube of side `2 * SMALL` starting at `origin` with the contents of `other`,
+    /// like stamping a prefab. Voxels of the cube that are empty in `other` become empty.
+    ///
+    /// The nodes of the cube are freed as a whole and the nodes of `other` are copied in their place,
+    /// so no voxel is visited one by one.
+    ///
+    /// panics if `origin` is out of bounds, not a multiple of the cube side or the cube is larger than this tree.
+    pub fn paste_subtree<const SMALL: u8>(&mut self, origin: PosU8, other: &Octree<V, SMALL>) {
+        Self::assert_in_bounds(origin);
+        assert!(
+            SMALL <= HALF_WIDTH,
+            "octree of side {} does not fit into octree of side {}",
+            Octree::<V, SMALL>::SIDE,
+            Self::SIDE
+        );
+        let mask = (Octree::<V, SMALL>::SIDE - 1) as u8;
+        assert!(
+            (origin.x | origin.y | origin.z) & mask == 0,
+            "origin {origin:?} is not aligned to the side {}",
+            Octree::<V, SMALL>::SIDE
+        );
+        self.mark_dirty_cube(origin, Octree::<V, SMALL>::SIDE);
+        if SMALL == HALF_WIDTH {
+            self.clear();
+            let copy_ptr = other.copy_node_into(0, SMALL, self);
+            let root = self.nodes.remove(copy_ptr);
+            self.nodes[0] = root;
+            return;
+        }
+        let other_is_empty = other.nodes[0].is_empty();
+        // (node_ptr, half_width, oct_idx) of the Mixed nodes from the root down to the parent of the cube.
+        let mut path: Vec<(usize, u8, usize)> = vec![];
+        let mut node_ptr: usize = 0;
+        let mut half_width: u8 = HALF_WIDTH;
+        while half_width > SMALL {
+            if let Node::Full(_) = self.nodes[node_ptr] {
+                let full_val = self.take_full_value(node_ptr);
+                let ptrs = std::array::from_fn(|_| {
+                    self.insert_uniform_child(full_val.clone(), half_width / 2)
+                });
+                self.nodes[node_ptr] = Node::from_ptrs(ptrs);
+            }
+            let Node::Mixed(mut ptrs, _) = self.nodes[node_ptr] else {
+                unreachable!("Full nodes were split above");
+            };
+            let idx = Self::absolute_oct_index(origin, half_width);
+            path.push((node_ptr, half_width, idx));
+            if half_width / 2 == SMALL {
+                // the child is the cube itself, which is replaced below.
+                if ptrs[idx] != EMPTY_PTR {
+                    self.delete_child(ptrs[idx], half_width);
+                }
+                ptrs[idx] = if other_is_empty {
+                    EMPTY_PTR
+                } else {
+                    other.copy_node_into(0, SMALL, self)
+                };
+                self.nodes[node_ptr] = Node::from_ptrs(ptrs);
+                break;
+            }
+            if ptrs[idx] == EMPTY_PTR {
+                if other_is_empty {
+                    // nothing to clear.
+                    return;
+                }
+                ptrs[idx] = self.nodes.insert(Node::empty());
+                self.nodes[node_ptr] = Node::from_ptrs(ptrs);
+            }
+            node_ptr = ptrs[idx];
+            half_width /= 2;
+        }
+        if other_is_empty {
+            let path: Vec<(usize, usize)> = path.iter().map(|(ptr, _, idx)| (*ptr, *idx)).collect();
+            self.collapse_empty_path(&path);
+        } else {
+            self.merge_path(path.iter().map(|(ptr, hw, _)| (*ptr, *hw)).collect());
+        }
+    }
+
+    /// copies the node at `node_ptr` with `half_width` and everything below it into the slabs of `target`
+    /// and returns the pointer of the copy. The copy is not linked to any node of `target` yet.
+    pub(crate) fn copy_node_into<const W: u8>(
+        &self,
+        node_ptr: usize,
+        half_width: u8,
+        target: &mut Octree<V, W>,
+    ) -> Ptr {
+        let node = match self.nodes[node_ptr] {
+            Node::Full(ref val) => Node::Full(val.clone()),
+            Node::Mixed(ptrs, _) => Node::from_ptrs(ptrs.map(|ptr| {
+                if ptr == EMPTY_PTR {
+                    EMPTY_PTR
+                } else if half_width == 1 {
+                    // points to leaf
+                    target.leafs.insert(self.leafs[ptr].clone())
+                } else {
+                    self.copy_node_into(ptr, half_width / 2, target)
+                }
+            })),
+        };
+        target.nodes.insert(node)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use super::RegionValue;
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn get_region_value() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.fill_box(pos!(16, 0, 16), pos!(31, 15, 31), 7);
+        octree.insert(pos!(3, 3, 3), 1);
+        octree.insert(pos!(3, 3, 4), 2);
+
+        assert_eq!(
+            octree.get_region_value(pos!(16, 0, 16), 16),
+            RegionValue::Uniform(7)
+        );
+        // a cube inside of a Full node:
+        assert_eq!(
+            octree.get_region_value(pos!(20, 4, 24), 4),
+            RegionValue::Uniform(7)
+        );
+        assert_eq!(
+            octree.get_region_value(pos!(3, 3, 3), 1),
+            RegionValue::Uniform(1)
+        );
+        assert_eq!(
+            octree.get_region_value(pos!(0, 16, 0), 16),
+            RegionValue::Empty
+        );
+        assert_eq!(
+            octree.get_region_value(pos!(4, 4, 4), 1),
+            RegionValue::Empty
+        );
+        assert_eq!(
+            octree.get_region_value(pos!(2, 2, 2), 2),
+            RegionValue::Mixed
+        );
+        assert_eq!(octree.get_region_value(PosU8::ZERO, 32), RegionValue::Mixed);
+        assert_eq!(
+            Octree::<u32, 16>::new().get_region_value(PosU8::ZERO, 32),
+            RegionValue::Empty
+        );
+
+        // not aligned:
+        assert_eq!(
+            octree.get_region_value(pos!(8, 0, 0), 16),
+            RegionValue::Unaligned
+        );
+        assert_eq!(
+            octree.get_region_value(pos!(0, 0, 0), 3),
+            RegionValue::Unaligned
+        );
+        assert_eq!(
+            octree.get_region_value(pos!(0, 0, 0), 0),
+            RegionValue::Unaligned
+        );
+        assert_eq!(
+            octree.get_region_value(pos!(0, 0, 0), 64),
+            RegionValue::Unaligned
+        );
+    }
+
+    #[test]
+    pub fn subtree_at_octant() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 16>::new();
+        for _ in 0..2000 {
+            let pos = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            octree.insert(pos, rng.gen_range(0..3));
+        }
+        octree.fill_box(pos!(16, 0, 16), pos!(23, 7, 23), 7);
+
+        let octant = octree.subtree_at::<8>(pos!(16, 0, 16)).unwrap();
+        assert_eq!(
+            octant.encode(),
+            octree.crop_into::<8>(pos!(16, 0, 16)).encode()
+        );
+        for (pos, val) in octant.iter() {
+            assert_eq!(octree.get(pos + pos!(16, 0, 16)), Some(val));
+        }
+        assert_eq!(octant.get(pos!(3, 3, 3)), Some(7));
+
+        // a Full node and an empty octant:
+        assert_eq!(
+            octree.subtree_at::<2>(pos!(16, 0, 16)).unwrap().encode(),
+            Octree::<u32, 2>::filled(7).encode()
+        );
+        let mut sparse = Octree::<u32, 16>::new();
+        sparse.insert(pos!(1, 1, 1), 1);
+        assert!(sparse.subtree_at::<8>(pos!(16, 16, 16)).unwrap().is_empty());
+        assert_eq!(sparse.subtree_at::<16>(PosU8::ZERO).unwrap().len(), 1);
+
+        // not aligned or too large:
+        assert!(octree.subtree_at::<8>(pos!(8, 0, 0)).is_none());
+        assert!(octree.subtree_at::<4>(pos!(0, 3, 0)).is_none());
+        assert!(octree.subtree_at::<32>(PosU8::ZERO).is_none());
+    }
+
+    #[test]
+    pub fn paste_subtree() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 16>::new();
+        for _ in 0..2000 {
+            let pos = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            octree.insert(pos, rng.gen_range(0..3));
+        }
+        octree.fill_box(pos!(16, 16, 16), pos!(31, 31, 31), 9);
+        let mut prefab = Octree::<u32, 4>::new();
+        prefab.fill_box(pos!(0, 0, 0), pos!(7, 3, 7), 5);
+        prefab.insert(pos!(2, 6, 2), 6);
+
+        // the same result as writing the cube voxel by voxel:
+        let mut expected = octree.clone();
+        for pos in PosU8::iter_box(pos!(8, 0, 24), pos!(15, 7, 31)) {
+            match prefab.get(pos - pos!(8, 0, 24)) {
+                Some(val) => expected.insert(pos, val),
+                None => {
+                    expected.remove(pos);
+                }
+            }
+        }
+        octree.paste_subtree(pos!(8, 0, 24), &prefab);
+        assert_eq!(octree.encode(), expected.encode());
+        assert_eq!(octree.node_count(), expected.node_count());
+        assert_eq!(octree.leaf_count(), expected.leaf_count());
+        assert_eq!(
+            octree.subtree_at::<4>(pos!(8, 0, 24)).unwrap().encode(),
+            prefab.encode()
+        );
+
+        // into a Full node, the surroundings keep their value:
+        octree.paste_subtree(pos!(24, 24, 24), &prefab);
+        assert_eq!(octree.get(pos!(26, 30, 26)), Some(6));
+        assert_eq!(octree.get(pos!(25, 29, 25)), None);
+        assert_eq!(octree.get(pos!(23, 24, 24)), Some(9));
+        assert_eq!(octree.get(pos!(16, 16, 16)), Some(9));
+        // pasting the original cube back merges the Full node again:
+        let nodes_before = octree.node_count();
+        octree.paste_subtree(pos!(24, 24, 24), &Octree::<u32, 4>::filled(9));
+        assert!(octree.node_count() < nodes_before);
+        assert_eq!(
+            octree.subtree_at::<8>(pos!(16, 16, 16)).unwrap().encode(),
+            Octree::<u32, 8>::filled(9).encode()
+        );
+
+        // pasting an empty tree clears the cube and frees its nodes:
+        let mut sparse = Octree::<u32, 16>::new();
+        sparse.insert(pos!(1, 1, 1), 1);
+        sparse.paste_subtree(pos!(0, 0, 0), &Octree::<u32, 2>::new());
+        assert!(sparse.is_empty());
+        assert_eq!(sparse.node_count(), 1);
+        sparse.paste_subtree(pos!(16, 0, 0), &Octree::<u32, 2>::new());
+        assert_eq!(sparse.node_count(), 1);
+
+        // the whole tree:
+        sparse.paste_subtree(PosU8::ZERO, &octree);
+        assert_eq!(sparse.encode(), octree.encode());
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn paste_subtree_not_aligned_panics() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.paste_subtree(pos!(4, 0, 0), &Octree::<u32, 4>::filled(1));
+    }
+}
@@ -0,0 +1,156 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use crate::{Octree, PosU8};
+
+/// A straightforward model of an [`Octree`], storing every voxel in a `HashMap`, for differential tests:
+/// apply the same edits to both and compare them with [`ReferenceOctree::assert_equivalent`].
+///
+/// Positions are checked against the same bounds as in an `Octree<V, HALF_WIDTH>`.
+#[derive(Debug, Clone)]
+pub struct ReferenceOctree<V, const HALF_WIDTH: u8> {
+    voxels: HashMap<PosU8, V>,
+}
+
+impl<V, const HALF_WIDTH: u8> Default for ReferenceOctree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> ReferenceOctree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    pub fn new() -> Self {
+        ReferenceOctree {
+            voxels: HashMap::new(),
+        }
+    }
+
+    /// like [`Octree::get`].
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn get(&self, pos: PosU8) -> Option<V> {
+        self.get_ref(pos).cloned()
+    }
+
+    /// like [`Octree::get_ref`].
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn get_ref(&self, pos: PosU8) -> Option<&V> {
+        Octree::<V, HALF_WIDTH>::assert_in_bounds(pos);
+        self.voxels.get(&pos)
+    }
+
+    /// like [`Octree::contains`].
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn contains(&self, pos: PosU8) -> bool {
+        self.get_ref(pos).is_some()
+    }
+
+    /// like [`Octree::insert`].
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn insert(&mut self, pos: PosU8, val: V) {
+        Octree::<V, HALF_WIDTH>::assert_in_bounds(pos);
+        self.voxels.insert(pos, val);
+    }
+
+    /// like [`Octree::remove`].
+    ///
+    /// panics if `pos` is out of bounds.
+    pub fn remove(&mut self, pos: PosU8) -> Option<V> {
+        Octree::<V, HALF_WIDTH>::assert_in_bounds(pos);
+        self.voxels.remove(&pos)
+    }
+
+    /// like [`Octree::len`].
+    pub fn len(&self) -> u64 {
+        self.voxels.len() as u64
+    }
+
+    /// like [`Octree::is_empty`].
+    pub fn is_empty(&self) -> bool {
+        self.voxels.is_empty()
+    }
+
+    /// all set voxels in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (PosU8, &V)> + '_ {
+        self.voxels.iter().map(|(pos, val)| (*pos, val))
+    }
+}
+
+impl<V, const HALF_WIDTH: u8> ReferenceOctree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq + Debug,
+{
+    /// panics with the first difference found if `octree` does not store the same values at the same positions,
+    /// or if it is not well-formed according to [`Octree::validate`].
+    pub fn assert_equivalent(&self, octree: &Octree<V, HALF_WIDTH>) {
+        if let Err(err) = octree.validate() {
+            panic!("octree is not well-formed: {err}");
+        }
+        for (pos, val) in octree.iter() {
+            match self.voxels.get(&pos) {
+                Some(expected) if *expected == val => {}
+                expected => panic!("octree has {val:?} at {pos:?}, the reference has {expected:?}"),
+            }
+        }
+        // every voxel of the octree is in the reference, so equal lengths leave no voxel missing in the octree.
+        assert_eq!(
+            octree.len(),
+            self.len(),
+            "octree has {} voxels, the reference has {}",
+            octree.len(),
+            self.len()
+        );
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use super::ReferenceOctree;
+    use crate::{pos, Octree, PosU8};
+
+    #[test]
+    pub fn random_edits_match_reference() {
+        let mut rng = thread_rng();
+        for _ in 0..20 {
+            let mut octree = Octree::<u32, 4>::new();
+            let mut reference = ReferenceOctree::<u32, 4>::new();
+            for _ in 0..500 {
+                // a small tree and few values, such that nodes are merged and split all the time:
+                let pos = pos!(
+                    rng.gen_range(0..8),
+                    rng.gen_range(0..8),
+                    rng.gen_range(0..8)
+                );
+                if rng.gen_bool(0.3) {
+                    assert_eq!(octree.remove(pos), reference.remove(pos));
+                } else {
+                    let val = rng.gen_range(0..2);
+                    octree.insert(pos, val);
+                    reference.insert(pos, val);
+                }
+                assert_eq!(octree.get(pos), reference.get(pos));
+            }
+            reference.assert_equivalent(&octree);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn different_trees_are_not_equivalent() {
+        let mut octree = Octree::<u32, 4>::new();
+        let mut reference = ReferenceOctree::<u32, 4>::new();
+        octree.insert(pos!(1, 1, 1), 1);
+        reference.insert(pos!(1, 1, 1), 2);
+        reference.assert_equivalent(&octree);
+    }
+}
@@ -0,0 +1,484 @@
+use crate::{dense::Built, oct_offset, Node, Octree, PosU8, EMPTY_PTR};
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// returns a tree with the exact same structure, where every value is replaced by `f(value)`.
+    ///
+    /// Nodes are not merged, even if `f` maps the values of all children of a node to the same value.
+    /// Use [`Octree::map_and_merge`] for that.
+    pub fn map<W, F: Fn(&V) -> W>(&self, f: F) -> Octree<W, HALF_WIDTH>
+    where
+        W: Clone + PartialEq,
+    {
+        // keeps the slab keys, so all pointers stay valid:
+        let nodes = self.nodes.iter().map(|(ptr, node)| {
+            let node = match node {
+                Node::Full(val) => Node::Full(f(val)),
+                Node::Mixed(ptrs, mask) => Node::Mixed(*ptrs, *mask),
+            };
+            (ptr, node)
+        });
+        Octree {
+            nodes: nodes.collect(),
+            leafs: self.leafs.iter().map(|(ptr, val)| (ptr, f(val))).collect(),
+            dirty: None,
+        }
+    }
+
+    /// like [`Octree::map`], but merges nodes that became uniform because of the mapping into Full nodes.
+    pub fn map_and_merge<W, F: Fn(&V) -> W>(&self, f: F) -> Octree<W, HALF_WIDTH>
+    where
+        W: Clone + PartialEq,
+    {
+        let mut octree = self.map(f);
+        octree.merge_subtree(0, HALF_WIDTH);
+        octree
+    }
+
+    /// replaces every occurrence of `old` with `new` and returns the number of changed values
+    /// (leafs and Full nodes, not voxels).
+    ///
+    /// Nodes that became uniform because of the replacement are merged afterwards.
+    pub fn replace_all(&mut self, old: V, new: V) -> usize {
+        if old == new {
+            return 0;
+        }
+        if self.dirty.is_some() {
+            let replaced: Vec<(PosU8, u16)> = self
+                .iter_regions()
+                .filter(|(_, _, val)| *val == old)
+                .map(|(origin, width, _)| (origin, width))
+                .collect();
+            for (origin, width) in replaced {
+                self.mark_dirty_cube(origin, width);
+            }
+        }
+        let mut changed = 0;
+        let full_vals = self.nodes.iter_mut().filter_map(|(_, node)| match node {
+            Node::Full(val) => Some(val),
+            Node::Mixed(..) => None,
+        });
+        for val in self.leafs.iter_mut().map(|(_, val)| val).chain(full_vals) {
+            if *val == old {
+                *val = new.clone();
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.merge_subtree(0, HALF_WIDTH);
+        }
+        changed
+    }
+
+    /// removes every voxel for which `pred(pos, value)` returns false, like [`Vec::retain`].
+    ///
+    /// `pred` is called for every voxel, also for the ones inside of Full nodes. A Full node is rebuilt bottom-up
+    /// from the answers, so octants for which `pred` returns the same everywhere are kept or dropped as a whole
+    /// and only the others are split up.
+    pub fn retain<F: FnMut(PosU8, &V) -> bool>(&mut self, mut pred: F) {
+        self.retain_in_node(0, PosU8::ZERO, HALF_WIDTH, &mut pred);
+    }
+
+    /// applies [`Octree::retain`] to the node at `node_ptr` with `origin` and `half_width`.
+    /// Returns true if the node is empty afterwards, such that its parent can remove it.
+    fn retain_in_node<F: FnMut(PosU8, &V) -> bool>(
+        &mut self,
+        node_ptr: usize,
+        origin: PosU8,
+        half_width: u8,
+        pred: &mut F,
+    ) -> bool {
+        match self.nodes[node_ptr] {
+            Node::Full(ref val) => {
+                let val = val.clone();
+                let mut kept = |pos: PosU8| pred(pos, &val).then(|| val.clone());
+                let children = self.build_children(&mut kept, origin, half_width);
+                match self.store_built(children, half_width) {
+                    Built::Empty => {
+                        self.mark_dirty_cube(origin, half_width as u16 * 2);
+                        self.nodes[node_ptr] = Node::empty();
+                        true
+                    }
+                    // every voxel is kept.
+                    Built::Uniform(_) => false,
+                    Built::Node(ptr) => {
+                        self.mark_dirty_cube(origin, half_width as u16 * 2);
+                        self.nodes[node_ptr] = self.nodes.remove(ptr);
+                        false
+                    }
+                }
+            }
+            Node::Mixed(mut ptrs, _) => {
+                for (i, ptr) in ptrs.iter_mut().enumerate() {
+                    if *ptr == EMPTY_PTR {
+                        continue;
+                    }
+                    let child_origin = origin + oct_offset(i, half_width);
+                    if half_width == 1 {
+                        // points to leaf
+                        if !pred(child_origin, &self.leafs[*ptr]) {
+                            self.mark_dirty(child_origin);
+                            self.leafs.remove(*ptr);
+                            *ptr = EMPTY_PTR;
+                        }
+                    } else if self.retain_in_node(*ptr, child_origin, half_width / 2, pred) {
+                        self.nodes.remove(*ptr);
+                        *ptr = EMPTY_PTR;
+                    }
+                }
+                let node = Node::from_ptrs(ptrs);
+                let is_empty = node.is_empty();
+                self.nodes[node_ptr] = node;
+                is_empty
+            }
+        }
+    }
+
+    /// returns a copy of this tree with every voxel moved by `offset`. Voxels that would end up outside of the
+    /// bounds are dropped, nothing wraps around.
+    ///
+    /// Uniform regions are moved as a whole, so if `offset` is a multiple of a region's width the region stays
+    /// a single Full node.
+    pub fn translate(&self, offset: PosU8) -> Octree<V, HALF_WIDTH> {
+        let mut translated = Octree::new();
+        let last = Self::SIDE - 1;
+        for (origin, width, val) in self.iter_regions() {
+            let min = [
+                origin.x as u16 + offset.x as u16,
+                origin.y as u16 + offset.y as u16,
+                origin.z as u16 + offset.z as u16,
+            ];
+            if min.iter().any(|c| *c > last) {
+                continue;
+            }
+            let max = min.map(|c| (c + width - 1).min(last));
+            translated.fill_box(
+                PosU8::new(min[0] as u8, min[1] as u8, min[2] as u8),
+                PosU8::new(max[0] as u8, max[1] as u8, max[2] as u8),
+                val,
+            );
+        }
+        translated
+    }
+
+    /// returns a copy of this tree, where the children of every Mixed node are reordered such that child `i`
+    /// becomes child `f(i)`. Because the octant layout is the same on every level, this applies a symmetry
+    /// operation of the cube to the whole tree, without looking at a single voxel.
+    fn permute_octants(&self, f: impl Fn(usize) -> usize) -> Octree<V, HALF_WIDTH> {
+        let mut octree = self.clone();
+        // the dirty cubes are in the coordinates of this tree, so the copy starts untracked like `map` does.
+        octree.dirty = None;
+        for (_, node) in octree.nodes.iter_mut() {
+            if let Node::Mixed(ptrs, _) = *node {
+                let mut permuted = [EMPTY_PTR; 8];
+                for (i, ptr) in ptrs.iter().enumerate() {
+                    permuted[f(i)] = *ptr;
+                }
+                *node = Node::from_ptrs(permuted);
+            }
+        }
+        octree
+    }
+
+    /// returns a copy mirrored along the x axis, the voxel at `(x, y, z)` moves to `(SIDE - 1 - x, y, z)`.
+    pub fn mirror_x(&self) -> Octree<V, HALF_WIDTH> {
+        self.permute_octants(|i| i ^ 4)
+    }
+
+    /// returns a copy mirrored along the y axis, the voxel at `(x, y, z)` moves to `(x, SIDE - 1 - y, z)`.
+    pub fn mirror_y(&self) -> Octree<V, HALF_WIDTH> {
+        self.permute_octants(|i| i ^ 2)
+    }
+
+    /// returns a copy mirrored along the z axis, the voxel at `(x, y, z)` moves to `(x, y, SIDE - 1 - z)`.
+    pub fn mirror_z(&self) -> Octree<V, HALF_WIDTH> {
+        self.permute_octants(|i| i ^ 1)
+    }
+
+    /// returns a copy rotated by 90° around the y axis, the voxel at `(x, y, z)` moves to `(SIDE - 1 - z, y, x)`.
+    pub fn rotate_y_90(&self) -> Octree<V, HALF_WIDTH> {
+        // new x bit is the flipped z bit, new z bit is the x bit.
+        self.permute_octants(|i| (!i & 1) << 2 | (i & 2) | (i >> 2) & 1)
+    }
+
+    /// returns a copy rotated by 180° around the y axis, the voxel at `(x, y, z)` moves to
+    /// `(SIDE - 1 - x, y, SIDE - 1 - z)`.
+    pub fn rotate_y_180(&self) -> Octree<V, HALF_WIDTH> {
+        self.permute_octants(|i| i ^ 5)
+    }
+
+    /// returns a copy rotated by 270° around the y axis, the voxel at `(x, y, z)` moves to `(z, y, SIDE - 1 - x)`.
+    pub fn rotate_y_270(&self) -> Octree<V, HALF_WIDTH> {
+        // new x bit is the z bit, new z bit is the flipped x bit.
+        self.permute_octants(|i| (i & 1) << 2 | (i & 2) | (!i >> 2) & 1)
+    }
+
+    /// merges all uniform nodes below and including the node at `node_ptr` bottom-up.
+    pub(crate) fn merge_subtree(&mut self, node_ptr: usize, half_width: u8) {
+        let Node::Mixed(ptrs, _) = self.nodes[node_ptr] else {
+            return;
+        };
+        if half_width > 1 {
+            for ptr in ptrs {
+                if ptr != EMPTY_PTR {
+                    self.merge_subtree(ptr, half_width / 2);
+                }
+            }
+        }
+        if let Some(val) = self.uniform_child_value(&ptrs, half_width).cloned() {
+            self.delete_mixed_child_nodes(&ptrs, half_width);
+            self.nodes[node_ptr] = Node::Full(val);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use crate::{pos, Node, Octree, PosU8, RegionValue};
+
+    #[test]
+    pub fn map() {
+        let mut octree = Octree::<u32, 16>::new();
+        octree.fill_box(pos!(3, 1, 7), pos!(20, 9, 30), 1);
+        octree.insert(pos!(4, 4, 4), 2);
+        octree.insert(pos!(31, 0, 0), 3);
+
+        let mapped = octree.map(|v| format!("block {v}"));
+        assert_eq!(mapped.node_count(), octree.node_count());
+        assert_eq!(mapped.leaf_count(), octree.leaf_count());
+        assert_eq!(mapped.get(pos!(4, 4, 4)), Some("block 2".to_string()));
+        assert_eq!(mapped.get(pos!(10, 5, 10)), Some("block 1".to_string()));
+        assert_eq!(mapped.get(pos!(0, 0, 0)), None);
+        assert_eq!(mapped.len(), octree.len());
+    }
+
+    #[test]
+    pub fn map_constant_collapses() {
+        let mut octree = Octree::<u32, 16>::new();
+        for x in 0..32 {
+            for y in 0..32 {
+                for z in 0..32 {
+                    octree.insert(pos!(x, y, z), (x + y + z) as u32 % 2);
+                }
+            }
+        }
+        assert_eq!(octree.leaf_count(), 32 * 32 * 32);
+
+        // without merging the structure stays the same:
+        let mapped = octree.map(|_| 0u8);
+        assert_eq!(mapped.leaf_count(), 32 * 32 * 32);
+
+        let merged = octree.map_and_merge(|_| 0u8);
+        assert!(matches!(merged.nodes[0], Node::Full(_)));
+        assert_eq!(merged.node_count(), 1);
+        assert_eq!(merged.leaf_count(), 0);
+        assert_eq!(merged.get(PosU8::ZERO), Some(0));
+    }
+
+    #[test]
+    pub fn replace_all() {
+        let mut octree = Octree::<&'static str, 16>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), "stone");
+        octree.insert(pos!(3, 3, 3), "dirt");
+        octree.insert(pos!(20, 20, 20), "dirt");
+        octree.insert(pos!(21, 20, 20), "grass");
+        let len = octree.len();
+
+        assert_eq!(octree.replace_all("dirt", "dirt"), 0);
+        assert_eq!(octree.replace_all("air", "dirt"), 0);
+
+        // 7 Full nodes on 2 levels + 7 leafs of stone:
+        assert_eq!(octree.replace_all("stone", "dirt"), 7 * 3);
+        assert_eq!(octree.len(), len);
+        assert_eq!(octree.get(pos!(0, 0, 0)), Some("dirt"));
+        assert_eq!(octree.get(pos!(21, 20, 20)), Some("grass"));
+
+        // the 8x8x8 cube is uniform again:
+        let mut expected = Octree::<&'static str, 16>::new();
+        expected.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), "dirt");
+        expected.insert(pos!(20, 20, 20), "dirt");
+        expected.insert(pos!(21, 20, 20), "grass");
+        assert_eq!(octree.encode(), expected.encode());
+        assert_eq!(octree.leaf_count(), 2);
+    }
+
+    #[test]
+    pub fn translate() {
+        let mut octree = Octree::<u32, 8>::new();
+        octree.fill_box(pos!(2, 2, 2), pos!(5, 5, 5), 1);
+        octree.insert(pos!(0, 0, 0), 2);
+
+        let translated = octree.translate(pos!(10, 1, 0));
+        assert_eq!(translated.len(), 64 + 1);
+        assert_eq!(translated.get(pos!(10, 1, 0)), Some(2));
+        assert_eq!(translated.get(pos!(12, 3, 2)), Some(1));
+        assert_eq!(translated.get(pos!(15, 6, 5)), Some(1));
+        assert_eq!(translated.get(pos!(2, 2, 2)), None);
+
+        // the part of the cube that is moved across the border is dropped:
+        let translated = octree.translate(pos!(12, 0, 0));
+        assert_eq!(translated.len(), 2 * 4 * 4 + 1);
+        assert_eq!(translated.get(pos!(15, 5, 5)), Some(1));
+        assert_eq!(translated.get(pos!(14, 2, 2)), Some(1));
+        assert_eq!(translated.get(pos!(13, 2, 2)), None);
+        assert_eq!(translated.get(pos!(12, 0, 0)), Some(2));
+
+        assert!(octree.translate(pos!(16, 0, 0)).is_empty());
+        assert!(octree.translate(PosU8::ZERO) == octree);
+
+        // aligned moves keep Full nodes:
+        let mut octree = Octree::<u32, 8>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(3, 3, 3), 1);
+        let translated = octree.translate(pos!(4, 8, 12));
+        assert_eq!(translated.leaf_count(), 0);
+        assert_eq!(translated.get(pos!(7, 11, 15)), Some(1));
+    }
+
+    #[test]
+    pub fn mirror() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 8>::new();
+        for _ in 0..300 {
+            let pos = pos!(
+                rng.gen_range(0..16),
+                rng.gen_range(0..16),
+                rng.gen_range(0..16)
+            );
+            octree.insert(pos, rng.gen_range(0..3));
+        }
+        octree.fill_box(pos!(0, 8, 8), pos!(7, 15, 15), 4);
+
+        let mirrored = octree.mirror_x();
+        for (pos, val) in octree.iter() {
+            assert_eq!(mirrored.get(pos!(15 - pos.x, pos.y, pos.z)), Some(val));
+        }
+        assert_eq!(mirrored.len(), octree.len());
+        let mirrored = octree.mirror_y();
+        assert_eq!(mirrored.get(pos!(3, 0, 12)), Some(4));
+        let mirrored = octree.mirror_z();
+        assert_eq!(mirrored.get(pos!(3, 12, 0)), Some(4));
+
+        assert!(octree.mirror_x().mirror_x() == octree);
+        assert!(octree.mirror_y().mirror_y() == octree);
+        assert!(octree.mirror_z().mirror_z() == octree);
+    }
+
+    #[test]
+    pub fn mirror_and_rotate_drop_dirty_regions() {
+        let mut octree = Octree::<u32, 8>::new();
+        octree.enable_dirty_tracking();
+        octree.insert(pos!(1, 2, 3), 1);
+        for mut copy in [octree.mirror_x(), octree.mirror_z(), octree.rotate_y_90()] {
+            assert_eq!(copy.take_dirty_regions(), vec![]);
+            copy.insert(pos!(0, 0, 0), 2);
+            assert_eq!(copy.take_dirty_regions(), vec![]);
+        }
+        assert_eq!(octree.take_dirty_regions(), vec![(pos!(1, 2, 3), 1)]);
+    }
+
+    #[test]
+    pub fn rotate() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 8>::new();
+        for _ in 0..300 {
+            let pos = pos!(
+                rng.gen_range(0..16),
+                rng.gen_range(0..16),
+                rng.gen_range(0..16)
+            );
+            octree.insert(pos, rng.gen_range(0..3));
+        }
+        octree.fill_box(pos!(0, 0, 8), pos!(7, 7, 15), 4);
+
+        let rotated = octree.rotate_y_90();
+        for (pos, val) in octree.iter() {
+            assert_eq!(rotated.get(pos!(15 - pos.z, pos.y, pos.x)), Some(val));
+        }
+        assert_eq!(rotated.len(), octree.len());
+
+        let four_times = octree
+            .rotate_y_90()
+            .rotate_y_90()
+            .rotate_y_90()
+            .rotate_y_90();
+        assert!(four_times == octree);
+        assert!(octree.rotate_y_90().rotate_y_90() == octree.rotate_y_180());
+        assert!(octree.rotate_y_180().rotate_y_90() == octree.rotate_y_270());
+        assert!(octree.rotate_y_270().rotate_y_90() == octree);
+    }
+
+    #[test]
+    pub fn retain() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 8>::new();
+        for _ in 0..400 {
+            let pos = pos!(
+                rng.gen_range(0..16),
+                rng.gen_range(0..16),
+                rng.gen_range(0..16)
+            );
+            octree.insert(pos, rng.gen_range(0..3));
+        }
+        octree.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 7);
+        let before = octree.clone();
+
+        octree.retain(|pos, _| pos.x % 2 == 0);
+        for x in 0..16 {
+            for y in 0..16 {
+                for z in 0..16 {
+                    let pos = pos!(x, y, z);
+                    let expected = if x % 2 == 0 { before.get(pos) } else { None };
+                    assert_eq!(octree.get(pos), expected);
+                }
+            }
+        }
+        let mut expected = before.clone();
+        for (pos, _) in before.iter().filter(|(pos, _)| pos.x % 2 == 1) {
+            expected.remove(pos);
+        }
+        assert_eq!(octree.encode(), expected.encode());
+    }
+
+    #[test]
+    pub fn retain_whole_regions() {
+        let mut octree = Octree::<u32, 8>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), 1);
+        octree.fill_box(pos!(8, 8, 8), pos!(15, 15, 15), 2);
+        octree.retain(|_, val| *val == 2);
+        assert_eq!(octree.len(), 512);
+        assert_eq!(octree.leaf_count(), 0);
+        assert_eq!(octree.node_count(), 2);
+
+        octree.retain(|_, _| false);
+        assert!(octree.is_empty());
+        assert_eq!(octree.node_count(), 1);
+    }
+
+    #[test]
+    pub fn retain_partially_in_full_root() {
+        let mut octree = Octree::<u8, 64>::filled(1);
+        octree.retain(|pos, _| pos.x % 2 == 0 || pos.y >= 64);
+        let expected =
+            Octree::<u8, 64>::from_fn(|pos| (pos.x % 2 == 0 || pos.y >= 64).then_some(1));
+        assert_eq!(octree.encode(), expected.encode());
+        assert_eq!(octree.node_count(), expected.node_count());
+        assert_eq!(octree.leaf_count(), expected.leaf_count());
+        // the upper half is kept as whole octants:
+        assert_eq!(
+            octree.get_region_value(pos!(0, 64, 0), 64),
+            RegionValue::Uniform(1)
+        );
+
+        // an octant for which pred is false everywhere is dropped as a whole:
+        let mut octree = Octree::<u8, 64>::filled(1);
+        octree.retain(|pos, _| pos.z < 64);
+        let mut expected = Octree::<u8, 64>::new();
+        expected.fill_box(pos!(0, 0, 0), pos!(127, 127, 63), 1);
+        assert_eq!(octree.encode(), expected.encode());
+        assert_eq!(octree.node_count(), 5);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{Node, Octree, Ptr, EMPTY_PTR};
+
+impl<V, const HALF_WIDTH: u8> Octree<V, HALF_WIDTH>
+where
+    V: Clone + PartialEq,
+{
+    /// checks that the tree is well-formed, e.g. in a fuzz harness or while hunting a corruption bug.
+    /// Returns a description of the first violation found.
+    ///
+    /// Checked are that every pointer points to a live slab entry that no other pointer points to,
+    /// that every slab entry is reachable from the root, that the presence masks match the pointers,
+    /// that no Mixed node except the root is empty and that no Mixed node has 8 equal children that should
+    /// have been merged into a Full node. The latter is also reported after [`Octree::get_mut`]
+    /// until [`Octree::remerge_around`] is called.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.nodes.contains(0) {
+            return Err("the root node at ptr 0 does not exist".to_string());
+        }
+        let mut seen_nodes = vec![false; self.nodes.capacity()];
+        let mut seen_leafs = vec![false; self.leafs.capacity()];
+        self.validate_node(0, HALF_WIDTH, &mut seen_nodes, &mut seen_leafs)?;
+        if let Some((ptr, _)) = self.nodes.iter().find(|(ptr, _)| !seen_nodes[*ptr]) {
+            return Err(format!("node {ptr} is not reachable from the root"));
+        }
+        if let Some((ptr, _)) = self.leafs.iter().find(|(ptr, _)| !seen_leafs[*ptr]) {
+            return Err(format!("leaf {ptr} is not reachable from the root"));
+        }
+        Ok(())
+    }
+
+    fn validate_node(
+        &self,
+        node_ptr: Ptr,
+        half_width: u8,
+        seen_nodes: &mut [bool],
+        seen_leafs: &mut [bool],
+    ) -> Result<(), String> {
+        if seen_nodes[node_ptr] {
+            return Err(format!(
+                "node {node_ptr} is the child of more than one node"
+            ));
+        }
+        seen_nodes[node_ptr] = true;
+        let Node::Mixed(ptrs, mask) = self.nodes[node_ptr] else {
+            return Ok(());
+        };
+        let expected_mask = (0..8)
+            .filter(|i| ptrs[*i] != EMPTY_PTR)
+            .fold(0u8, |mask, i| mask | 1 << i);
+        if mask != expected_mask {
+            return Err(format!(
+                "presence mask {mask:#010b} of node {node_ptr} does not match its children"
+            ));
+        }
+        if node_ptr != 0 && mask == 0 {
+            return Err(format!(
+                "node {node_ptr} with half width {half_width} is empty and should have been removed"
+            ));
+        }
+        for ptr in ptrs {
+            if ptr == EMPTY_PTR {
+                continue;
+            } else if half_width == 1 {
+                // points to leaf
+                if !self.leafs.contains(ptr) {
+                    return Err(format!(
+                        "node {node_ptr} points to leaf {ptr}, which does not exist"
+                    ));
+                }
+                if seen_leafs[ptr] {
+                    return Err(format!("leaf {ptr} is the child of more than one node"));
+                }
+                seen_leafs[ptr] = true;
+            } else {
+                if !self.nodes.contains(ptr) {
+                    return Err(format!(
+                        "node {node_ptr} points to node {ptr}, which does not exist"
+                    ));
+                }
+                self.validate_node(ptr, half_width / 2, seen_nodes, seen_leafs)?;
+            }
+        }
+        if self.uniform_child_value(&ptrs, half_width).is_some() {
+            return Err(format!(
+                "the 8 children of node {node_ptr} with half width {half_width} have the same value \
+                 and should have been merged into a Full node"
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use rand::{thread_rng, Rng};
+
+    use crate::{pos, Node, Octree, PosU8, EMPTY_PTR};
+
+    #[test]
+    pub fn valid_trees_validate() {
+        let mut rng = thread_rng();
+        let mut octree = Octree::<u32, 16>::new();
+        assert_eq!(octree.validate(), Ok(()));
+        for _ in 0..3000 {
+            let pos = pos!(
+                rng.gen_range(0..32),
+                rng.gen_range(0..32),
+                rng.gen_range(0..32)
+            );
+            if rng.gen_bool(0.2) {
+                octree.remove(pos);
+            } else {
+                octree.insert(pos, rng.gen_range(0..2));
+            }
+        }
+        octree.fill_box(pos!(0, 0, 0), pos!(15, 15, 15), 3);
+        octree.clear_box(pos!(4, 4, 4), pos!(9, 9, 9));
+        assert_eq!(octree.validate(), Ok(()));
+        assert_eq!(Octree::<u32, 16>::filled(1).validate(), Ok(()));
+    }
+
+    #[test]
+    pub fn corrupted_trees_are_invalid() {
+        // 8 equal Full children that were not merged:
+        let mut octree = Octree::<u32, 8>::new();
+        let ptrs = std::array::from_fn(|_| octree.nodes.insert(Node::Full(1)));
+        octree.nodes[0] = Node::from_ptrs(ptrs);
+        assert!(octree.validate().unwrap_err().contains("merged"));
+
+        // get_mut does not merge until remerge_around:
+        let mut octree = Octree::<u32, 8>::new();
+        octree.fill_box(pos!(0, 0, 0), pos!(7, 7, 7), 1);
+        *octree.get_mut(pos!(1, 1, 1)).unwrap() = 1;
+        assert!(octree.validate().is_err());
+        octree.remerge_around(pos!(1, 1, 1));
+        assert_eq!(octree.validate(), Ok(()));
+
+        // an empty Mixed node that was not collapsed:
+        let mut octree = Octree::<u32, 8>::new();
+        octree.insert(pos!(1, 1, 1), 1);
+        let empty = octree.nodes.insert(Node::empty());
+        let Node::Mixed(mut ptrs, _) = octree.nodes[0] else {
+            unreachable!();
+        };
+        ptrs[7] = empty;
+        octree.nodes[0] = Node::from_ptrs(ptrs);
+        assert!(octree.validate().unwrap_err().contains("empty"));
+
+        // a dangling leaf pointer:
+        let mut octree = Octree::<u32, 8>::new();
+        octree.insert(pos!(1, 1, 1), 1);
+        let leaf_ptr = octree.leafs.iter().next().unwrap().0;
+        octree.leafs.remove(leaf_ptr);
+        assert!(octree.validate().unwrap_err().contains("does not exist"));
+
+        // a stale presence mask:
+        let mut octree = Octree::<u32, 8>::new();
+        octree.insert(pos!(1, 1, 1), 1);
+        let Node::Mixed(ptrs, mask) = octree.nodes[0] else {
+            unreachable!();
+        };
+        assert_ne!(ptrs[0], EMPTY_PTR);
+        octree.nodes[0] = Node::Mixed(ptrs, mask | 0b1000_0000);
+        assert!(octree.validate().unwrap_err().contains("mask"));
+
+        // an unreachable node:
+        let mut octree = Octree::<u32, 8>::new();
+        octree.nodes.insert(Node::Full(1));
+        assert!(octree.validate().unwrap_err().contains("not reachable"));
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use crate::{Octree, PosU8};
+
+/// A position in an [`OctreeU16`], like [`PosU8`] but with `u16` components.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct PosU16 {
+    pub x: u16,
+    pub y: u16,
+    pub z: u16,
+}
+
+impl PosU16 {
+    pub fn new(x: u16, y: u16, z: u16) -> Self {
+        PosU16 { x, y, z }
+    }
+
+    /// position of the chunk containing this position.
+    fn chunk(self) -> PosU8 {
+        PosU8::new(
+            (self.x >> 8) as u8,
+            (self.y >> 8) as u8,
+            (self.z >> 8) as u8,
+        )
+    }
+
+    /// position inside of its chunk.
+    fn local(self) -> PosU8 {
+        PosU8::new(self.x as u8, self.y as u8, self.z as u8)
+    }
+
+    fn from_chunk_and_local(chunk: PosU8, local: PosU8) -> Self {
+        PosU16 {
+            x: (chunk.x as u16) << 8 | local.x as u16,
+            y: (chunk.y as u16) << 8 | local.y as u16,
+            z: (chunk.z as u16) << 8 | local.z as u16,
+        }
+    }
+}
+
+impl From<PosU8> for PosU16 {
+    fn from(pos: PosU8) -> Self {
+        PosU16::new(pos.x as u16, pos.y as u16, pos.z as u16)
+    }
+}
+
+impl From<[u16; 3]> for PosU16 {
+    fn from([x, y, z]: [u16; 3]) -> Self {
+        PosU16 { x, y, z }
+    }
+}
+
+/// A sparse voxel tree spanning the whole `u16` coordinate range (65536³), for worlds larger than the 256³
+/// an [`Octree`] can cover.
+///
+/// The `u8` based [`Octree`] can not simply get a bigger `HALF_WIDTH`, so the space is split into chunks of
+/// 256³, each of which is an `Octree<V, 128>`. The top 8 bits of a coordinate select the chunk, the low 8 bits
+/// the voxel inside of it. Chunks are only allocated once a voxel is inserted into them and dropped again
+/// when they become empty.
+#[derive(Debug, Clone)]
+pub struct OctreeU16<V> {
+    chunks: HashMap<PosU8, Octree<V, 128>>,
+}
+
+impl<V> OctreeU16<V>
+where
+    V: Clone + PartialEq,
+{
+    pub fn new() -> Self {
+        OctreeU16 {
+            chunks: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, pos: PosU16, val: V) {
+        self.chunks
+            .entry(pos.chunk())
+            .or_default()
+            .insert(pos.local(), val);
+    }
+
+    pub fn get(&self, pos: PosU16) -> Option<V> {
+        self.get_ref(pos).cloned()
+    }
+
+    pub fn get_ref(&self, pos: PosU16) -> Option<&V> {
+        self.chunks.get(&pos.chunk())?.get_ref(pos.local())
+    }
+
+    pub fn contains(&self, pos: PosU16) -> bool {
+        self.get_ref(pos).is_some()
+    }
+
+    /// removes the voxel at `pos` and returns its value, a chunk that becomes empty is freed.
+    pub fn remove(&mut self, pos: PosU16) -> Option<V> {
+        let chunk_pos = pos.chunk();
+        let chunk = self.chunks.get_mut(&chunk_pos)?;
+        let removed = chunk.remove(pos.local());
+        if chunk.is_empty() {
+            self.chunks.remove(&chunk_pos);
+        }
+        removed
+    }
+
+    /// number of set voxels.
+    pub fn len(&self) -> u64 {
+        self.chunks.values().map(|chunk| chunk.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// number of allocated chunks of 256³ voxels.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// all set voxels, chunk by chunk in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (PosU16, V)> + '_ {
+        self.chunks.iter().flat_map(|(chunk_pos, chunk)| {
+            chunk
+                .iter()
+                .map(|(local, val)| (PosU16::from_chunk_and_local(*chunk_pos, local), val))
+        })
+    }
+}
+
+impl<V> Default for OctreeU16<V>
+where
+    V: Clone + PartialEq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::collections::HashMap;
+
+    use rand::{thread_rng, Rng};
+
+    use super::{OctreeU16, PosU16};
+
+    #[test]
+    pub fn sparse_1024_cube() {
+        let mut rng = thread_rng();
+        let mut octree = OctreeU16::<u32>::new();
+        let mut expected = HashMap::new();
+        for _ in 0..2000 {
+            let pos = PosU16::new(
+                rng.gen_range(0..1024),
+                rng.gen_range(0..1024),
+                rng.gen_range(0..1024),
+            );
+            let val = rng.gen_range(0..10);
+            octree.insert(pos, val);
+            expected.insert(pos, val);
+        }
+        assert_eq!(octree.len(), expected.len() as u64);
+        assert!(octree.chunk_count() <= 64);
+        for (pos, val) in expected.iter() {
+            assert_eq!(octree.get(*pos), Some(*val));
+        }
+        let collected: HashMap<PosU16, u32> = octree.iter().collect();
+        assert_eq!(collected, expected);
+
+        for pos in expected.keys() {
+            assert_eq!(octree.remove(*pos), expected.get(pos).copied());
+        }
+        assert!(octree.is_empty());
+        assert_eq!(octree.chunk_count(), 0);
+    }
+
+    #[test]
+    pub fn chunk_borders() {
+        let mut octree = OctreeU16::<u32>::new();
+        let positions = [
+            PosU16::new(255, 255, 255),
+            PosU16::new(256, 255, 255),
+            PosU16::new(511, 512, 0),
+            PosU16::new(1023, 1023, 1023),
+            PosU16::new(u16::MAX, u16::MAX, u16::MAX),
+        ];
+        for (i, pos) in positions.iter().enumerate() {
+            octree.insert(*pos, i as u32);
+        }
+        assert_eq!(octree.chunk_count(), 5);
+        for (i, pos) in positions.iter().enumerate() {
+            assert_eq!(octree.get(*pos), Some(i as u32));
+        }
+        assert!(!octree.contains(PosU16::new(257, 255, 255)));
+        assert_eq!(octree.remove(PosU16::new(0, 0, 0)), None);
+    }
+}